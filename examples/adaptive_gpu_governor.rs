@@ -1,9 +1,12 @@
-use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
+use cyan_skillfish_governor::config::GovernorConfig;
+use cyan_skillfish_governor::discovery::{DEFAULT_SYSFS_ROOT, find_cyan_skillfish};
+use cyan_skillfish_governor::policy::{Decision, FrequencyPolicy, LoadSample, PolicyContext};
+use libdrm_amdgpu_sys::AMDGPU::DeviceHandle;
 use std::collections::{BTreeMap, VecDeque};
 use std::fs::{File, OpenOptions};
 use std::io::{Error as IoError, Read, Write};
 use std::os::fd::AsRawFd;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 const GRBM_STATUS_REG: u32 = 0x2004;
@@ -266,7 +269,20 @@ enum GovernorMode {
     Adjusting, // Ajustement temporaire si confort dégradé
 }
 
-/// Gouverneur adaptatif avec modes
+impl GovernorMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Learning => "learning",
+            Self::Locked => "locked",
+            Self::Adjusting => "adjusting",
+        }
+    }
+}
+
+/// Gouverneur adaptatif avec modes, piloté comme les politiques de la bibliothèque
+///
+/// Apprend la fréquence la plus confortable pour toutes les charges confondues
+/// (`StatsCollector`), s'y verrouille et réajuste si le confort se dégrade.
 struct SimpleGovernor {
     current_freq: u16,
     optimal_freq: Option<u16>,
@@ -278,11 +294,37 @@ struct SimpleGovernor {
     load_history: VecDeque<f32>,
     history_size: usize,
     discomfort_count: u32,
+    stats: StatsCollector,
 }
 
 impl SimpleGovernor {
-    fn new(starting_freq: u16, mode: GovernorMode) -> Self {
-        Self {
+    /// Démarre verrouillé si `stats` désigne déjà une fréquence confortable
+    fn new(stats: StatsCollector) -> Self {
+        let (mode, starting_freq) = match stats.get_optimal_frequency() {
+            Some((optimal_freq, score))
+                if stats.has_sufficient_data() && score >= MIN_COMFORT_SCORE =>
+            {
+                println!(
+                    "🔒 Mode LOCKED: Fréquence optimale détectée: {} MHz (confort: {:.1}/100)\n",
+                    optimal_freq, score
+                );
+                (GovernorMode::Locked, optimal_freq)
+            }
+            optimal => {
+                if optimal.is_some() {
+                    println!("📚 Mode LEARNING: Données insuffisantes ou confort trop faible\n");
+                } else {
+                    println!("📚 Mode LEARNING: Première exécution\n");
+                }
+                println!(
+                    "   Phase d'apprentissage: {} secondes ({} minutes)\n",
+                    LEARNING_DURATION_SECS,
+                    LEARNING_DURATION_SECS / 60
+                );
+                (GovernorMode::Learning, MIN_FREQ_MHZ)
+            }
+        };
+        let mut governor = Self {
             current_freq: starting_freq,
             optimal_freq: None,
             mode,
@@ -293,11 +335,13 @@ impl SimpleGovernor {
             load_history: VecDeque::with_capacity(20),
             history_size: 10,
             discomfort_count: 0,
+            stats,
+        };
+        if mode == GovernorMode::Locked {
+            governor.optimal_freq = Some(starting_freq);
         }
-    }
-
-    fn set_optimal_freq(&mut self, freq: u16) {
-        self.optimal_freq = Some(freq);
+        governor.stats.set_frequency(starting_freq, 0.0);
+        governor
     }
 
     fn switch_to_locked(&mut self, optimal_freq: u16) {
@@ -368,70 +412,123 @@ impl SimpleGovernor {
         self.discomfort_count < 3
     }
 
-    fn try_adjust(&mut self, stats: &StatsCollector) -> Option<u16> {
-        match self.mode {
-            GovernorMode::Learning => {
-                // Mode apprentissage: comportement normal
-                if self.last_change.elapsed() < self.min_change_interval {
-                    return None;
-                }
+    /// Palier suivant en mode Learning / Adjusting (Locked reste sur sa fréquence)
+    fn try_adjust(&mut self) -> Option<u16> {
+        if self.mode == GovernorMode::Locked
+            || self.last_change.elapsed() < self.min_change_interval
+        {
+            return None;
+        }
+
+        let new_freq = if self.should_increase() {
+            (self.current_freq + FREQ_STEP_MHZ).min(MAX_FREQ_MHZ)
+        } else if self.should_decrease() {
+            self.current_freq
+                .saturating_sub(FREQ_STEP_MHZ)
+                .max(MIN_FREQ_MHZ)
+        } else {
+            return None;
+        };
+
+        if new_freq == self.current_freq {
+            return None;
+        }
+        let direction = if new_freq > self.current_freq {
+            "↑"
+        } else {
+            "↓"
+        };
+        println!(
+            "\n[{}] {} MHz {} {} MHz",
+            self.mode.as_str().to_uppercase(),
+            self.current_freq,
+            direction,
+            new_freq
+        );
+        self.current_freq = new_freq;
+        self.last_change = Instant::now();
+        self.load_history.clear();
+        Some(new_freq)
+    }
 
-                let new_freq = if self.should_increase() {
-                    (self.current_freq + FREQ_STEP_MHZ).min(MAX_FREQ_MHZ)
-                } else if self.should_decrease() {
-                    self.current_freq
-                        .saturating_sub(FREQ_STEP_MHZ)
-                        .max(MIN_FREQ_MHZ)
+    fn stats(&self) -> &StatsCollector {
+        &self.stats
+    }
+}
+
+impl FrequencyPolicy for SimpleGovernor {
+    fn name(&self) -> &'static str {
+        "adaptive"
+    }
+
+    fn decide(&mut self, sample: &LoadSample, _current_freq: u16, ctx: &PolicyContext) -> Decision {
+        let load = sample.load_percent;
+        self.add_load_sample(load);
+        self.stats.add_load_sample(load);
+        let previous = self.current_freq;
+
+        // Gestion des transitions de mode
+        let reason = match self.mode {
+            GovernorMode::Learning => {
+                // Fin de la phase d'apprentissage?
+                if self.mode_start.elapsed() >= Duration::from_secs(LEARNING_DURATION_SECS)
+                    && self.stats.has_sufficient_data()
+                    && let Some((optimal_freq, _)) = self.stats.get_optimal_frequency()
+                {
+                    println!("\n\n🎯 Phase d'apprentissage terminée!");
+                    self.stats.print_summary();
+                    println!("\n🔒 Passage en mode LOCKED à {} MHz\n", optimal_freq);
+                    self.switch_to_locked(optimal_freq);
+                    Some("learning finished")
                 } else {
-                    return None;
-                };
-
-                if new_freq != self.current_freq {
-                    self.current_freq = new_freq;
-                    self.last_change = Instant::now();
-                    self.load_history.clear();
-                    return Some(new_freq);
+                    self.try_adjust().map(|_| "learning")
                 }
             }
             GovernorMode::Locked => {
-                // Mode locked: rester à la fréquence optimale
-                // Sauf si on détecte un inconfort
-                if let Some(optimal) = self.optimal_freq {
-                    if self.current_freq != optimal {
-                        self.current_freq = optimal;
-                        return Some(optimal);
-                    }
+                if !self.check_comfort(load) {
+                    println!("\n⚠ Confort dégradé en mode LOCKED, passage en mode ADJUSTING\n");
+                    self.switch_to_adjusting();
+                }
+                // Réévaluation périodique?
+                if self.mode_start.elapsed() >= Duration::from_secs(REEVALUATION_INTERVAL_SECS) {
+                    println!("\n🔄 Réévaluation périodique, passage en mode LEARNING\n");
+                    self.switch_to_learning();
                 }
+                None
             }
             GovernorMode::Adjusting => {
-                // Mode ajustement: comme Learning mais peut retourner en Locked
-                if self.last_change.elapsed() < self.min_change_interval {
-                    return None;
-                }
-
-                let new_freq = if self.should_increase() {
-                    (self.current_freq + FREQ_STEP_MHZ).min(MAX_FREQ_MHZ)
-                } else if self.should_decrease() {
-                    self.current_freq
-                        .saturating_sub(FREQ_STEP_MHZ)
-                        .max(MIN_FREQ_MHZ)
+                let adjusted = self.try_adjust().map(|_| "adjusting");
+                // Retour en locked si confort revenu?
+                if self.check_comfort(load)
+                    && self.mode_start.elapsed() >= Duration::from_secs(30)
+                    && let Some((optimal_freq, _)) = self.stats.get_optimal_frequency()
+                {
+                    println!(
+                        "\n✓ Confort restauré, retour en mode LOCKED à {} MHz\n",
+                        optimal_freq
+                    );
+                    self.switch_to_locked(optimal_freq);
+                    Some("comfort restored")
                 } else {
-                    return None;
-                };
-
-                if new_freq != self.current_freq {
-                    self.current_freq = new_freq;
-                    self.last_change = Instant::now();
-                    self.load_history.clear();
-                    return Some(new_freq);
+                    adjusted
                 }
             }
+        };
+        if self.current_freq != previous {
+            self.stats.set_frequency(self.current_freq, load);
+        }
+
+        Decision {
+            freq: self.current_freq.clamp(ctx.min_freq, ctx.max_freq),
+            mode: self.mode.as_str(),
+            reason,
+            immediate: true,
         }
-        None
     }
 
-    fn current_freq(&self) -> u16 {
-        self.current_freq
+    fn reset(&mut self, freq: u16) {
+        self.current_freq = freq;
+        self.load_history.clear();
     }
 }
 
@@ -469,12 +566,7 @@ fn get_stats_path() -> PathBuf {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Governor GPU Adaptatif Permanent ===\n");
 
-    let location = BUS_INFO {
-        domain: 0,
-        bus: 1,
-        dev: 0,
-        func: 0,
-    };
+    let location = find_cyan_skillfish(Path::new(DEFAULT_SYSFS_ROOT), None)?;
 
     let card = File::open(location.get_drm_render_path()?)?;
     let (dev_handle, _, _) = DeviceHandle::init(card.as_raw_fd())
@@ -490,7 +582,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Charger les stats existantes ou créer nouvelles
     let stats_path = get_stats_path();
-    let mut stats = if stats_path.exists() {
+    let stats = if stats_path.exists() {
         println!("📊 Chargement des statistiques existantes...");
         match StatsCollector::load_from_file(&stats_path) {
             Ok(s) => {
@@ -516,49 +608,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         StatsCollector::new()
     };
 
-    // Déterminer mode de démarrage
-    let (mode, starting_freq) = if let Some((optimal_freq, score)) = stats.get_optimal_frequency() {
-        if stats.has_sufficient_data() && score >= MIN_COMFORT_SCORE {
-            println!(
-                "🔒 Mode LOCKED: Fréquence optimale détectée: {} MHz (confort: {:.1}/100)\n",
-                optimal_freq, score
-            );
-            (GovernorMode::Locked, optimal_freq)
-        } else {
-            println!("📚 Mode LEARNING: Données insuffisantes ou confort trop faible\n");
-            println!(
-                "   Phase d'apprentissage: {} secondes ({} minutes)\n",
-                LEARNING_DURATION_SECS,
-                LEARNING_DURATION_SECS / 60
-            );
-            (GovernorMode::Learning, MIN_FREQ_MHZ)
-        }
-    } else {
-        println!("📚 Mode LEARNING: Première exécution\n");
-        println!(
-            "   Phase d'apprentissage: {} secondes ({} minutes)\n",
-            LEARNING_DURATION_SECS,
-            LEARNING_DURATION_SECS / 60
-        );
-        (GovernorMode::Learning, MIN_FREQ_MHZ)
-    };
-
+    // Seuls les champs de `PolicyContext` servent: les seuils sont ceux de l'exemple
+    let config = GovernorConfig::parse("")?;
     let mut load_monitor = GpuLoadMonitor::new(SAMPLE_WINDOW_SIZE);
-    let mut governor = SimpleGovernor::new(starting_freq, mode);
-
-    // Si mode locked, définir la fréquence optimale
-    if matches!(mode, GovernorMode::Locked) {
-        if let Some((optimal, _)) = stats.get_optimal_frequency() {
-            governor.set_optimal_freq(optimal);
-        }
-    }
+    let mut governor = SimpleGovernor::new(stats);
+    let mut current_freq = governor.current_freq;
+    let mut last_change = Instant::now();
 
     let mut sample_count = 0u64;
     let mut last_display = Instant::now();
     let mut last_save = Instant::now();
 
-    set_gpu_frequency(&mut pp_file, starting_freq)?;
-    stats.set_frequency(starting_freq, 0.0);
+    set_gpu_frequency(&mut pp_file, current_freq)?;
 
     println!("🚀 Monitoring démarré... (Ctrl+C pour arrêter)\n");
 
@@ -572,116 +633,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         sample_count += 1;
 
         if load_monitor.is_full() {
-            let load = load_monitor.load_percent();
-            governor.add_load_sample(load);
-            stats.add_load_sample(load);
-
-            // Gestion des transitions de mode
-            match governor.mode {
-                GovernorMode::Learning => {
-                    // Fin de la phase d'apprentissage?
-                    if governor.mode_start.elapsed() >= Duration::from_secs(LEARNING_DURATION_SECS)
-                        && stats.has_sufficient_data()
-                    {
-                        if let Some((optimal_freq, score)) = stats.get_optimal_frequency() {
-                            println!("\n\n🎯 Phase d'apprentissage terminée!");
-                            stats.print_summary();
-                            println!("\n🔒 Passage en mode LOCKED à {} MHz\n", optimal_freq);
-                            governor.switch_to_locked(optimal_freq);
-                            set_gpu_frequency(&mut pp_file, optimal_freq)?;
-                            stats.set_frequency(optimal_freq, load);
-                        }
-                    } else {
-                        // Ajustement normal en mode learning
-                        if let Some(new_freq) = governor.try_adjust(&stats) {
-                            let direction = if new_freq > governor.current_freq() {
-                                "↑"
-                            } else {
-                                "↓"
-                            };
-                            println!(
-                                "\n[LEARNING] {} MHz {} {} MHz | Charge: {:.1}%",
-                                governor.current_freq(),
-                                direction,
-                                new_freq,
-                                load
-                            );
-                            set_gpu_frequency(&mut pp_file, new_freq)?;
-                            stats.set_frequency(new_freq, load);
-                        }
-                    }
-                }
-                GovernorMode::Locked => {
-                    // Vérifier le confort
-                    if !governor.check_comfort(load) {
-                        println!("\n⚠ Confort dégradé en mode LOCKED, passage en mode ADJUSTING\n");
-                        governor.switch_to_adjusting();
-                    }
-
-                    // Réévaluation périodique?
-                    if governor.mode_start.elapsed()
-                        >= Duration::from_secs(REEVALUATION_INTERVAL_SECS)
-                    {
-                        println!("\n🔄 Réévaluation périodique, passage en mode LEARNING\n");
-                        governor.switch_to_learning();
-                    }
-                }
-                GovernorMode::Adjusting => {
-                    // Ajuster la fréquence
-                    if let Some(new_freq) = governor.try_adjust(&stats) {
-                        let direction = if new_freq > governor.current_freq() {
-                            "↑"
-                        } else {
-                            "↓"
-                        };
-                        println!(
-                            "\n[ADJUSTING] {} MHz {} {} MHz | Charge: {:.1}%",
-                            governor.current_freq(),
-                            direction,
-                            new_freq,
-                            load
-                        );
-                        set_gpu_frequency(&mut pp_file, new_freq)?;
-                        stats.set_frequency(new_freq, load);
-                    }
-
-                    // Retour en locked si confort revenu?
-                    if governor.check_comfort(load)
-                        && governor.mode_start.elapsed() >= Duration::from_secs(30)
-                    {
-                        if let Some((optimal_freq, _)) = stats.get_optimal_frequency() {
-                            println!(
-                                "\n✓ Confort restauré, retour en mode LOCKED à {} MHz\n",
-                                optimal_freq
-                            );
-                            governor.switch_to_locked(optimal_freq);
-                            set_gpu_frequency(&mut pp_file, optimal_freq)?;
-                            stats.set_frequency(optimal_freq, load);
-                        }
-                    }
-                }
+            let now = Instant::now();
+            let sample = LoadSample {
+                busy: is_active,
+                load_percent: load_monitor.load_percent(),
+                timestamp: now,
+            };
+            let ctx = PolicyContext {
+                config: &config,
+                min_freq: MIN_FREQ_MHZ,
+                max_freq: MAX_FREQ_MHZ,
+                last_change,
+                active_process: None,
+                active_process_name: None,
+                process_stable: false,
+            };
+            let decision = governor.decide(&sample, current_freq, &ctx);
+            if decision.freq != current_freq {
+                set_gpu_frequency(&mut pp_file, decision.freq)?;
+                current_freq = decision.freq;
+                last_change = now;
             }
         }
 
         // Affichage temps réel
         if last_display.elapsed() >= Duration::from_millis(500) {
             let load = load_monitor.load_percent();
-            let freq = governor.current_freq();
-            let mode_str = match governor.mode {
-                GovernorMode::Learning => "LEARNING",
-                GovernorMode::Locked => "LOCKED  ",
-                GovernorMode::Adjusting => "ADJUSTING",
-            };
             eprint!(
-                "\r[{}] Charge: {:5.1}% | Fréq: {:4} MHz | Échantillons: {}",
-                mode_str, load, freq, sample_count
+                "\r[{:<9}] Charge: {:5.1}% | Fréq: {:4} MHz | Échantillons: {}",
+                governor.mode.as_str().to_uppercase(),
+                load,
+                current_freq,
+                sample_count
             );
             last_display = Instant::now();
         }
 
         // Sauvegarde périodique des stats (toutes les 60 secondes)
         if last_save.elapsed() >= Duration::from_secs(60) {
-            if let Err(e) = stats.save_to_file(&stats_path) {
+            if let Err(e) = governor.stats().save_to_file(&stats_path) {
                 eprintln!("\n⚠ Erreur sauvegarde stats: {}", e);
             }
             last_save = Instant::now();
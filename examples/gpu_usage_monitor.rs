@@ -3,11 +3,13 @@ use std::{
     fs::File,
     io::Error as IoError,
     os::fd::AsRawFd,
+    path::Path,
     thread,
     time::Duration,
 };
 
-use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
+use cyan_skillfish_governor::discovery::{DEFAULT_SYSFS_ROOT, find_cyan_skillfish};
+use libdrm_amdgpu_sys::AMDGPU::DeviceHandle;
 
 // Registre contenant le statut GRBM pour Cyan Skillfish (gfx1013)
 const GRBM_STATUS_REG: u32 = 0x2004;
@@ -62,26 +64,10 @@ impl GpuUsageCalculator {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 Démarrage du moniteur d'utilisation GPU...\n");
 
-    // Location PCI du GPU Cyan Skillfish (Steam Deck)
-    let location = BUS_INFO {
-        domain: 0,
-        bus: 1,
-        dev: 0,
-        func: 0,
-    };
-
-    // Vérifier que c'est bien un GPU Cyan Skillfish
-    let sysfs_path = location.get_sysfs_path();
-    let vendor = std::fs::read_to_string(sysfs_path.join("vendor"))?;
-    let device = std::fs::read_to_string(sysfs_path.join("device"))?;
-    
-    if !((vendor == "0x1002\n") && (device == "0x13fe\n")) {
-        return Err(IoError::other(
-            "GPU Cyan Skillfish introuvable à l'emplacement PCI attendu (0000:01:00.0)"
-        ))?;
-    }
+    // Recherche du GPU Cyan Skillfish sur le bus PCI
+    let location = find_cyan_skillfish(Path::new(DEFAULT_SYSFS_ROOT), None)?;
 
-    println!("✅ GPU Cyan Skillfish détecté");
+    println!("✅ GPU Cyan Skillfish détecté ({})", location);
 
     // Ouvrir le device DRM
     let card = File::open(location.get_drm_render_path()?)?;
@@ -1,40 +1,45 @@
 use cyan_skillfish_governor::constants::*;
-use cyan_skillfish_governor::governor::{GovernorMode, ProcessAwareGovernor};
+use cyan_skillfish_governor::governor::GovernorMode;
 use cyan_skillfish_governor::load_monitor::GpuLoadMonitor;
 use cyan_skillfish_governor::process_detection::EXCLUDED_PROCESSES;
 use cyan_skillfish_governor::process_monitor::ProcessMonitor;
 use cyan_skillfish_governor::profile_db::ProcessDatabase;
 
-use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::os::fd::AsRawFd;
+use cyan_skillfish_governor::clock_guard::ClockGuard;
+use cyan_skillfish_governor::config::GovernorConfig;
+use cyan_skillfish_governor::control::{self, ControlState, DEFAULT_SOCKET_PATH};
+use cyan_skillfish_governor::gpu_backend::{AmdgpuBackend, GpuBackend};
+use cyan_skillfish_governor::policy::{
+    FrequencyPolicy, LoadSample, Policy, PolicyContext, ProcessAwarePolicy,
+};
+use cyan_skillfish_governor::voltage::VoltageCurve;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-const GRBM_STATUS_REG: u32 = 0x2004;
-const GUI_ACTIVE_BIT_MASK: u32 = 1 << 31;
+/// Écrit les fréquences avec la tension de la courbe des `safe-points`
+struct FrequencySetter<'a> {
+    backend: &'a dyn GpuBackend,
+    control: &'a ControlState,
+    curve: VoltageCurve,
+}
 
-fn interpolate_voltage(freq: u16) -> u16 {
-    if freq <= MIN_FREQ_MHZ {
-        return MIN_VOLTAGE_MV;
-    }
-    if freq >= MAX_FREQ_MHZ {
-        return MAX_VOLTAGE_MV;
+impl FrequencySetter<'_> {
+    /// Applique `freq`, sauf si une fréquence est figée via le socket de contrôle
+    fn set(&self, freq: u16) -> Result<(), Box<dyn std::error::Error>> {
+        if self.control.pinned_freq().is_some() {
+            return Ok(());
+        }
+        self.force(freq)
     }
 
-    let freq_range = MAX_FREQ_MHZ - MIN_FREQ_MHZ;
-    let voltage_range = MAX_VOLTAGE_MV - MIN_VOLTAGE_MV;
-    let freq_offset = freq - MIN_FREQ_MHZ;
-
-    MIN_VOLTAGE_MV + (freq_offset as u32 * voltage_range as u32 / freq_range as u32) as u16
-}
-
-fn set_gpu_frequency(pp_file: &mut File, freq: u16) -> Result<(), Box<dyn std::error::Error>> {
-    let voltage = interpolate_voltage(freq);
-    pp_file.write_all(format!("vc 0 {} {}\n", freq, voltage).as_bytes())?;
-    pp_file.write_all(b"c\n")?;
-    pp_file.flush()?;
-    Ok(())
+    fn force(&self, freq: u16) -> Result<(), Box<dyn std::error::Error>> {
+        let voltage = self
+            .curve
+            .voltage(freq)
+            .ok_or_else(|| format!("Aucun point sûr pour {} MHz", freq))?;
+        self.backend.set_frequency(freq, voltage)?;
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -58,26 +63,63 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         PROCESS_UPDATE_INTERVAL_SECS
     );
 
-    let location = BUS_INFO {
-        domain: 0,
-        bus: 1,
-        dev: 0,
-        func: 0,
-    };
+    // Seuls `device`, `safe-points` et `voltage` sont utilisés ici: la politique est
+    // toujours `process-aware`
+    let config_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/etc/cyan-skillfish-governor/config.toml".to_string());
+    let config = GovernorConfig::load(&config_path).or_else(|e| {
+        if e.kind() == std::io::ErrorKind::InvalidInput {
+            return Err(e);
+        }
+        println!("⚠ Config {} illisible, points sûrs par défaut", config_path);
+        GovernorConfig::parse("")
+    })?;
+    println!(
+        "⚡ Tension: interpolation {} entre {} points sûrs\n",
+        config.voltage.interpolation.name(),
+        config.safe_points.len()
+    );
 
-    let card = File::open(location.get_drm_render_path()?)?;
-    let (dev_handle, _, _) = DeviceHandle::init(card.as_raw_fd())
-        .map_err(|e| format!("Échec ouverture GPU: erreur {}", e))?;
+    let backend = Arc::new(
+        AmdgpuBackend::discover(&config.paths, config.device.as_deref())
+            .map_err(|e| format!("Échec ouverture GPU: {}", e))?,
+    );
+    println!("🐧 {}\n", backend.quirks());
 
-    let sysfs_path = dev_handle
-        .get_sysfs_path()
-        .map_err(std::io::Error::from_raw_os_error)?;
+    // Remet les horloges d'origine à l'arrêt (Ctrl+C, SIGTERM, panique, erreur)
+    let guard = Arc::new(ClockGuard::new(backend.clone()));
+    guard.install_handlers()?;
 
-    let mut pp_file = OpenOptions::new()
-        .write(true)
-        .open(sysfs_path.join("pp_od_clk_voltage"))?;
+    let control = Arc::new(ControlState::new(&[Policy::ProcessAware.name()]));
+    control.update_status(|status| {
+        status.min_freq = MIN_FREQ_MHZ;
+        status.max_freq = MAX_FREQ_MHZ;
+    });
+    control::serve(DEFAULT_SOCKET_PATH.as_ref(), Arc::clone(&control))
+        .map_err(|e| format!("Échec ouverture socket {}: {}", DEFAULT_SOCKET_PATH, e))?;
+    println!("🔌 Socket de contrôle: {}\n", DEFAULT_SOCKET_PATH);
 
-    let mut db = ProcessDatabase::new();
+    // Les écritures passent par la garde: aucune après la restauration
+    let setter = FrequencySetter {
+        backend: &*guard,
+        control: &control,
+        curve: config.voltage_curve(),
+    };
+    let result = run(&*backend, &config, &control, &setter);
+    if let Err(e) = guard.restore() {
+        eprintln!("❌ Échec de la restauration des horloges: {}", e);
+    }
+    result
+}
+
+fn run(
+    backend: &dyn GpuBackend,
+    config: &GovernorConfig,
+    control: &ControlState,
+    setter: &FrequencySetter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = ProcessDatabase::with_path(config.profiles.database_path(), &config.paths);
     if !db.profiles.is_empty() {
         println!("💾 Base de données chargée:");
         db.print_summary();
@@ -85,7 +127,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("🆕 Aucun profil existant, création nouvelle base de données\n");
     }
 
-    let mut process_monitor = ProcessMonitor::new();
+    let mut process_monitor = ProcessMonitor::with_paths(config.paths.clone());
+    process_monitor.set_rules(config.processes.clone());
     // Activer le debug par défaut pour voir ce qui se passe
     process_monitor.debug_mode = true;
     // Possibilité de désactiver avec DEBUG_GPU_PROCESSES=0
@@ -96,184 +139,89 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("🔍 Mode debug activé (désactiver avec DEBUG_GPU_PROCESSES=0)\n");
     }
     let mut load_monitor = GpuLoadMonitor::new(SAMPLE_WINDOW_SIZE);
-    let mut governor = ProcessAwareGovernor::new();
+    let mut policy = ProcessAwarePolicy::new(db);
 
     let mut last_display = Instant::now();
     let mut sample_count = 0u64;
+    let mut current_freq = MIN_FREQ_MHZ;
+    let mut last_change = Instant::now();
     let mut current_tracked_process: Option<String> = None;
     let mut previous_tracked_process: Option<String> = None;
     let mut process_start_time: Option<Instant> = None;
+    let mut applied_pin: Option<u16> = None;
 
-    set_gpu_frequency(&mut pp_file, MIN_FREQ_MHZ)?;
+    setter.set(MIN_FREQ_MHZ)?;
 
     println!("🚀 Monitoring démarré... (Ctrl+C pour arrêter)\n");
 
     loop {
         // Lecture de l'activité GPU
-        let grbm_status = dev_handle
-            .read_mm_registers(GRBM_STATUS_REG)
-            .map_err(|e| format!("Échec lecture registre GPU: erreur {}", e))?;
-        let is_active = (grbm_status & GUI_ACTIVE_BIT_MASK) != 0;
+        let is_active = backend
+            .read_busy()
+            .map_err(|e| format!("Échec lecture registre GPU: {}", e))?;
 
         load_monitor.add_sample(is_active);
         sample_count += 1;
 
         // Détection du processus principal
         let detected_process = process_monitor.update();
-
-        // Détection de changement de processus
-        if detected_process.as_deref() != current_tracked_process.as_deref() {
-            if let Some(ref new_process) = detected_process {
-                let usage_percent = process_monitor.current_process_usage_percent;
+        if detected_process != current_tracked_process {
+            if detected_process.is_some() {
                 println!(
-                    "\n🔄 Détection nouveau processus GPU: '{}' ({:.2}% GPU)",
-                    new_process, usage_percent
+                    "\n🔍 Utilisation GPU du processus: {:.2}%",
+                    process_monitor.current_process_usage_percent
                 );
-
-                // Sauvegarder le profil du processus précédent si en apprentissage
-                if matches!(
-                    governor.mode,
-                    GovernorMode::Learning | GovernorMode::Reevaluating
-                ) {
-                    if let Some(ref old_process) = current_tracked_process {
-                        println!(
-                            "   Sauvegarde profil de '{}' (apprentissage interrompu)",
-                            old_process
-                        );
-                        if let Some(mut profile) = governor.finalize_learning() {
-                            profile.name = old_process.clone();
-                            db.set(profile);
-                        }
-                    }
-                }
-
-                // Charger ou démarrer apprentissage pour le nouveau processus
-                if let Some(profile) = db.get(new_process) {
-                    println!(
-                        "   ✓ Profil connu trouvé: {} MHz (confort: {:.1}/100, {} échantillons)",
-                        profile.optimal_freq, profile.comfort_score, profile.samples_count
-                    );
-                    println!("   Application de la fréquence optimale connue");
-                    governor.apply_known_frequency(profile.optimal_freq);
-                    set_gpu_frequency(&mut pp_file, profile.optimal_freq)?;
-                } else {
-                    println!(
-                        "   ⚠ Processus inconnu, lancement apprentissage ({} secondes)",
-                        LEARNING_DURATION_SECS
-                    );
-                    governor.start_learning(MIN_FREQ_MHZ);
-                    set_gpu_frequency(&mut pp_file, MIN_FREQ_MHZ)?;
-                }
-
-                previous_tracked_process = current_tracked_process.clone();
-                current_tracked_process = Some(new_process.clone());
                 process_start_time = Some(Instant::now());
             } else {
-                // Plus de processus GPU actif (ou seulement des processus inactifs)
-                if current_tracked_process.is_some() {
-                    println!("\n💤 Aucune activité GPU significative (processus desktop ignorés)");
-                    governor.enter_idle();
-                    set_gpu_frequency(&mut pp_file, MIN_FREQ_MHZ)?;
-                    previous_tracked_process = current_tracked_process.clone();
-                    current_tracked_process = None;
-                    process_start_time = None;
-                }
+                process_start_time = None;
             }
+            previous_tracked_process = current_tracked_process.take();
+            current_tracked_process = detected_process;
         }
 
+        // La politique ne reçoit la charge qu'une fois la fenêtre pleine
         if load_monitor.is_full() {
-            let load = load_monitor.load_percent();
-            governor.add_load_sample(load);
-
-            // Mise à jour des stats d'apprentissage
-            if let Some(stats) = &mut governor.learning_stats {
-                stats.set_frequency(governor.current_freq, load);
+            let now = Instant::now();
+            let sample = LoadSample {
+                busy: is_active,
+                load_percent: load_monitor.load_percent(),
+                timestamp: now,
+            };
+            let ctx = PolicyContext {
+                config,
+                min_freq: MIN_FREQ_MHZ,
+                max_freq: MAX_FREQ_MHZ,
+                last_change,
+                active_process: current_tracked_process.as_deref(),
+                active_process_name: process_monitor.current_name(),
+                process_stable: process_monitor.is_process_stable(),
+            };
+            let decision = policy.decide(&sample, current_freq, &ctx);
+            if decision.freq != current_freq {
+                setter.set(decision.freq)?;
+                current_freq = decision.freq;
+                last_change = now;
             }
+        }
 
-            match governor.mode {
-                GovernorMode::Idle => {
-                    // Rien à faire
-                }
-                GovernorMode::Applied => {
-                    // Vérifier si saturation
-                    if governor.check_saturation() && process_monitor.is_process_stable() {
-                        if let Some(ref process_name) = current_tracked_process {
-                            if let Some(profile) = db.get(process_name) {
-                                println!(
-                                    "\n⚠ SURCHARGE DÉTECTÉE: Charge > {:.0}% pendant 60s (moyenne: {:.1}%)",
-                                    HIGH_LOAD_THRESHOLD,
-                                    governor.average_load()
-                                );
-                                println!(
-                                    "   La config graphique a peut-être changé, augmentation par palier de {} MHz",
-                                    FREQ_STEP_MHZ
-                                );
-                                governor.start_reevaluation(profile.optimal_freq);
-                            }
-                        }
-                    }
-                    // Vérifier si sous-charge
-                    else if governor.check_underload() && process_monitor.is_process_stable() {
-                        if let Some(ref process_name) = current_tracked_process {
-                            if let Some(profile) = db.get(process_name) {
-                                println!(
-                                    "\n🔻 SOUS-CHARGE DÉTECTÉE: Charge < {:.0}% pendant 60s (moyenne: {:.1}%)",
-                                    LOW_LOAD_THRESHOLD,
-                                    governor.average_load()
-                                );
-                                println!(
-                                    "   La config graphique a peut-être changé, réduction par palier de {} MHz",
-                                    FREQ_STEP_MHZ
-                                );
-                                governor.start_reevaluation(profile.optimal_freq);
-                            }
-                        }
-                    }
-                }
-                GovernorMode::Learning | GovernorMode::Reevaluating => {
-                    // Ajustement dynamique pendant l'apprentissage
-                    let old_freq = governor.current_freq;
-                    if let Some(new_freq) = governor.try_adjust_learning() {
-                        set_gpu_frequency(&mut pp_file, new_freq)?;
-                        let direction = if new_freq > old_freq { "↑" } else { "↓" };
-                        println!(
-                            "   [{}] {} MHz {} {} MHz (charge: {:.1}%, palier: ±{} MHz)",
-                            if matches!(governor.mode, GovernorMode::Learning) {
-                                "LEARNING"
-                            } else {
-                                "REEVALUAT"
-                            },
-                            old_freq,
-                            direction,
-                            new_freq,
-                            load,
-                            FREQ_STEP_MHZ
-                        );
-                    }
-
-                    // Vérifier si apprentissage terminé
-                    let learning_done = governor.mode_start.elapsed()
-                        >= Duration::from_secs(LEARNING_DURATION_SECS);
-
-                    if learning_done && process_monitor.is_process_stable() {
-                        if let Some(ref process_name) = current_tracked_process {
-                            if let Some(mut profile) = governor.finalize_learning() {
-                                profile.name = process_name.clone();
-                                println!(
-                                    "\n✓ Apprentissage terminé pour '{}': {} MHz optimal",
-                                    process_name, profile.optimal_freq
-                                );
-                                db.set(profile.clone());
-
-                                // Appliquer la fréquence optimale trouvée
-                                governor.apply_known_frequency(profile.optimal_freq);
-                                set_gpu_frequency(&mut pp_file, profile.optimal_freq)?;
-                            }
-                        }
-                    }
-                }
-            }
+        // Fréquence figée / libérée via le socket de contrôle
+        let pinned = control.pinned_freq();
+        if pinned != applied_pin {
+            setter.force(pinned.unwrap_or(current_freq))?;
+            applied_pin = pinned;
         }
+        let governor = policy.governor();
+        control.update_status(|status| {
+            status.current_freq = pinned.unwrap_or(current_freq);
+            status.target_freq = current_freq;
+            status.load_percent = load_monitor.load_percent();
+            status.mode = governor.mode.as_str().to_string();
+            status.active_process = process_monitor
+                .current_name()
+                .map(str::to_string)
+                .or_else(|| current_tracked_process.clone());
+            status.learning_progress = policy.learning_progress();
+        });
 
         // Affichage temps réel
         if last_display.elapsed() >= Duration::from_millis(500) {
@@ -300,7 +248,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             };
             eprint!(
                 "\r[{}] {} | Charge: {:5.1}% | Fréq: {:4} MHz | Process: {} (âge: {}) | Prev: {}",
-                mode_str, sample_count, load, governor.current_freq, process_str, age_str, prev_str
+                mode_str, sample_count, load, current_freq, process_str, age_str, prev_str
             );
             last_display = Instant::now();
         }
@@ -1,4 +1,5 @@
-use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
+use cyan_skillfish_governor::discovery::{DEFAULT_SYSFS_ROOT, find_cyan_skillfish};
+use libdrm_amdgpu_sys::AMDGPU::DeviceHandle;
 use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs::{File, OpenOptions};
 use std::io::{Error as IoError, Write};
@@ -863,12 +864,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         PROCESS_UPDATE_INTERVAL_SECS
     );
 
-    let location = BUS_INFO {
-        domain: 0,
-        bus: 1,
-        dev: 0,
-        func: 0,
-    };
+    let location = find_cyan_skillfish(Path::new(DEFAULT_SYSFS_ROOT), None)?;
 
     let card = File::open(location.get_drm_render_path()?)?;
     let (dev_handle, _, _) = DeviceHandle::init(card.as_raw_fd())
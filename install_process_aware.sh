@@ -47,4 +47,5 @@ echo "  - Arrêter le service:   sudo systemctl stop process-aware-governor.serv
 echo "  - Redémarrer:           sudo systemctl restart process-aware-governor.service"
 echo "  - Désactiver:           sudo systemctl disable process-aware-governor.service"
 echo ""
-echo "Base de données des profils: ~/.cache/cyan-skillfish-governor/process_profiles.json"
+echo "Base de données des profils: /var/lib/cyan-skillfish-governor/process_profiles.json"
+echo "(importée une fois depuis /root/.cache/cyan-skillfish-governor si elle y existe)"
@@ -1,4 +1,5 @@
 use cyan_skillfish_governor::gpu_sensor::GpuSensor;
+use cyan_skillfish_governor::metrics::{self, DEFAULT_METRICS_LISTEN};
 use std::env;
 use std::process;
 
@@ -11,11 +12,15 @@ fn print_usage() {
     println!("Options:");
     println!("  --path <path>       Chemin du fichier sensor (défaut: /run/gpu-sensor/load)");
     println!("  --interval <ms>     Intervalle de mise à jour en ms (défaut: 1000)");
+    println!(
+        "  --metrics [<addr>]  Exporteur Prometheus sur http://<addr>/metrics (défaut: {DEFAULT_METRICS_LISTEN})"
+    );
     println!("  --help              Afficher cette aide");
     println!();
     println!("Exemples:");
     println!("  sudo gpu_sensor_daemon");
     println!("  sudo gpu_sensor_daemon --path /tmp/gpu-load --interval 500");
+    println!("  sudo gpu_sensor_daemon --metrics 0.0.0.0:9617");
     println!();
     println!("Le daemon expose la charge GPU dans deux formats:");
     println!("  1. Fichier simple: <path> contient le pourcentage (ex: 45.32)");
@@ -31,6 +36,7 @@ fn main() {
 
     let mut sensor_path = "/run/gpu-sensor/load".to_string();
     let mut interval_ms = 1000u64;
+    let mut metrics_listen: Option<String> = None;
 
     // Parser les arguments
     let mut i = 1;
@@ -64,6 +70,16 @@ fn main() {
                     process::exit(1);
                 }
             }
+            "--metrics" => {
+                // Adresse optionnelle: boucle locale par défaut
+                match args.get(i + 1).filter(|a| !a.starts_with("--")) {
+                    Some(addr) => {
+                        metrics_listen = Some(addr.clone());
+                        i += 1;
+                    }
+                    None => metrics_listen = Some(DEFAULT_METRICS_LISTEN.to_string()),
+                }
+            }
             _ => {
                 eprintln!("❌ Argument inconnu: {}", args[i]);
                 eprintln!();
@@ -91,6 +107,19 @@ fn main() {
         }
     };
 
+    if let Some(listen) = metrics_listen {
+        let stats = sensor.stats();
+        match metrics::serve(&listen, move |format| {
+            metrics::sensor_metrics(&stats.lock().unwrap(), format)
+        }) {
+            Ok(addr) => println!("📈 Métriques: http://{addr}/metrics"),
+            Err(e) => {
+                eprintln!("❌ Erreur exporteur de métriques sur {listen}: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
     // Gérer Ctrl+C proprement
     let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
     let r = running.clone();
@@ -102,7 +131,7 @@ fn main() {
     .expect("Erreur lors de la configuration du handler Ctrl+C");
 
     // Lancer le daemon
-    if let Err(e) = sensor.run_daemon() {
+    if let Err(e) = sensor.run_daemon(&running) {
         eprintln!("❌ Erreur fatale: {}", e);
         process::exit(1);
     }
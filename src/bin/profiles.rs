@@ -0,0 +1,209 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use cyan_skillfish_governor::config::GovernorConfig;
+use cyan_skillfish_governor::profile_bundle::{BundleFormat, MergeStrategy, ProfileBundle};
+use cyan_skillfish_governor::profile_db::ProcessDatabase;
+
+/// Config of the systemd unit, used when --config is not given and it exists
+const DEFAULT_CONFIG_PATH: &str = "/etc/cyan-skillfish-governor/config.toml";
+
+fn print_usage() {
+    println!("profiles - List, export and import the profiles learned by the process-aware policy");
+    println!();
+    println!("Usage:");
+    println!("  profiles list");
+    println!("  profiles export --output <file> [--format json|toml] [PROFILE ...]");
+    println!("  profiles import [--strategy <name>] [--format json|toml] <bundle>");
+    println!();
+    println!("PROFILE is a profile key (e.g. steam:1145360) or process name; default: all.");
+    println!("The bundle format follows the file extension (.toml, JSON otherwise)");
+    println!("unless --format is given.");
+    println!();
+    println!("Import strategies, for profiles already learned here:");
+    println!("  keep-local     Keep the local profile (default)");
+    println!("  more-samples   Keep the profile learned over more samples");
+    println!("  prefer-remote  Replace it with the bundle's");
+    println!();
+    println!("Bundles learned with other safe-points or voltage interpolation are refused.");
+    println!();
+    println!("Options:");
+    println!("  --config <file>   Governor config: database path and safe-points");
+    println!("                    (default: {DEFAULT_CONFIG_PATH} if present)");
+    println!("  --help            Show this help");
+    println!();
+    println!("Examples:");
+    println!("  sudo profiles export --output deck.toml steam:1145360 vkcube");
+    println!("  sudo profiles import --strategy more-samples deck.toml");
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {message}");
+    process::exit(1);
+}
+
+fn load_config(path: Option<&str>) -> GovernorConfig {
+    let path = path.or(Path::new(DEFAULT_CONFIG_PATH)
+        .exists()
+        .then_some(DEFAULT_CONFIG_PATH));
+    match path {
+        Some(path) => GovernorConfig::load(path)
+            .unwrap_or_else(|e| fail(&format!("could not load {path}: {e}"))),
+        None => GovernorConfig::parse("").unwrap_or_else(|e| fail(&e.to_string())),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut command = None;
+    let mut config_path = None;
+    let mut output: Option<PathBuf> = None;
+    let mut format = None;
+    let mut strategy = MergeStrategy::default();
+    let mut operands = Vec::new();
+
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" | "-h" => {
+                print_usage();
+                process::exit(0);
+            }
+            "--config" => {
+                config_path = Some(
+                    args.get(i + 1)
+                        .unwrap_or_else(|| fail("--config requires an argument"))
+                        .as_str(),
+                );
+                i += 1;
+            }
+            "--output" | "-o" => {
+                let path = args
+                    .get(i + 1)
+                    .unwrap_or_else(|| fail(&format!("{arg} requires an argument")));
+                output = Some(PathBuf::from(path));
+                i += 1;
+            }
+            "--format" => {
+                let name = args
+                    .get(i + 1)
+                    .unwrap_or_else(|| fail("--format requires an argument"));
+                format = Some(
+                    BundleFormat::from_name(name)
+                        .unwrap_or_else(|| fail(&format!("unknown format: {name}"))),
+                );
+                i += 1;
+            }
+            "--strategy" => {
+                let name = args
+                    .get(i + 1)
+                    .unwrap_or_else(|| fail("--strategy requires an argument"));
+                strategy = MergeStrategy::from_name(name)
+                    .unwrap_or_else(|| fail(&format!("unknown strategy: {name}")));
+                i += 1;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Error: unknown argument: {arg}");
+                eprintln!();
+                print_usage();
+                process::exit(1);
+            }
+            _ if command.is_none() => command = Some(arg.to_string()),
+            _ => operands.push(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some(command) = command else {
+        print_usage();
+        process::exit(1);
+    };
+    let config = load_config(config_path);
+    let fingerprint = config.voltage_curve().fingerprint();
+
+    match command.as_str() {
+        "list" => {
+            let db = ProcessDatabase::open_read_only(config.profiles.database_path());
+            println!("Database: {}", db.path().display());
+            if let Some(device) = db.device() {
+                println!("GPU: {} [{}]", device.pci_address, device.pci_id);
+            }
+            println!("Voltage curve of this config: {fingerprint}");
+            println!();
+            db.print_summary();
+            let mut other: Vec<_> = db
+                .profiles
+                .iter()
+                .filter(|(_, p)| p.voltage_curve.as_ref() != Some(&fingerprint))
+                .map(|(key, _)| key.as_str())
+                .collect();
+            if !other.is_empty() {
+                other.sort_unstable();
+                println!(
+                    "Learned with another voltage curve, or before curves were recorded: {}",
+                    other.join(", ")
+                );
+            }
+        }
+        "export" => {
+            // Not stdout: the database prints its own messages there
+            let output = output.unwrap_or_else(|| fail("export requires --output"));
+            let db = ProcessDatabase::open_read_only(config.profiles.database_path());
+            let bundle =
+                ProfileBundle::export(&db, &operands).unwrap_or_else(|e| fail(&e.to_string()));
+            let format = format.unwrap_or_else(|| BundleFormat::from_path(&output));
+            let text = bundle
+                .to_string(format)
+                .unwrap_or_else(|e| fail(&e.to_string()));
+            std::fs::write(&output, text)
+                .unwrap_or_else(|e| fail(&format!("could not write {}: {e}", output.display())));
+            println!(
+                "Exported {} profiles to {}",
+                bundle.profiles.len(),
+                output.display()
+            );
+        }
+        "import" => {
+            let [path] = operands.as_slice() else {
+                fail("import takes exactly one bundle");
+            };
+            let path = Path::new(path);
+            let content = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| fail(&format!("could not read {}: {e}", path.display())));
+            let format = format.unwrap_or_else(|| BundleFormat::from_path(path));
+            let bundle = ProfileBundle::parse(&content, format)
+                .unwrap_or_else(|e| fail(&format!("{}: {e}", path.display())));
+            if let Some(device) = &bundle.device {
+                println!(
+                    "Bundle from GPU {} [{}], {} profiles",
+                    device.pci_address,
+                    device.pci_id,
+                    bundle.profiles.len()
+                );
+            }
+            let mut db = ProcessDatabase::with_path(config.profiles.database_path(), &config.paths);
+            let summary = bundle
+                .import_into(&mut db, &fingerprint, strategy)
+                .unwrap_or_else(|e| fail(&e.to_string()));
+            for (label, keys) in [
+                ("Added", &summary.added),
+                ("Replaced", &summary.replaced),
+                ("Kept local", &summary.kept),
+            ] {
+                if !keys.is_empty() {
+                    println!("{label}: {}", keys.join(", "));
+                }
+            }
+            println!("Database: {}", db.path().display());
+        }
+        _ => {
+            eprintln!("Error: unknown command: {command}");
+            eprintln!();
+            print_usage();
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::env;
+use std::process;
+use std::time::Duration;
+
+use cyan_skillfish_governor::config::GovernorConfig;
+use cyan_skillfish_governor::policy::Policy;
+use cyan_skillfish_governor::simulation::{FramePacing, LoadSource, SquareWave, Trace, simulate};
+
+/// Without a trace, generators run for this long unless --duration is given
+const DEFAULT_DURATION_SECS: f64 = 60.0;
+
+enum Source {
+    Trace(Trace),
+    Square { period: Duration, duty: f64 },
+    Game { fps: f64, work: f64 },
+    Idle,
+}
+
+impl Source {
+    /// A fresh source, so that every config replays the same load
+    fn start(&self) -> Box<dyn LoadSource> {
+        match self {
+            Source::Trace(trace) => Box::new(trace.clone()),
+            Source::Square { period, duty } => Box::new(SquareWave::new(*period, *duty)),
+            Source::Game { fps, work } => Box::new(FramePacing::new(*fps, *work)),
+            Source::Idle => Box::new(FramePacing::idle_desktop()),
+        }
+    }
+}
+
+fn print_usage() {
+    println!("simulate - Replay a GPU load through the governor loop, without a GPU");
+    println!();
+    println!("Usage:");
+    println!("  simulate <SOURCE> [OPTIONS] [config.toml ...]");
+    println!();
+    println!("Sources:");
+    println!("  --trace <file>              Recorded GRBM samples ('1' busy, '0' idle)");
+    println!("  --square <period_ms>:<duty> Square wave, busy <duty>% of each period");
+    println!("  --game <fps>:<mcycles>      Frame pacing, <mcycles> million cycles per frame");
+    println!("  --idle                      Idle desktop (compositor at 60 fps)");
+    println!();
+    println!("Options:");
+    println!("  --duration <s>    Simulated time (default: the whole trace, or 60 s)");
+    println!("  --policy <name>   Override the config policy (threshold, pid)");
+    println!("  --help            Show this help");
+    println!();
+    println!("One report is printed per config file (default: built-in defaults).");
+    println!();
+    println!("Examples:");
+    println!("  simulate --game 60:15 /etc/cyan-skillfish-governor/config.toml");
+    println!("  simulate --square 500:30 --policy pid a.toml b.toml");
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {message}");
+    process::exit(1);
+}
+
+/// `<a>:<b>`, both numbers
+fn pair(arg: &str, value: Option<&String>) -> (f64, f64) {
+    let value = value.unwrap_or_else(|| fail(&format!("{arg} requires an argument")));
+    value
+        .split_once(':')
+        .and_then(|(a, b)| Some((a.parse().ok()?, b.parse().ok()?)))
+        .filter(|&(a, b): &(f64, f64)| a > 0.0 && b >= 0.0)
+        .unwrap_or_else(|| fail(&format!("invalid {arg} value: {value}")))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut source = None;
+    let mut duration = None;
+    let mut policy = None;
+    let mut configs = Vec::new();
+
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" | "-h" => {
+                print_usage();
+                process::exit(0);
+            }
+            "--trace" => {
+                let path = args
+                    .get(i + 1)
+                    .unwrap_or_else(|| fail("--trace requires an argument"));
+                let trace = Trace::load(path)
+                    .unwrap_or_else(|e| fail(&format!("could not read {path}: {e}")));
+                source = Some(Source::Trace(trace));
+                i += 1;
+            }
+            "--square" => {
+                let (period_ms, duty) = pair(arg, args.get(i + 1));
+                let period = Duration::from_secs_f64(period_ms / 1000.0);
+                source = Some(Source::Square { period, duty });
+                i += 1;
+            }
+            "--game" => {
+                let (fps, work) = pair(arg, args.get(i + 1));
+                source = Some(Source::Game { fps, work });
+                i += 1;
+            }
+            "--idle" => source = Some(Source::Idle),
+            "--duration" => {
+                let secs: f64 = args
+                    .get(i + 1)
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&s: &f64| s > 0.0 && s.is_finite())
+                    .unwrap_or_else(|| fail("--duration requires a positive number of seconds"));
+                duration = Some(Duration::from_secs_f64(secs));
+                i += 1;
+            }
+            "--policy" => {
+                let name = args
+                    .get(i + 1)
+                    .unwrap_or_else(|| fail("--policy requires an argument"));
+                policy = Some(
+                    Policy::from_name(name)
+                        .unwrap_or_else(|| fail(&format!("unknown policy: {name}"))),
+                );
+                i += 1;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Error: unknown argument: {arg}");
+                eprintln!();
+                print_usage();
+                process::exit(1);
+            }
+            _ => configs.push(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some(source) = source else {
+        print_usage();
+        process::exit(1);
+    };
+    let duration = duration.unwrap_or(match source {
+        Source::Trace(_) => Duration::MAX,
+        _ => Duration::from_secs_f64(DEFAULT_DURATION_SECS),
+    });
+
+    let configs: Vec<(String, GovernorConfig)> = if configs.is_empty() {
+        let config = GovernorConfig::parse("").unwrap_or_else(|e| fail(&e.to_string()));
+        vec![("defaults".to_string(), config)]
+    } else {
+        configs
+            .into_iter()
+            .map(|path| {
+                let config = GovernorConfig::load(&path)
+                    .unwrap_or_else(|e| fail(&format!("could not load {path}: {e}")));
+                (path, config)
+            })
+            .collect()
+    };
+
+    for (n, (name, config)) in configs.iter().enumerate() {
+        let policy = policy.unwrap_or(config.policy);
+        let initial = config.min_safe_freq();
+        // Checked before building: `process-aware` would open the profile database
+        if policy == Policy::ProcessAware {
+            fail(&format!(
+                "{name}: the {} policy needs running processes and cannot be simulated",
+                policy.name()
+            ));
+        }
+        let freq_policy = policy.build(initial, config);
+
+        if n > 0 {
+            println!();
+        }
+        println!("== {name} (policy: {}) ==", policy.name());
+        let report = simulate(config, freq_policy, &mut *source.start(), duration);
+        println!("{report}");
+    }
+}
@@ -0,0 +1,258 @@
+use std::io::{Error as IoError, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::gpu_backend::GpuBackend;
+
+/// Remet les horloges d'origine du GPU quand le gouverneur s'arrête
+///
+/// L'état de `pp_od_clk_voltage` est relevé à la création. Il est réécrit au
+/// `Drop` du dernier `Arc` (retour d'erreur, fin de `main`), sur SIGINT/SIGTERM
+/// et en cas de panique une fois `install_handlers` appelé. Si l'état d'origine
+/// n'a pas pu être lu, la table par défaut du noyau est restaurée (`r`).
+///
+/// Les fréquences écrites à travers la garde (elle implémente `GpuBackend`)
+/// sont refusées une fois la restauration faite: le GPU ne peut pas repartir à
+/// une fréquence du gouverneur pendant l'arrêt.
+pub struct ClockGuard {
+    backend: Arc<dyn GpuBackend>,
+    original: Option<(u16, u16)>,
+    restored: AtomicBool,
+    /// Tenu pendant chaque écriture: la restauration attend celle en cours
+    writing: Mutex<()>,
+}
+
+/// Attente maximale de l'écriture en cours depuis le hook de panique, qui
+/// peut s'exécuter sur le thread même qui tient `writing`
+const PANIC_LOCK_TIMEOUT: Duration = Duration::from_millis(500);
+
+impl ClockGuard {
+    pub fn new(backend: Arc<dyn GpuBackend>) -> Self {
+        let original = backend
+            .read_od_table()
+            .ok()
+            .and_then(|table| table.user_point());
+        Self {
+            backend,
+            original,
+            restored: AtomicBool::new(false),
+            writing: Mutex::new(()),
+        }
+    }
+
+    /// Point fréquence (MHz) / tension (mV) relevé au démarrage
+    pub fn original(&self) -> Option<(u16, u16)> {
+        self.original
+    }
+
+    /// Restaure les horloges d'origine; les appels suivants ne font rien
+    pub fn restore(&self) -> Result<(), IoError> {
+        if self.restored.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let _writing = self.writing.lock().unwrap_or_else(PoisonError::into_inner);
+        self.write_original()
+    }
+
+    /// `restore` depuis le hook de panique
+    ///
+    /// La panique a pu survenir pendant une écriture de ce thread, qui tient
+    /// encore `writing`: au-delà de `PANIC_LOCK_TIMEOUT`, la restauration se
+    /// fait sans le verrou plutôt que de bloquer le processus.
+    fn restore_after_panic(&self) -> Result<(), IoError> {
+        if self.restored.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let _writing = self.try_lock_writing(PANIC_LOCK_TIMEOUT);
+        self.write_original()
+    }
+
+    fn try_lock_writing(&self, timeout: Duration) -> Option<MutexGuard<'_, ()>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.writing.try_lock() {
+                Ok(lock) => return Some(lock),
+                Err(TryLockError::Poisoned(e)) => return Some(e.into_inner()),
+                Err(TryLockError::WouldBlock) if Instant::now() >= deadline => return None,
+                Err(TryLockError::WouldBlock) => std::thread::sleep(Duration::from_millis(1)),
+            }
+        }
+    }
+
+    fn write_original(&self) -> Result<(), IoError> {
+        match self.original {
+            Some((freq, voltage)) => {
+                println!("🔙 Restauration des horloges d'origine: {freq} MHz @ {voltage} mV");
+                self.backend.set_frequency(freq, voltage)
+            }
+            None => {
+                println!("🔙 Restauration de la table OD par défaut du noyau");
+                self.backend.reset()
+            }
+        }
+    }
+
+    /// Exécute une écriture, sauf si les horloges ont déjà été restaurées
+    fn write(&self, f: impl FnOnce(&dyn GpuBackend) -> Result<(), IoError>) -> Result<(), IoError> {
+        let _writing = self.writing.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.restored.load(Ordering::SeqCst) {
+            return Err(IoError::new(
+                ErrorKind::Interrupted,
+                "original clocks restored, frequency writes stopped",
+            ));
+        }
+        f(&*self.backend)
+    }
+
+    /// Restaure les horloges sur SIGINT/SIGTERM (puis quitte) et sur panique
+    ///
+    /// Les gestionnaires ne gardent qu'une référence faible: le `Drop` a lieu
+    /// dès que le dernier `Arc` est relâché.
+    ///
+    /// Avec la fonctionnalité `termination`, ctrlc prend aussi SIGHUP: le
+    /// rechargement (`reload::install_sighup_handler`) doit être installé
+    /// *après* cet appel pour récupérer ce signal.
+    pub fn install_handlers(self: &Arc<Self>) -> Result<(), ctrlc::Error> {
+        let guard = Arc::downgrade(self);
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+            if let Some(guard) = guard.upgrade()
+                && let Err(e) = guard.restore_after_panic()
+            {
+                eprintln!("❌ Échec de la restauration des horloges: {e}");
+            }
+        }));
+
+        let guard = Arc::downgrade(self);
+        ctrlc::set_handler(move || {
+            println!("\n🛑 Arrêt demandé");
+            // Sans garde, le `Drop` a déjà restauré les horloges
+            let code = match guard.upgrade().map_or(Ok(()), |guard| guard.restore()) {
+                Ok(()) => 0,
+                Err(e) => {
+                    eprintln!("❌ Échec de la restauration des horloges: {e}");
+                    1
+                }
+            };
+            std::process::exit(code);
+        })
+    }
+}
+
+impl GpuBackend for ClockGuard {
+    fn read_busy(&self) -> Result<bool, IoError> {
+        self.backend.read_busy()
+    }
+
+    fn clock_limits(&self) -> Result<(u64, u64), IoError> {
+        self.backend.clock_limits()
+    }
+
+    fn apply(&self, freq: u16, voltage: u16) -> Result<(), IoError> {
+        self.write(|backend| backend.apply(freq, voltage))
+    }
+
+    fn commit(&self) -> Result<(), IoError> {
+        self.write(|backend| backend.commit())
+    }
+
+    fn read_sclk(&self) -> Result<u16, IoError> {
+        self.backend.read_sclk()
+    }
+
+    fn read_od_state(&self) -> Result<String, IoError> {
+        self.backend.read_od_state()
+    }
+
+    fn reset(&self) -> Result<(), IoError> {
+        self.write(|backend| backend.reset())
+    }
+
+    fn set_frequency(&self, freq: u16, voltage: u16) -> Result<(), IoError> {
+        self.write(|backend| backend.set_frequency(freq, voltage))
+    }
+}
+
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.restore() {
+            eprintln!("❌ Échec de la restauration des horloges: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu_backend::MockBackend;
+
+    #[test]
+    fn test_restores_original_point() {
+        let mock = Arc::new(MockBackend::new(350, 2000));
+        mock.set_frequency(1000, 900).unwrap();
+
+        let guard = ClockGuard::new(mock.clone());
+        assert_eq!(guard.original(), Some((1000, 900)));
+        mock.set_frequency(2000, 1000).unwrap();
+        drop(guard);
+
+        assert_eq!(mock.current(), Some((1000, 900)));
+    }
+
+    #[test]
+    fn test_resets_when_no_manual_point() {
+        let mock = Arc::new(MockBackend::new(350, 2000).with_stock_point(0, 0));
+        let guard = ClockGuard::new(mock.clone());
+        assert_eq!(guard.original(), None);
+
+        mock.set_frequency(2000, 1000).unwrap();
+        guard.restore().unwrap();
+        guard.restore().unwrap();
+        assert_eq!(mock.committed(), vec![(2000, 1000), (0, 0)]);
+    }
+
+    #[test]
+    fn test_writes_refused_once_restored() {
+        let mock = Arc::new(MockBackend::new(350, 2000));
+        mock.set_frequency(1000, 900).unwrap();
+        let guard = ClockGuard::new(mock.clone());
+
+        guard.set_frequency(2000, 1000).unwrap();
+        guard.restore().unwrap();
+        let err = guard.set_frequency(1500, 950).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        assert_eq!(mock.current(), Some((1000, 900)));
+    }
+
+    #[test]
+    fn test_panic_during_write_still_restores() {
+        let mock = Arc::new(MockBackend::new(350, 2000));
+        mock.set_frequency(1000, 900).unwrap();
+        let guard = ClockGuard::new(mock.clone());
+        guard.set_frequency(2000, 1000).unwrap();
+
+        // Hook de panique appelé pendant une écriture de ce même thread
+        let writing = guard.writing.lock().unwrap();
+        guard.restore_after_panic().unwrap();
+        drop(writing);
+        assert_eq!(mock.current(), Some((1000, 900)));
+        assert_eq!(
+            guard.set_frequency(1500, 950).unwrap_err().kind(),
+            ErrorKind::Interrupted
+        );
+    }
+
+    #[test]
+    fn test_handlers_do_not_keep_guard_alive() {
+        let mock = Arc::new(MockBackend::new(350, 2000));
+        mock.set_frequency(1000, 900).unwrap();
+        let guard = Arc::new(ClockGuard::new(mock.clone()));
+        guard.install_handlers().unwrap();
+
+        guard.set_frequency(2000, 1000).unwrap();
+        drop(guard);
+        assert_eq!(mock.current(), Some((1000, 900)));
+    }
+}
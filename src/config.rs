@@ -0,0 +1,1311 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::control::DEFAULT_SOCKET_PATH;
+use crate::discovery::parse_pci_address;
+use crate::game_overrides::{GameOverride, GameSelector};
+use crate::metrics::{DEFAULT_METRICS_LISTEN, MetricsConfig};
+use crate::pid::PidConfig;
+use crate::policy::Policy;
+use crate::process_rules::{Field, Pattern, ProcessRule, ProcessRules, builtin_exclusions};
+use crate::profile_db::ProfilesConfig;
+use crate::system_paths::SystemPaths;
+use crate::trace::{DEFAULT_TRACE_PATH, TraceConfig};
+use crate::verify::{MAX_BACKOFF_MS, MAX_RETRY_MS, VerifyConfig};
+use crate::voltage::{Interpolation, VoltageCurve};
+
+/// Configuration du gouverneur, partagée par tous les binaires
+///
+/// Les valeurs absentes ou invalides sont remplacées par leur défaut (avec un
+/// message), sauf `safe-points` dont toute erreur est fatale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", try_from = "RawConfig")]
+pub struct GovernorConfig {
+    /// Adresse PCI du GPU (`0000:01:00.0`); détection automatique si absente
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    pub policy: Policy,
+    pub timing: TimingConfig,
+    pub frequency_thresholds: FrequencyThresholds,
+    pub load_target: LoadTarget,
+    pub pid: PidConfig,
+    pub reload: ReloadConfig,
+    pub control: ControlConfig,
+    pub metrics: MetricsConfig,
+    pub voltage: VoltageConfig,
+    pub verify: VerifyConfig,
+    pub trace: TraceConfig,
+    pub paths: SystemPaths,
+    pub processes: ProcessRules,
+    pub profiles: ProfilesConfig,
+    /// Sections `[[game]]`, dans l'ordre du fichier
+    #[serde(rename = "game", skip_serializing_if = "Vec::is_empty")]
+    pub games: Vec<GameOverride>,
+    /// MHz → mV
+    #[serde(serialize_with = "serialize_safe_points")]
+    pub safe_points: BTreeMap<u16, u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TimingConfig {
+    pub intervals: Intervals,
+    pub ramp_rates: RampRates,
+    /// Nombre d'échantillons actifs consécutifs déclenchant un burst (0 = désactivé)
+    pub burst_samples: u8,
+    /// Taille de la fenêtre de moyenne mobile de la charge
+    pub window_samples: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intervals {
+    /// us
+    pub sample: u16,
+    /// us
+    pub adjust: u64,
+    /// us
+    pub finetune: u64,
+    /// secondes
+    pub log: u64,
+    /// us (0 = désactivé)
+    pub optimize: u64,
+}
+
+/// MHz/ms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RampRates {
+    pub normal: f32,
+    pub burst: f32,
+}
+
+/// MHz
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyThresholds {
+    pub adjust: u16,
+    pub finetune: u16,
+}
+
+/// Pourcentage (0-100)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadTarget {
+    pub upper: f32,
+    pub lower: f32,
+}
+
+/// Rechargement à chaud de la configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReloadConfig {
+    /// Surveiller le fichier et le recharger dès qu'il change (en plus de SIGHUP)
+    pub watch: bool,
+}
+
+/// Socket de contrôle (voir `control`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlConfig {
+    /// Chemin du socket; chaîne vide = désactivé. Pris en compte au démarrage seulement
+    pub socket: String,
+}
+
+/// Tension appliquée entre les `safe-points`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoltageConfig {
+    pub interpolation: Interpolation,
+}
+
+impl GovernorConfig {
+    /// Lit et valide un fichier de configuration TOML
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, IoError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Valide une configuration TOML déjà lue
+    ///
+    /// Une erreur de syntaxe indique sa ligne et sa colonne.
+    pub fn parse(content: &str) -> Result<Self, IoError> {
+        toml::from_str(content)
+            .map_err(|e| IoError::new(ErrorKind::InvalidInput, e.to_string().trim_end()))
+    }
+
+    /// Masque des `burst-samples` derniers échantillons (None si désactivé)
+    pub fn burst_mask(&self) -> Option<u64> {
+        match self.timing.burst_samples {
+            0 => None,
+            v @ 1..64 => Some(!(u64::MAX << v)),
+            _ => Some(u64::MAX),
+        }
+    }
+
+    /// Courbe de tension des `safe-points`, selon `voltage.interpolation`
+    pub fn voltage_curve(&self) -> VoltageCurve {
+        VoltageCurve::new(self.safe_points.clone(), self.voltage.interpolation)
+    }
+
+    /// Plus basse fréquence sûre, en MHz
+    pub fn min_safe_freq(&self) -> u16 {
+        *self.safe_points.first_key_value().unwrap().0
+    }
+
+    /// Plus haute fréquence sûre, en MHz
+    pub fn max_safe_freq(&self) -> u16 {
+        *self.safe_points.last_key_value().unwrap().0
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct RawConfig {
+    device: Option<Value>,
+    policy: Option<Value>,
+    #[serde(default)]
+    timing: RawTiming,
+    #[serde(default)]
+    frequency_thresholds: RawFrequencyThresholds,
+    #[serde(default)]
+    load_target: RawLoadTarget,
+    #[serde(default)]
+    pid: RawPid,
+    #[serde(default)]
+    reload: RawReload,
+    #[serde(default)]
+    control: RawControl,
+    #[serde(default)]
+    metrics: RawMetrics,
+    #[serde(default)]
+    voltage: RawVoltage,
+    #[serde(default)]
+    verify: RawVerify,
+    #[serde(default)]
+    trace: RawTrace,
+    #[serde(default)]
+    paths: RawPaths,
+    #[serde(default)]
+    processes: RawProcesses,
+    #[serde(default)]
+    profiles: RawProfiles,
+    game: Option<Value>,
+    safe_points: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct RawTiming {
+    #[serde(default)]
+    intervals: RawIntervals,
+    #[serde(default)]
+    ramp_rates: RawRampRates,
+    burst_samples: Option<Value>,
+    window_samples: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawIntervals {
+    sample: Option<Value>,
+    adjust: Option<Value>,
+    finetune: Option<Value>,
+    log: Option<Value>,
+    optimize: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawRampRates {
+    normal: Option<Value>,
+    burst: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawFrequencyThresholds {
+    adjust: Option<Value>,
+    finetune: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawLoadTarget {
+    upper: Option<Value>,
+    lower: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawPid {
+    setpoint: Option<Value>,
+    kp: Option<Value>,
+    ki: Option<Value>,
+    kd: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawReload {
+    watch: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawControl {
+    socket: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawMetrics {
+    enabled: Option<Value>,
+    listen: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawVoltage {
+    interpolation: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawVerify {
+    enabled: Option<Value>,
+    tolerance: Option<Value>,
+    retries: Option<Value>,
+    backoff: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct RawTrace {
+    enabled: Option<Value>,
+    path: Option<Value>,
+    max_size: Option<Value>,
+    max_files: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawPaths {
+    proc: Option<Value>,
+    sys: Option<Value>,
+    dev: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawProcesses {
+    exclude: Option<Value>,
+    allow: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawProfiles {
+    path: Option<Value>,
+}
+
+fn integer(v: Option<&Value>) -> Result<i64, Cow<'static, str>> {
+    v.ok_or("is missing")?
+        .as_integer()
+        .ok_or("must be an integer".into())
+}
+
+fn number(v: Option<&Value>) -> Result<f64, Cow<'static, str>> {
+    let v = v.ok_or("is missing")?;
+    v.as_float()
+        .or_else(|| v.as_integer().map(|v| v as f64))
+        .ok_or("must be a number".into())
+}
+
+fn positive(v: i64) -> Result<i64, Cow<'static, str>> {
+    v.is_positive()
+        .then_some(v)
+        .ok_or("must be positive".into())
+}
+
+fn not_negative(v: i64) -> Result<i64, Cow<'static, str>> {
+    (!v.is_negative())
+        .then_some(v)
+        .ok_or("must not be negative".into())
+}
+
+fn fits<T: TryFrom<i64>>(v: i64, max: impl std::fmt::Display) -> Result<T, Cow<'static, str>> {
+    T::try_from(v).map_err(|_| format!("cannot be greater than {max}").into())
+}
+
+impl TryFrom<RawConfig> for GovernorConfig {
+    type Error = IoError;
+
+    fn try_from(raw: RawConfig) -> Result<Self, Self::Error> {
+        let device = match raw.device {
+            None => None,
+            Some(Value::String(address)) => {
+                parse_pci_address(&address)?;
+                Some(address)
+            }
+            Some(_) => {
+                return Err(IoError::new(
+                    ErrorKind::InvalidInput,
+                    "device must be a string",
+                ));
+            }
+        };
+
+        // optional, the historical ramps unless asked for
+        let policy = match raw.policy {
+            None => Policy::Threshold,
+            Some(v) => v.as_str().and_then(Policy::from_name).unwrap_or_else(|| {
+                println!(
+                    "policy must be \"threshold\", \"pid\" or \"process-aware\", replaced with \
+                    the default of \"threshold\""
+                );
+                Policy::Threshold
+            }),
+        };
+
+        let intervals = &raw.timing.intervals;
+        // us
+        let sample: u16 = integer(intervals.sample.as_ref())
+            .and_then(positive)
+            .and_then(|v| fits(v, u16::MAX))
+            .unwrap_or_else(|s| {
+                println!("timing.intervals.sample {s}, replaced with the default value of 2 ms");
+                2000
+            });
+        // us
+        let adjust: u64 = integer(intervals.adjust.as_ref())
+            .and_then(positive)
+            .and_then(|v| {
+                (v >= i64::from(sample))
+                    .then_some(v)
+                    .ok_or("must be at least as high as timing.intervals.sample".into())
+            })
+            .and_then(|v| fits(v, u64::MAX))
+            .unwrap_or_else(|s| {
+                println!(
+                    "timing.intervals.adjust {s}, replaced with the default of \
+                    10 * timing.intervals.sample"
+                );
+                10 * u64::from(sample)
+            });
+        // us
+        let finetune: u64 = integer(intervals.finetune.as_ref())
+            .and_then(positive)
+            .and_then(|v| {
+                (v >= i64::from(sample))
+                    .then_some(v)
+                    .ok_or("must be at least as high as timing.intervals.sample".into())
+            })
+            .and_then(|v| fits(v, u64::MAX))
+            .unwrap_or_else(|s| {
+                println!(
+                    "timing.intervals.finetune {s}, replaced with the default of \
+                    50_000 * timing.intervals.adjust"
+                );
+                50_000 * u64::from(sample)
+            });
+        // seconds
+        let log: u64 = integer(intervals.log.as_ref())
+            .and_then(not_negative)
+            .and_then(|v| fits(v, u64::MAX))
+            .unwrap_or_else(|s| {
+                println!("timing.intervals.log {s}, replaced with the default of 60 second");
+                60
+            });
+        // us - optimization interval
+        let optimize: u64 = integer(intervals.optimize.as_ref())
+            .and_then(not_negative)
+            .and_then(|v| fits(v, u64::MAX))
+            .unwrap_or_else(|s| {
+                println!(
+                    "timing.intervals.optimize {s}, replaced with the default of 30 seconds (0 = disabled)"
+                );
+                30_000_000
+            });
+        // samples - window size for GPU load moving average
+        let window_samples: usize = integer(raw.timing.window_samples.as_ref())
+            .and_then(positive)
+            .and_then(|v| fits(v, usize::MAX))
+            .unwrap_or_else(|s| {
+                println!("timing.window-samples {s}, replaced with the default of 100 samples");
+                100
+            });
+
+        // samples
+        let burst_samples: u8 =
+            match integer(raw.timing.burst_samples.as_ref()).and_then(not_negative) {
+                Err(s) => {
+                    println!("timing.burst-samples {s}, replaced with the default of 48");
+                    48
+                }
+                Ok(v @ 0..=64) => v as u8,
+                Ok(_) => {
+                    println!("timing.burst-samples can be at most 64, clamping");
+                    64
+                }
+            };
+
+        let ramp_rates = &raw.timing.ramp_rates;
+        // MHz/ms
+        let ramp_normal = number(ramp_rates.normal.as_ref())
+            .and_then(|v| {
+                v.is_sign_positive()
+                    .then_some(v)
+                    .ok_or("must have positive sign".into())
+            })
+            .map(|v| v as f32)
+            .unwrap_or_else(|s| {
+                println!(
+                    "timing.ramp-rates.normal {s}, replaced with the default value of \
+                    1 MHz/ms"
+                );
+                1.0
+            });
+        // MHz/ms
+        let ramp_burst = number(ramp_rates.burst.as_ref())
+            .and_then(|v| {
+                v.is_sign_positive()
+                    .then_some(v)
+                    .ok_or("must have positive sign".into())
+            })
+            .map(|v| v as f32)
+            .and_then(|v| {
+                (v > ramp_normal || burst_samples == 0).then_some(v).ok_or(
+                    "must, if bursting is active, be greater than timing.ramp-rates.normal \
+                    (if you want to turn bursting off, set timing.burst-samples = 0)"
+                        .into(),
+                )
+            })
+            .unwrap_or_else(|s| {
+                println!(
+                    "timing.ramp-rates.burst {s}, replaced with the default value of \
+                    50 * timing.ramp-rates.normal"
+                );
+                50.0 * ramp_normal
+            });
+
+        let freq_threshs = &raw.frequency_thresholds;
+        // MHz
+        let finetune_change: u16 = integer(freq_threshs.finetune.as_ref())
+            .and_then(positive)
+            .and_then(|v| fits(v, u16::MAX))
+            .unwrap_or_else(|s| {
+                println!(
+                    "frequency-thresholds.finetune {s}, replaced with the default of \
+                    10 MHz"
+                );
+                10
+            });
+        // MHz
+        let adjust_change: u16 = integer(freq_threshs.adjust.as_ref())
+            .and_then(positive)
+            .and_then(|v| fits(v, u16::MAX))
+            .unwrap_or_else(|s| {
+                println!(
+                    "frequency-thresholds.adjust {s}, replaced with the default of \
+                    10 * frequency-thresholds.finetune"
+                );
+                10 * finetune_change
+            });
+
+        let percentage = |v: f64| {
+            (0.0..=100.0)
+                .contains(&v)
+                .then_some(v)
+                .ok_or("must be between 0 and 100".into())
+        };
+        // percentage (0-100)
+        let upper = number(raw.load_target.upper.as_ref())
+            .and_then(percentage)
+            .map(|v| v as f32)
+            .unwrap_or_else(|s| {
+                println!(
+                    "load-target.upper {s}, replaced with the default value of \
+                    90%"
+                );
+                90.0
+            });
+        // percentage (0-100)
+        let lower = number(raw.load_target.lower.as_ref())
+            .and_then(percentage)
+            .map(|v| v as f32)
+            .unwrap_or_else(|s| {
+                println!(
+                    "load-target.lower {s}, replaced with the default value of \
+                    upper - 10%"
+                );
+                (upper - 10.0).max(0.0)
+            });
+        let lower = if lower > upper {
+            println!("load-target.lower can't be greater than load-target.upper, clamping");
+            upper
+        } else {
+            lower
+        };
+
+        // percentage (0-100)
+        let setpoint = match raw.pid.setpoint {
+            None => (upper + lower) / 2.0,
+            v => number(v.as_ref())
+                .and_then(percentage)
+                .map(|v| v as f32)
+                .unwrap_or_else(|s| {
+                    println!(
+                        "pid.setpoint {s}, replaced with the default of the middle of \
+                        load-target"
+                    );
+                    (upper + lower) / 2.0
+                }),
+        };
+        let gain = |v: Option<&Value>, name: &str, default: f32| -> f32 {
+            match v {
+                None => default,
+                v => number(v)
+                    .and_then(|v| (v >= 0.0).then_some(v).ok_or("must not be negative".into()))
+                    .map(|v| v as f32)
+                    .unwrap_or_else(|s| {
+                        println!("pid.{name} {s}, replaced with the default of {default}");
+                        default
+                    }),
+            }
+        };
+        // MHz/%
+        let kp = gain(raw.pid.kp.as_ref(), "kp", 10.0);
+        // MHz/(%·s)
+        let ki = gain(raw.pid.ki.as_ref(), "ki", 20.0);
+        // MHz·s/%
+        let kd = gain(raw.pid.kd.as_ref(), "kd", 0.0);
+
+        // optional, off unless asked for
+        let watch = match raw.reload.watch {
+            None => false,
+            Some(v) => v.as_bool().unwrap_or_else(|| {
+                println!("reload.watch must be a boolean, replaced with the default of false");
+                false
+            }),
+        };
+
+        // optional, on by default
+        let socket = match raw.control.socket {
+            None => DEFAULT_SOCKET_PATH.to_string(),
+            Some(Value::String(path)) => path,
+            Some(_) => {
+                println!(
+                    "control.socket must be a string, replaced with the default of \
+                    {DEFAULT_SOCKET_PATH}"
+                );
+                DEFAULT_SOCKET_PATH.to_string()
+            }
+        };
+
+        // optional, off unless asked for
+        let metrics_enabled = match raw.metrics.enabled {
+            None => false,
+            Some(v) => v.as_bool().unwrap_or_else(|| {
+                println!("metrics.enabled must be a boolean, replaced with the default of false");
+                false
+            }),
+        };
+        // loopback unless asked for
+        let metrics_listen = match raw.metrics.listen {
+            None => DEFAULT_METRICS_LISTEN.to_string(),
+            Some(Value::String(addr)) if addr.parse::<std::net::SocketAddr>().is_ok() => addr,
+            Some(_) => {
+                println!(
+                    "metrics.listen must be an address and port such as \"127.0.0.1:9617\", \
+                    replaced with the default of {DEFAULT_METRICS_LISTEN}"
+                );
+                DEFAULT_METRICS_LISTEN.to_string()
+            }
+        };
+
+        // optional, conservative unless asked for
+        let interpolation = match raw.voltage.interpolation {
+            None => Interpolation::Step,
+            Some(v) => v
+                .as_str()
+                .and_then(Interpolation::from_name)
+                .unwrap_or_else(|| {
+                    println!(
+                        "voltage.interpolation must be \"step\" or \"linear\", replaced with \
+                        the default of \"step\""
+                    );
+                    Interpolation::Step
+                }),
+        };
+
+        // optional, off unless asked for
+        let verify_enabled = match raw.verify.enabled {
+            None => false,
+            Some(v) => v.as_bool().unwrap_or_else(|| {
+                println!("verify.enabled must be a boolean, replaced with the default of false");
+                false
+            }),
+        };
+        // MHz
+        let verify_tolerance: u16 = match raw.verify.tolerance {
+            None => 25,
+            v => integer(v.as_ref())
+                .and_then(not_negative)
+                .and_then(|v| fits(v, u16::MAX))
+                .unwrap_or_else(|s| {
+                    println!("verify.tolerance {s}, replaced with the default of 25 MHz");
+                    25
+                }),
+        };
+        let verify_retries: u8 = match raw.verify.retries {
+            None => 3,
+            v => integer(v.as_ref())
+                .and_then(not_negative)
+                .and_then(|v| {
+                    (v <= 10)
+                        .then_some(v)
+                        .ok_or("cannot be greater than 10".into())
+                })
+                .and_then(|v| fits(v, u8::MAX))
+                .unwrap_or_else(|s| {
+                    println!("verify.retries {s}, replaced with the default of 3");
+                    3
+                }),
+        };
+        // ms
+        let verify_backoff: u64 = match raw.verify.backoff {
+            None => 20,
+            v => integer(v.as_ref())
+                .and_then(not_negative)
+                .and_then(|v| fits(v, u64::MAX))
+                .and_then(|v| {
+                    (v <= MAX_BACKOFF_MS)
+                        .then_some(v)
+                        .ok_or("cannot be greater than 1000".into())
+                })
+                .unwrap_or_else(|s| {
+                    println!("verify.backoff {s}, replaced with the default of 20 ms");
+                    20
+                }),
+        };
+        let mut verify = VerifyConfig {
+            enabled: verify_enabled,
+            tolerance: verify_tolerance,
+            retries: verify_retries,
+            backoff: verify_backoff,
+        };
+        // Retried in the setter thread: bounded so frequency control never stalls
+        let retry_budget = Duration::from_millis(MAX_RETRY_MS);
+        if verify.total_delay() > retry_budget {
+            while verify.total_delay() > retry_budget {
+                verify.retries -= 1;
+            }
+            println!(
+                "verify.retries with verify.backoff waits more than {MAX_RETRY_MS} ms per frequency, \
+                lowered to {}",
+                verify.retries
+            );
+        }
+
+        // optional, off unless asked for (or through the control socket)
+        let trace_enabled = match raw.trace.enabled {
+            None => false,
+            Some(v) => v.as_bool().unwrap_or_else(|| {
+                println!("trace.enabled must be a boolean, replaced with the default of false");
+                false
+            }),
+        };
+        let trace_path = match raw.trace.path {
+            None => DEFAULT_TRACE_PATH.to_string(),
+            Some(Value::String(path)) if !path.is_empty() => path,
+            Some(_) => {
+                println!(
+                    "trace.path must be a non-empty string, replaced with the default of \
+                    {DEFAULT_TRACE_PATH}"
+                );
+                DEFAULT_TRACE_PATH.to_string()
+            }
+        };
+        // MiB
+        let trace_max_size: u64 = match raw.trace.max_size {
+            None => 64,
+            v => integer(v.as_ref())
+                .and_then(positive)
+                .and_then(|v| fits(v, u64::MAX))
+                .unwrap_or_else(|s| {
+                    println!("trace.max-size {s}, replaced with the default of 64 MiB");
+                    64
+                }),
+        };
+        let trace_max_files: u8 = match raw.trace.max_files {
+            None => 4,
+            v => integer(v.as_ref())
+                .and_then(not_negative)
+                .and_then(|v| {
+                    (v <= 99)
+                        .then_some(v)
+                        .ok_or("cannot be greater than 99".into())
+                })
+                .and_then(|v| fits(v, u8::MAX))
+                .unwrap_or_else(|s| {
+                    println!("trace.max-files {s}, replaced with the default of 4");
+                    4
+                }),
+        };
+
+        // optional, only read at startup
+        let default_paths = SystemPaths::default();
+        let root = |name, raw: Option<Value>, default: PathBuf| match raw {
+            None => default,
+            Some(Value::String(path)) if Path::new(&path).is_absolute() => PathBuf::from(path),
+            Some(_) => {
+                println!(
+                    "paths.{name} must be an absolute path, replaced with the default of {}",
+                    default.display()
+                );
+                default
+            }
+        };
+        let paths = SystemPaths {
+            proc: root("proc", raw.paths.proc, default_paths.proc),
+            sys: root("sys", raw.paths.sys, default_paths.sys),
+            dev: root("dev", raw.paths.dev, default_paths.dev),
+        };
+
+        // optional, the built-in exclusions unless replaced
+        let processes = ProcessRules {
+            exclude: match raw.processes.exclude {
+                None => builtin_exclusions(),
+                Some(value) => parse_process_rules("exclude", &value).unwrap_or_else(|| {
+                    println!(
+                        "processes.exclude must be an array of tables, replaced with the \
+                        built-in list"
+                    );
+                    builtin_exclusions()
+                }),
+            },
+            allow: match raw.processes.allow {
+                None => Vec::new(),
+                Some(value) => parse_process_rules("allow", &value).unwrap_or_else(|| {
+                    println!("processes.allow must be an array of tables, ignored");
+                    Vec::new()
+                }),
+            },
+        };
+
+        // optional, read when the process-aware policy opens its database
+        let profiles = ProfilesConfig {
+            path: match raw.profiles.path {
+                None => None,
+                Some(Value::String(path)) if Path::new(&path).is_absolute() => {
+                    Some(PathBuf::from(path))
+                }
+                Some(_) => {
+                    println!("profiles.path must be an absolute path, replaced with the default");
+                    None
+                }
+            },
+        };
+
+        // optional, applied by the process-aware policy when a game starts
+        let games = match raw.game {
+            None => Vec::new(),
+            Some(value) => parse_games(&value).unwrap_or_else(|| {
+                println!("game must be an array of tables, ignored");
+                Vec::new()
+            }),
+        };
+
+        let safe_points = match raw.safe_points {
+            Some(value) => parse_safe_points(&value)?,
+            None => {
+                println!(
+                    "safe-points undefined, using conservative defaults:\n\
+                    * 350 MHz @ 700 mV\n\
+                    * 2000 MHz @ 1000 mV"
+                );
+                BTreeMap::from([(350, 700), (2000, 1000)])
+            }
+        };
+
+        Ok(Self {
+            device,
+            policy,
+            timing: TimingConfig {
+                intervals: Intervals {
+                    sample,
+                    adjust,
+                    finetune,
+                    log,
+                    optimize,
+                },
+                ramp_rates: RampRates {
+                    normal: ramp_normal,
+                    burst: ramp_burst,
+                },
+                burst_samples,
+                window_samples,
+            },
+            frequency_thresholds: FrequencyThresholds {
+                adjust: adjust_change,
+                finetune: finetune_change,
+            },
+            load_target: LoadTarget { upper, lower },
+            pid: PidConfig {
+                setpoint,
+                kp,
+                ki,
+                kd,
+            },
+            reload: ReloadConfig { watch },
+            control: ControlConfig { socket },
+            metrics: MetricsConfig {
+                enabled: metrics_enabled,
+                listen: metrics_listen,
+            },
+            voltage: VoltageConfig { interpolation },
+            verify,
+            trace: TraceConfig {
+                enabled: trace_enabled,
+                path: trace_path,
+                max_size: trace_max_size,
+                max_files: trace_max_files,
+            },
+            paths,
+            processes,
+            profiles,
+            games,
+            safe_points,
+        })
+    }
+}
+
+/// Règles `processes.<key>`; None si ce n'est pas un tableau
+///
+/// Une règle invalide est ignorée avec un message, les autres sont gardées.
+fn parse_process_rules(key: &str, value: &Value) -> Option<Vec<ProcessRule>> {
+    let mut rules = Vec::new();
+    for (i, t) in value.as_array()?.iter().enumerate() {
+        let rule = t
+            .as_table()
+            .ok_or_else(|| "must be a table".to_string())
+            .and_then(|t| {
+                let field = match t.get("match") {
+                    None => Field::Name,
+                    Some(v) => v.as_str().and_then(Field::from_name).ok_or_else(|| {
+                        "match must be \"name\", \"comm\", \"exe\", \"cmdline\" or \"game\""
+                            .to_string()
+                    })?,
+                };
+                let pattern = match (t.get("glob"), t.get("regex")) {
+                    (Some(Value::String(glob)), None) => Pattern::Glob(glob.clone()),
+                    (None, Some(Value::String(regex))) => Pattern::Regex(regex.clone()),
+                    _ => return Err("must have either a glob or a regex string".to_string()),
+                };
+                ProcessRule::new(field, pattern).map_err(|e| e.to_string())
+            });
+        match rule {
+            Ok(rule) => rules.push(rule),
+            Err(e) => println!("processes.{key}[{i}] {e}, ignored"),
+        }
+    }
+    Some(rules)
+}
+
+/// Sections `[[game]]`; None si ce n'est pas un tableau
+///
+/// Une section invalide est ignorée avec un message, les autres sont gardées.
+fn parse_games(value: &Value) -> Option<Vec<GameOverride>> {
+    let mut games = Vec::new();
+    for (i, t) in value.as_array()?.iter().enumerate() {
+        let game = t
+            .as_table()
+            .ok_or_else(|| "must be a table".to_string())
+            .and_then(|t| {
+                let selector = match (t.get("name"), t.get("glob"), t.get("steam-app-id")) {
+                    (Some(Value::String(name)), None, None) => GameSelector::Name(name.clone()),
+                    (None, Some(Value::String(glob)), None) => GameSelector::Glob(glob.clone()),
+                    (None, None, Some(Value::Integer(app_id))) if *app_id > 0 => {
+                        GameSelector::SteamAppId(*app_id as u64)
+                    }
+                    _ => {
+                        return Err("must have either a name, a glob or a positive steam-app-id"
+                            .to_string());
+                    }
+                };
+                let mut game = GameOverride::new(selector).map_err(|e| e.to_string())?;
+
+                let freq = |key: &str| match t.get(key) {
+                    None => Ok(None),
+                    Some(Value::Integer(v)) if *v > 0 && *v <= i64::from(u16::MAX) => {
+                        Ok(Some(*v as u16))
+                    }
+                    Some(_) => Err(format!("{key} must be a frequency in MHz")),
+                };
+                game.freq = freq("freq")?;
+                game.min_freq = freq("min-freq")?;
+                game.max_freq = freq("max-freq")?;
+                if let (Some(min), Some(max)) = (game.min_freq, game.max_freq)
+                    && min > max
+                {
+                    return Err("min-freq cannot be greater than max-freq".to_string());
+                }
+                game.policy = match t.get("policy") {
+                    None => None,
+                    Some(v) => Some(v.as_str().and_then(Policy::from_name).ok_or_else(|| {
+                        format!(
+                            "policy must be one of: {}",
+                            Policy::ALL.map(|p| p.name()).join(", ")
+                        )
+                    })?),
+                };
+                game.learning = match t.get("learning") {
+                    None => true,
+                    Some(Value::Boolean(learning)) => *learning,
+                    Some(_) => return Err("learning must be a boolean".to_string()),
+                };
+                if game.freq.is_none()
+                    && game.min_freq.is_none()
+                    && game.max_freq.is_none()
+                    && game.policy.is_none()
+                    && game.learning
+                {
+                    return Err(
+                        "must set freq, min-freq, max-freq, policy or learning = false".to_string(),
+                    );
+                }
+                Ok(game)
+            });
+        match game {
+            Ok(game) => games.push(game),
+            Err(e) => println!("game[{i}] {e}, ignored"),
+        }
+    }
+    Some(games)
+}
+
+// MHz, mV
+fn parse_safe_points(value: &Value) -> Result<BTreeMap<u16, u16>, IoError> {
+    let invalid = |msg: String| IoError::new(ErrorKind::InvalidInput, msg);
+
+    let array = value
+        .as_array()
+        .ok_or_else(|| invalid("safe-points must be an array".to_string()))?;
+    if array.is_empty() {
+        return Err(invalid("safe-points must not be empty".to_string()));
+    }
+    let mut safe_points = BTreeMap::new();
+    for (i, t) in array.iter().enumerate() {
+        let t = t
+            .as_table()
+            .ok_or_else(|| invalid(format!("safe-points[{i}] must be a table")))?;
+        let field = |name: &str| -> Result<u16, IoError> {
+            let v = t
+                .get(name)
+                .ok_or_else(|| invalid(format!("safe-points[{i}].{name} must exist")))?
+                .as_integer()
+                .ok_or_else(|| invalid(format!("safe-points[{i}].{name} must be an integer")))?;
+            u16::try_from(v).map_err(|_| {
+                invalid(format!(
+                    "safe-points[{i}].{name} must be between 0 and {} inclusive",
+                    u16::MAX
+                ))
+            })
+        };
+
+        // MHz
+        let frequency = field("frequency")?;
+        // mV
+        let voltage = field("voltage")?;
+
+        if safe_points.insert(frequency, voltage).is_some() {
+            return Err(invalid(format!(
+                "multiple supposedly safe voltages for {frequency} MHz"
+            )));
+        }
+    }
+    let mut highest_pair = (0, 0);
+    for (frequency, voltage) in &safe_points {
+        let pair = (*voltage, *frequency);
+        if pair < highest_pair {
+            return Err(invalid(format!(
+                "supposedly safe voltage {} mV for {} MHz is higher than \
+                {voltage} mV for {frequency} MHz",
+                highest_pair.0, highest_pair.1,
+            )));
+        } else {
+            highest_pair = pair;
+        }
+    }
+    Ok(safe_points)
+}
+
+/// `safe-points` est écrit comme un tableau de tables, comme dans le fichier d'origine
+fn serialize_safe_points<S>(points: &BTreeMap<u16, u16>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    #[derive(Serialize)]
+    struct SafePoint {
+        frequency: u16,
+        voltage: u16,
+    }
+
+    points
+        .iter()
+        .map(|(&frequency, &voltage)| SafePoint { frequency, voltage })
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert_eq!(config.timing.intervals.sample, 2000);
+        assert_eq!(config.timing.intervals.adjust, 20_000);
+        assert_eq!(config.timing.intervals.finetune, 100_000_000);
+        assert_eq!(config.timing.burst_samples, 48);
+        assert_eq!(config.timing.ramp_rates.burst, 50.0);
+        assert_eq!(config.frequency_thresholds.adjust, 100);
+        assert_eq!(config.load_target.lower, 80.0);
+        assert_eq!(
+            config.safe_points,
+            BTreeMap::from([(350, 700), (2000, 1000)])
+        );
+    }
+
+    #[test]
+    fn test_default_config_file() {
+        let config = GovernorConfig::parse(include_str!("../default-config.toml")).unwrap();
+        assert_eq!(config.timing.intervals.sample, 10_000);
+        assert_eq!(config.timing.intervals.optimize, 100_000);
+        assert_eq!(config.timing.burst_samples, 0);
+        assert_eq!(config.burst_mask(), None);
+        assert_eq!(config.load_target.upper, 98.0);
+        assert_eq!(config.device, None);
+        assert_eq!(config.min_safe_freq(), 350);
+        assert_eq!(config.max_safe_freq(), 2000);
+    }
+
+    #[test]
+    fn test_invalid_values_fall_back() {
+        let config = GovernorConfig::parse(
+            "[timing]\nburst-samples = 100\n\
+            [timing.intervals]\nsample = -1\nadjust = 10\n\
+            [load-target]\nupper = 50\nlower = 60\n",
+        )
+        .unwrap();
+        assert_eq!(config.timing.intervals.sample, 2000);
+        assert_eq!(config.timing.intervals.adjust, 20_000);
+        assert_eq!(config.timing.burst_samples, 64);
+        assert_eq!(config.burst_mask(), Some(u64::MAX));
+        assert_eq!(config.load_target.lower, 50.0);
+
+        assert_eq!(
+            fits::<u16>(70_000, u16::MAX),
+            Err("cannot be greater than 65535".into())
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let err = GovernorConfig::parse("[timing]\nburst-samples = = 4\n").unwrap_err();
+        assert!(err.to_string().contains("line 2, column"), "{err}");
+    }
+
+    #[test]
+    fn test_safe_points_errors() {
+        let err = GovernorConfig::parse("safe-points = []").unwrap_err();
+        assert_eq!(err.to_string(), "safe-points must not be empty");
+
+        let err = GovernorConfig::parse("[[safe-points]]\nfrequency = 350\n").unwrap_err();
+        assert_eq!(err.to_string(), "safe-points[0].voltage must exist");
+
+        let err = GovernorConfig::parse(
+            "[[safe-points]]\nfrequency = 350\nvoltage = 900\n\
+            [[safe-points]]\nfrequency = 2000\nvoltage = 800\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "supposedly safe voltage 900 mV for 350 MHz is higher than 800 mV for 2000 MHz"
+        );
+    }
+
+    #[test]
+    fn test_device() {
+        let config = GovernorConfig::parse("device = \"0000:03:00.0\"").unwrap();
+        assert_eq!(config.device.as_deref(), Some("0000:03:00.0"));
+        assert!(GovernorConfig::parse("device = \"3:0.0\"").is_err());
+        assert!(GovernorConfig::parse("device = 3").is_err());
+    }
+
+    #[test]
+    fn test_voltage_interpolation() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert_eq!(config.voltage.interpolation, Interpolation::Step);
+        assert_eq!(config.voltage_curve().voltage(351), Some(1000));
+
+        let config = GovernorConfig::parse("[voltage]\ninterpolation = \"linear\"").unwrap();
+        assert_eq!(config.voltage_curve().voltage(1175), Some(850));
+
+        let config = GovernorConfig::parse("[voltage]\ninterpolation = \"cubic\"").unwrap();
+        assert_eq!(config.voltage.interpolation, Interpolation::Step);
+    }
+
+    #[test]
+    fn test_verify() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert!(!config.verify.enabled);
+        assert_eq!(config.verify.retries, 3);
+
+        let config =
+            GovernorConfig::parse("[verify]\nenabled = true\nretries = 50\nbackoff = 5\n").unwrap();
+        assert!(config.verify.enabled);
+        assert_eq!(config.verify.retries, 3);
+        assert_eq!(config.verify.backoff, 5);
+
+        let config = GovernorConfig::parse("[verify]\nbackoff = 10000\n").unwrap();
+        assert_eq!(config.verify.backoff, 20);
+
+        // 500 + 4 × 1000 ms fit in the retry budget, a fifth retry would not
+        let config = GovernorConfig::parse("[verify]\nretries = 10\nbackoff = 500\n").unwrap();
+        assert_eq!(config.verify.retries, 4);
+    }
+
+    #[test]
+    fn test_pid() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert_eq!(config.policy, Policy::Threshold);
+        assert_eq!(config.pid.setpoint, 85.0);
+        assert_eq!(config.pid.kd, 0.0);
+
+        let config =
+            GovernorConfig::parse("policy = \"pid\"\n[pid]\nsetpoint = 75\nkp = 5.5\nki = -1\n")
+                .unwrap();
+        assert_eq!(config.policy, Policy::Pid);
+        assert_eq!(config.pid.setpoint, 75.0);
+        assert_eq!(config.pid.kp, 5.5);
+        assert_eq!(config.pid.ki, 20.0);
+
+        let config = GovernorConfig::parse("policy = \"process-aware\"").unwrap();
+        assert_eq!(config.policy, Policy::ProcessAware);
+        let config = GovernorConfig::parse("policy = \"fuzzy\"").unwrap();
+        assert_eq!(config.policy, Policy::Threshold);
+    }
+
+    #[test]
+    fn test_trace() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert!(!config.trace.enabled);
+        assert_eq!(config.trace.path, DEFAULT_TRACE_PATH);
+        assert_eq!(config.trace.max_size, 64);
+
+        let config = GovernorConfig::parse(
+            "[trace]\nenabled = true\npath = \"/tmp/t.csv\"\nmax-size = 0\nmax-files = 0\n",
+        )
+        .unwrap();
+        assert!(config.trace.enabled);
+        assert_eq!(config.trace.path, "/tmp/t.csv");
+        assert_eq!(config.trace.max_size, 64);
+        assert_eq!(config.trace.max_files, 0);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert!(!config.metrics.enabled);
+        assert_eq!(config.metrics.listen, "127.0.0.1:9617");
+
+        let config =
+            GovernorConfig::parse("[metrics]\nenabled = true\nlisten = \"[::]:9100\"\n").unwrap();
+        assert!(config.metrics.enabled);
+        assert_eq!(config.metrics.listen, "[::]:9100");
+        let config = GovernorConfig::parse("[metrics]\nlisten = \"localhost\"\n").unwrap();
+        assert_eq!(config.metrics.listen, DEFAULT_METRICS_LISTEN);
+    }
+
+    #[test]
+    fn test_paths() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert_eq!(config.paths, SystemPaths::default());
+
+        let config =
+            GovernorConfig::parse("[paths]\nproc = \"/host/proc\"\nsys = \"host/sys\"\n").unwrap();
+        assert_eq!(config.paths.proc, Path::new("/host/proc"));
+        assert_eq!(config.paths.sys, Path::new("/sys"));
+        assert_eq!(config.paths.dev, Path::new("/dev"));
+    }
+
+    #[test]
+    fn test_processes() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert_eq!(config.processes, ProcessRules::default());
+
+        let config = GovernorConfig::parse(
+            "[[processes.exclude]]\nglob = \"kwin*\"\n\
+            [[processes.exclude]]\nmatch = \"cmdline\"\nregex = \"--type=(gpu|renderer)\"\n\
+            [[processes.exclude]]\nmatch = \"pid\"\nglob = \"1\"\n\
+            [[processes.exclude]]\nregex = \"(\"\n\
+            [[processes.allow]]\nmatch = \"game\"\nglob = \"Hades\"\nregex = \"Hades\"\n\
+            [[processes.allow]]\nmatch = \"game\"\nglob = \"Celeste\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.processes.exclude.len(), 2);
+        assert_eq!(config.processes.exclude[1].field(), Field::Cmdline);
+        assert_eq!(config.processes.allow.len(), 1);
+        assert_eq!(
+            config.processes.allow[0].pattern(),
+            &Pattern::Glob("Celeste".to_string())
+        );
+
+        // Une liste vide n'exclut rien
+        let config = GovernorConfig::parse("[processes]\nexclude = []\n").unwrap();
+        assert!(config.processes.exclude.is_empty());
+        let config = GovernorConfig::parse("[processes]\nexclude = \"steam\"\n").unwrap();
+        assert_eq!(config.processes.exclude, builtin_exclusions());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let config = GovernorConfig::parse("").unwrap();
+        assert_eq!(config.profiles.path, None);
+        let config =
+            GovernorConfig::parse("[profiles]\npath = \"/srv/csg/profiles.json\"\n").unwrap();
+        assert_eq!(
+            config.profiles.database_path(),
+            Path::new("/srv/csg/profiles.json")
+        );
+        let config = GovernorConfig::parse("[profiles]\npath = \"profiles.json\"\n").unwrap();
+        assert_eq!(config.profiles.path, None);
+    }
+
+    #[test]
+    fn test_games() {
+        let config = GovernorConfig::parse(
+            "[[game]]\nsteam-app-id = 1145360\nfreq = 1200\n\
+            [[game]]\nglob = \"*dolphin*\"\nmax-freq = 900\npolicy = \"pid\"\n\
+            [[game]]\nname = \"vkcube\"\nlearning = false\n\
+            [[game]]\nname = \"a\"\nglob = \"b\"\nfreq = 1000\n\
+            [[game]]\nname = \"a\"\nmin-freq = 1000\nmax-freq = 900\n\
+            [[game]]\nname = \"a\"\npolicy = \"fast\"\n\
+            [[game]]\nname = \"a\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.games.len(), 3);
+        assert_eq!(
+            config.games[0].selector(),
+            &GameSelector::SteamAppId(1145360)
+        );
+        assert_eq!(config.games[0].freq, Some(1200));
+        assert_eq!(
+            (config.games[1].max_freq, config.games[1].policy),
+            (Some(900), Some(Policy::Pid))
+        );
+        assert!(!config.games[2].learning);
+        assert!(config.games[1].matches("/usr/bin/dolphin-emu", None));
+
+        let dumped = toml::to_string(&config).unwrap();
+        assert_eq!(GovernorConfig::parse(&dumped).unwrap(), config);
+
+        let config = GovernorConfig::parse("game = 1\n").unwrap();
+        assert!(config.games.is_empty());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let config = GovernorConfig::parse(include_str!("../default-config.toml")).unwrap();
+        let dumped = toml::to_string(&config).unwrap();
+        assert_eq!(GovernorConfig::parse(&dumped).unwrap(), config);
+    }
+}
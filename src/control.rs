@@ -0,0 +1,376 @@
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Socket de contrôle par défaut
+pub const DEFAULT_SOCKET_PATH: &str = "/run/cyan-skillfish-governor.sock";
+
+/// Durée d'un `pin` sans `duration_secs`
+pub const DEFAULT_PIN_SECS: u64 = 60;
+
+/// État publié par le gouverneur en cours d'exécution
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GovernorStatus {
+    /// MHz
+    pub current_freq: u16,
+    pub target_freq: u16,
+    pub min_freq: u16,
+    pub max_freq: u16,
+    /// mV écrits avec la dernière fréquence appliquée
+    pub voltage: u16,
+    /// Pourcentage (0-100)
+    pub load_percent: f32,
+    pub policy: String,
+    pub mode: String,
+    pub active_process: Option<String>,
+    /// Avancement de l'apprentissage (0-1), hors apprentissage: None
+    pub learning_progress: Option<f32>,
+    /// Profils de la base, pour les politiques qui en ont
+    pub learned_profiles: Option<usize>,
+    pub pinned_freq: Option<u16>,
+    /// Raisons du dernier changement de fréquence (comme dans le log `[FREQ]`)
+    pub reasons: Vec<String>,
+    /// Fréquences écrites depuis le démarrage
+    pub frequency_writes: u64,
+    /// Fréquences jamais constatées à la relecture (voir `verify`)
+    pub verify_mismatches: u64,
+    pub verify_last_error: Option<String>,
+    /// Enregistrement des échantillons (voir `trace`)
+    pub trace: bool,
+    /// Enregistrements arrêtés sur une erreur d'écriture
+    pub trace_errors: u64,
+}
+
+/// Requête du protocole: un objet JSON par ligne
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum Request {
+    Status,
+    /// Fige la fréquence pendant `duration_secs` (défaut: 60 s)
+    Pin {
+        freq: u16,
+        duration_secs: Option<u64>,
+    },
+    Unpin,
+    SetPolicy {
+        policy: String,
+    },
+    /// Démarre ou arrête l'enregistrement des échantillons
+    Trace {
+        enabled: bool,
+    },
+}
+
+/// Réponse du protocole: un objet JSON par ligne
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<GovernorStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn ok(status: GovernorStatus) -> Self {
+        Self {
+            ok: true,
+            status: Some(status),
+            error: None,
+        }
+    }
+
+    fn error(error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            status: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// État partagé entre le gouverneur et le socket de contrôle
+pub struct ControlState {
+    status: Mutex<GovernorStatus>,
+    pin: Mutex<Option<(u16, Instant)>>,
+    policies: Vec<String>,
+}
+
+impl ControlState {
+    /// `policies` liste les politiques acceptées par `set-policy`, la première est active
+    pub fn new(policies: &[&str]) -> Self {
+        let status = GovernorStatus {
+            policy: policies.first().copied().unwrap_or_default().to_string(),
+            ..Default::default()
+        };
+        Self {
+            status: Mutex::new(status),
+            pin: Mutex::new(None),
+            policies: policies.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Met à jour l'état publié
+    pub fn update_status(&self, f: impl FnOnce(&mut GovernorStatus)) {
+        f(&mut self.status.lock().unwrap());
+    }
+
+    pub fn status(&self) -> GovernorStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.pinned_freq = self.pinned_freq();
+        status
+    }
+
+    /// Politique choisie (par la config ou via `set-policy`)
+    pub fn policy(&self) -> String {
+        self.status.lock().unwrap().policy.clone()
+    }
+
+    /// Enregistrement demandé (par la config ou via `trace`)
+    pub fn tracing(&self) -> bool {
+        self.status.lock().unwrap().trace
+    }
+
+    /// Fréquence figée par `pin`, tant qu'elle n'a pas expiré
+    pub fn pinned_freq(&self) -> Option<u16> {
+        let mut pin = self.pin.lock().unwrap();
+        match *pin {
+            Some((freq, until)) if Instant::now() < until => Some(freq),
+            Some(_) => {
+                println!("📌 Fréquence figée expirée, reprise du gouverneur");
+                *pin = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Exécute une requête
+    pub fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::Status => {}
+            Request::Pin {
+                freq,
+                duration_secs,
+            } => {
+                let (min_freq, max_freq) = {
+                    let status = self.status.lock().unwrap();
+                    (status.min_freq, status.max_freq)
+                };
+                // Plage publiée avec le premier état du gouverneur
+                if max_freq == 0 {
+                    return Response::error("governor not ready yet, retry shortly");
+                }
+                if !(min_freq..=max_freq).contains(&freq) {
+                    return Response::error(format!(
+                        "frequency {freq} MHz is outside the safe range [{min_freq} - {max_freq}] MHz"
+                    ));
+                }
+                let duration = Duration::from_secs(duration_secs.unwrap_or(DEFAULT_PIN_SECS));
+                let Some(until) = Instant::now().checked_add(duration) else {
+                    return Response::error(format!("pin duration {duration:?} is too long"));
+                };
+                println!("📌 Fréquence figée à {freq} MHz pendant {duration:?}");
+                *self.pin.lock().unwrap() = Some((freq, until));
+            }
+            Request::Unpin => {
+                *self.pin.lock().unwrap() = None;
+            }
+            Request::SetPolicy { policy } => {
+                if !self.policies.contains(&policy) {
+                    return Response::error(format!(
+                        "unknown policy \"{policy}\", available: {}",
+                        self.policies.join(", ")
+                    ));
+                }
+                println!("🔀 Politique: {policy}");
+                self.status.lock().unwrap().policy = policy;
+            }
+            Request::Trace { enabled } => {
+                self.status.lock().unwrap().trace = enabled;
+            }
+        }
+        Response::ok(self.status())
+    }
+
+    /// Exécute une ligne du protocole
+    pub fn handle_line(&self, line: &str) -> Response {
+        match serde_json::from_str(line) {
+            Ok(request) => self.handle(request),
+            Err(e) => Response::error(format!("invalid request: {e}")),
+        }
+    }
+}
+
+/// Sert le socket de contrôle dans un thread dédié
+///
+/// Un socket laissé par une instance arrêtée est remplacé; si une instance
+/// y répond encore, `ErrorKind::AddrInUse`: deux gouverneurs se disputeraient
+/// les horloges.
+pub fn serve(path: &Path, state: Arc<ControlState>) -> Result<JoinHandle<()>, IoError> {
+    if UnixStream::connect(path).is_ok() {
+        return Err(IoError::new(
+            ErrorKind::AddrInUse,
+            format!(
+                "another governor is already listening on {}",
+                path.display()
+            ),
+        ));
+    }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let listener = UnixListener::bind(path)?;
+    // Le socket permet de changer les fréquences: réservé au propriétaire (root)
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+    Ok(std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let state = Arc::clone(&state);
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle_client(stream, &state) {
+                            eprintln!("⚠️  Erreur client socket de contrôle: {e}");
+                        }
+                    });
+                }
+                Err(e) => eprintln!("⚠️  Erreur socket de contrôle: {e}"),
+            }
+        }
+    }))
+}
+
+fn handle_client(stream: UnixStream, state: &ControlState) -> Result<(), IoError> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = serde_json::to_string(&state.handle_line(&line))?;
+        writer.write_all(response.as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> ControlState {
+        let state = ControlState::new(&["threshold", "pid"]);
+        state.update_status(|s| {
+            s.min_freq = 350;
+            s.max_freq = 2000;
+        });
+        state
+    }
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let state = state();
+        let response = state.handle_line(r#"{"cmd":"pin","freq":1200}"#);
+        assert!(response.ok);
+        assert_eq!(response.status.unwrap().pinned_freq, Some(1200));
+
+        let response = state.handle_line(r#"{"cmd":"pin","freq":2500}"#);
+        assert!(!response.ok);
+        assert_eq!(state.pinned_freq(), Some(1200));
+
+        state.handle_line(r#"{"cmd":"unpin"}"#);
+        assert_eq!(state.pinned_freq(), None);
+
+        state.handle_line(r#"{"cmd":"pin","freq":1200,"duration_secs":0}"#);
+        assert_eq!(state.pinned_freq(), None);
+
+        // Une durée hors de portée d'`Instant` est refusée, sans paniquer
+        let response = state.handle_line(&format!(
+            r#"{{"cmd":"pin","freq":1200,"duration_secs":{}}}"#,
+            u64::MAX
+        ));
+        assert!(response.error.unwrap().contains("too long"));
+        assert_eq!(state.pinned_freq(), None);
+    }
+
+    #[test]
+    fn test_pin_before_first_status() {
+        let state = ControlState::new(&["threshold"]);
+        let response = state.handle_line(r#"{"cmd":"pin","freq":1200}"#);
+        assert_eq!(
+            response.error.as_deref(),
+            Some("governor not ready yet, retry shortly")
+        );
+        assert_eq!(state.pinned_freq(), None);
+    }
+
+    #[test]
+    fn test_set_policy() {
+        let state = state();
+        assert_eq!(state.policy(), "threshold");
+        assert!(
+            state
+                .handle_line(r#"{"cmd":"set-policy","policy":"pid"}"#)
+                .ok
+        );
+        assert_eq!(state.policy(), "pid");
+        assert!(!state.handle_line(r#"{"cmd":"set-policy","policy":"x"}"#).ok);
+        assert!(!state.handle_line("status").ok);
+    }
+
+    #[test]
+    fn test_trace() {
+        let state = state();
+        assert!(!state.tracing());
+        let response = state.handle_line(r#"{"cmd":"trace","enabled":true}"#);
+        assert!(response.status.unwrap().trace);
+        assert!(state.tracing());
+        assert!(!state.handle_line(r#"{"cmd":"trace"}"#).ok);
+    }
+
+    #[test]
+    fn test_socket_round_trip() {
+        let path = std::env::temp_dir().join(format!("csg-control-{}.sock", std::process::id()));
+        let state = Arc::new(state());
+        state.update_status(|s| s.current_freq = 800);
+        serve(&path, Arc::clone(&state)).unwrap();
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.write_all(b"{\"cmd\":\"status\"}\n").unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        let response: Response = serde_json::from_str(&line).unwrap();
+        assert_eq!(response.status.unwrap().current_freq, 800);
+
+        // A second instance must not take over a live socket …
+        let err = serve(&path, Arc::clone(&state)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        stream.write_all(b"{\"cmd\":\"status\"}\n").unwrap();
+        line.clear();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        assert!(line.contains("\"current_freq\":800"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stale_socket_replaced() {
+        let path =
+            std::env::temp_dir().join(format!("csg-control-stale-{}.sock", std::process::id()));
+        // … but replaces one nobody listens on anymore
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        serve(&path, Arc::new(state())).unwrap();
+        UnixStream::connect(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use libdrm_amdgpu_sys::PCI::BUS_INFO;
+
+pub const AMD_VENDOR_ID: u16 = 0x1002;
+pub const CYAN_SKILLFISH_DEVICE_ID: u16 = 0x13fe;
+
+/// Racine sysfs par défaut
+pub const DEFAULT_SYSFS_ROOT: &str = "/sys";
+
+/// Périphérique PCI relevé dans sysfs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciDevice {
+    pub location: BUS_INFO,
+    pub vendor: u16,
+    pub device: u16,
+}
+
+impl PciDevice {
+    pub fn is_cyan_skillfish(&self) -> bool {
+        self.vendor == AMD_VENDOR_ID && self.device == CYAN_SKILLFISH_DEVICE_ID
+    }
+}
+
+impl fmt::Display for PciDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{:04x}:{:04x}]",
+            self.location, self.vendor, self.device
+        )
+    }
+}
+
+/// Échec de la recherche du GPU Cyan Skillfish
+#[derive(Debug)]
+pub enum DiscoveryError {
+    /// L'adresse `device` de la config n'est pas au format `0000:xx:yy.z`
+    InvalidAddress(String),
+    /// Aucun Cyan Skillfish trouvé; `found` liste les périphériques AMD présents
+    NotFound {
+        found: Vec<PciDevice>,
+    },
+    /// Le périphérique demandé explicitement n'est pas un Cyan Skillfish
+    WrongDevice(PciDevice),
+    Io(PathBuf, IoError),
+}
+
+impl fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress(address) => write!(
+                f,
+                "invalid PCI address \"{address}\", expected domain:bus:device.function \
+                (e.g. 0000:01:00.0)"
+            ),
+            Self::NotFound { found } if found.is_empty() => {
+                write!(f, "Cyan Skillfish GPU not found, no AMD PCI device present")
+            }
+            Self::NotFound { found } => {
+                write!(f, "Cyan Skillfish GPU not found, AMD PCI devices present:")?;
+                for device in found {
+                    write!(f, "\n  * {device}")?;
+                }
+                Ok(())
+            }
+            Self::WrongDevice(device) => write!(
+                f,
+                "{device} is not a Cyan Skillfish GPU ({AMD_VENDOR_ID:04x}:{CYAN_SKILLFISH_DEVICE_ID:04x})"
+            ),
+            Self::Io(path, e) => write!(f, "cannot read {}: {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for DiscoveryError {}
+
+impl From<DiscoveryError> for IoError {
+    fn from(e: DiscoveryError) -> Self {
+        let kind = match &e {
+            DiscoveryError::InvalidAddress(_) => ErrorKind::InvalidInput,
+            DiscoveryError::NotFound { .. } | DiscoveryError::WrongDevice(_) => ErrorKind::NotFound,
+            DiscoveryError::Io(_, e) => e.kind(),
+        };
+        IoError::new(kind, e)
+    }
+}
+
+/// Parse une adresse PCI complète (`0000:01:00.0`)
+pub fn parse_pci_address(address: &str) -> Result<BUS_INFO, DiscoveryError> {
+    let invalid = || DiscoveryError::InvalidAddress(address.to_string());
+    let (domain, rest) = address.split_once(':').ok_or_else(invalid)?;
+    let (bus, rest) = rest.split_once(':').ok_or_else(invalid)?;
+    let (dev, func) = rest.split_once('.').ok_or_else(invalid)?;
+    let lengths_ok = domain.len() == 4 && bus.len() == 2 && dev.len() == 2 && func.len() == 1;
+    if !lengths_ok {
+        return Err(invalid());
+    }
+    Ok(BUS_INFO {
+        domain: u16::from_str_radix(domain, 16).map_err(|_| invalid())?,
+        bus: u8::from_str_radix(bus, 16).map_err(|_| invalid())?,
+        dev: u8::from_str_radix(dev, 16).map_err(|_| invalid())?,
+        func: u8::from_str_radix(func, 16).map_err(|_| invalid())?,
+    })
+}
+
+fn read_id(path: PathBuf) -> Result<u16, DiscoveryError> {
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| DiscoveryError::Io(path.clone(), e))?;
+    let id = content.trim().trim_start_matches("0x");
+    u16::from_str_radix(id, 16).map_err(|_| {
+        DiscoveryError::Io(
+            path,
+            IoError::new(
+                ErrorKind::InvalidData,
+                format!("invalid PCI ID \"{}\"", content.trim()),
+            ),
+        )
+    })
+}
+
+fn read_device(sysfs_root: &Path, location: BUS_INFO) -> Result<PciDevice, DiscoveryError> {
+    let dir = sysfs_root
+        .join("bus/pci/devices")
+        .join(location.to_string());
+    Ok(PciDevice {
+        location,
+        vendor: read_id(dir.join("vendor"))?,
+        device: read_id(dir.join("device"))?,
+    })
+}
+
+/// Liste les périphériques PCI de `<sysfs_root>/bus/pci/devices`, triés par adresse
+pub fn scan_pci_devices(sysfs_root: &Path) -> Result<Vec<PciDevice>, DiscoveryError> {
+    let dir = sysfs_root.join("bus/pci/devices");
+    let entries = std::fs::read_dir(&dir).map_err(|e| DiscoveryError::Io(dir.clone(), e))?;
+
+    let mut devices = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Ok(location) = parse_pci_address(&name.to_string_lossy()) else {
+            continue;
+        };
+        // Un périphérique illisible ne doit pas empêcher de trouver les autres
+        if let Ok(device) = read_device(sysfs_root, location) {
+            devices.push(device);
+        }
+    }
+    devices.sort_by_key(|d| {
+        (
+            d.location.domain,
+            d.location.bus,
+            d.location.dev,
+            d.location.func,
+        )
+    });
+    Ok(devices)
+}
+
+/// Trouve le GPU Cyan Skillfish
+///
+/// Avec `device` (clé `device` de la config), seule cette adresse est
+/// vérifiée; sinon le premier Cyan Skillfish trouvé sur le bus est retenu.
+pub fn find_cyan_skillfish(
+    sysfs_root: &Path,
+    device: Option<&str>,
+) -> Result<BUS_INFO, DiscoveryError> {
+    if let Some(address) = device {
+        let found = read_device(sysfs_root, parse_pci_address(address)?)?;
+        return if found.is_cyan_skillfish() {
+            Ok(found.location)
+        } else {
+            Err(DiscoveryError::WrongDevice(found))
+        };
+    }
+
+    let devices = scan_pci_devices(sysfs_root)?;
+    match devices.iter().find(|d| d.is_cyan_skillfish()) {
+        Some(found) => Ok(found.location),
+        None => Err(DiscoveryError::NotFound {
+            found: devices
+                .into_iter()
+                .filter(|d| d.vendor == AMD_VENDOR_ID)
+                .collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::FakeSystem;
+
+    fn sysfs(name: &str, devices: &[(&str, &str, &str)]) -> FakeSystem {
+        let system = FakeSystem::new(name);
+        for (address, vendor, device) in devices {
+            system.pci_device(address, vendor, device);
+        }
+        system
+    }
+
+    #[test]
+    fn test_finds_device_behind_bridge() {
+        let system = sysfs(
+            "pci-bridge",
+            &[
+                ("0000:00:08.1", "0x1022", "0x1635"),
+                ("0000:03:00.0", "0x1002", "0x13fe"),
+                ("0000:03:00.1", "0x1002", "0x1637"),
+            ],
+        );
+        let location = find_cyan_skillfish(&system.paths().sys, None).unwrap();
+        assert_eq!(location.to_string(), "0000:03:00.0");
+    }
+
+    #[test]
+    fn test_not_found_lists_amd_devices() {
+        let system = sysfs(
+            "pci-notfound",
+            &[
+                ("0000:00:08.1", "0x1022", "0x1635"),
+                ("0000:01:00.0", "0x1002", "0x73bf"),
+            ],
+        );
+        let err = find_cyan_skillfish(&system.paths().sys, None).unwrap_err();
+        assert!(matches!(&err, DiscoveryError::NotFound { found } if found.len() == 1));
+        assert!(err.to_string().contains("0000:01:00.0 [1002:73bf]"));
+    }
+
+    #[test]
+    fn test_explicit_device() {
+        let system = sysfs(
+            "pci-explicit",
+            &[
+                ("0000:01:00.0", "0x1002", "0x13fe"),
+                ("0000:02:00.0", "0x1002", "0x73bf"),
+            ],
+        );
+        let location = find_cyan_skillfish(&system.paths().sys, Some("0000:01:00.0")).unwrap();
+        assert_eq!(location.bus, 1);
+        assert!(matches!(
+            find_cyan_skillfish(&system.paths().sys, Some("0000:02:00.0")),
+            Err(DiscoveryError::WrongDevice(_))
+        ));
+        assert!(matches!(
+            find_cyan_skillfish(&system.paths().sys, Some("01:00.0")),
+            Err(DiscoveryError::InvalidAddress(_))
+        ));
+    }
+}
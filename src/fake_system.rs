@@ -0,0 +1,180 @@
+//! Répertoires temporaires et arborescences `/proc`, `/sys` et `/dev`
+//! factices pour les tests (de la bibliothèque comme des binaires)
+//!
+//! Compilé seulement pour les tests: ceux des binaires l'obtiennent par la
+//! fonctionnalité `test-support`, activée dans `[dev-dependencies]`.
+
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+use crate::system_paths::SystemPaths;
+
+/// Répertoire temporaire vide, propre à un test, supprimé à la destruction
+///
+/// `name` doit être unique parmi les tests: ils tournent en parallèle dans le
+/// même processus.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("csg-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Racine temporaire sous laquelle `proc`, `sys` et `dev` sont fabriqués
+///
+/// Supprimée à la destruction.
+pub struct FakeSystem {
+    _root: TempDir,
+    paths: SystemPaths,
+}
+
+impl FakeSystem {
+    pub fn new(name: &str) -> Self {
+        let root = TempDir::new(&format!("system-{name}"));
+        let paths = SystemPaths::under(root.path());
+        for dir in [&paths.proc, &paths.sys, &paths.dri()] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        // Nœuds du Cyan Skillfish (fichiers ordinaires)
+        for node in ["card0", "renderD128"] {
+            std::fs::write(paths.dri().join(node), "").unwrap();
+        }
+        // Entrées non numériques, ignorées par les parcours de processus
+        std::fs::create_dir_all(paths.proc.join("self")).unwrap();
+        std::fs::write(paths.proc.join("uptime"), "1.00 1.00\n").unwrap();
+        Self { _root: root, paths }
+    }
+
+    pub fn paths(&self) -> &SystemPaths {
+        &self.paths
+    }
+
+    /// `/sys/bus/pci/devices/<address>` avec ses identifiants (`0x1002` …)
+    pub fn pci_device(&self, address: &str, vendor: &str, device: &str) -> &Self {
+        let dir = self.paths.sys.join("bus/pci/devices").join(address);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("vendor"), format!("{vendor}\n")).unwrap();
+        std::fs::write(dir.join("device"), format!("{device}\n")).unwrap();
+        self
+    }
+
+    /// `/proc/sys/kernel/osrelease`
+    pub fn kernel_release(&self, release: &str) -> &Self {
+        let dir = self.paths.proc.join("sys/kernel");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("osrelease"), format!("{release}\n")).unwrap();
+        self
+    }
+
+    /// `/sys/module/amdgpu/parameters/<name>`
+    pub fn amdgpu_param(&self, name: &str, value: &str) -> &Self {
+        let dir = self.paths.sys.join("module/amdgpu/parameters");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(name), format!("{value}\n")).unwrap();
+        self
+    }
+
+    /// `/dev/dri/renderD128` de cette arborescence
+    pub fn render_node(&self) -> PathBuf {
+        self.paths.dri().join("renderD128")
+    }
+
+    /// Crée `/proc/<pid>` avec son `comm`
+    pub fn process(&self, pid: u32, comm: &str) -> FakeProcess {
+        let dir = self.paths.process(pid);
+        std::fs::create_dir_all(dir.join("fd")).unwrap();
+        std::fs::create_dir_all(dir.join("fdinfo")).unwrap();
+        std::fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+        FakeProcess {
+            dir,
+            render_node: self.render_node(),
+            next_fd: 0,
+        }
+    }
+}
+
+/// Processus de `FakeSystem`, complété par appels chaînés
+pub struct FakeProcess {
+    dir: PathBuf,
+    render_node: PathBuf,
+    next_fd: u32,
+}
+
+impl FakeProcess {
+    /// Arguments séparés par des octets nuls, comme `/proc/<pid>/cmdline`
+    pub fn cmdline(self, args: &[&str]) -> Self {
+        let content: String = args.iter().map(|a| format!("{a}\0")).collect();
+        std::fs::write(self.dir.join("cmdline"), content).unwrap();
+        self
+    }
+
+    /// Variables de `/proc/<pid>/environ`
+    pub fn environ(self, vars: &[(&str, &str)]) -> Self {
+        let content: String = vars.iter().map(|(k, v)| format!("{k}={v}\0")).collect();
+        std::fs::write(self.dir.join("environ"), content).unwrap();
+        self
+    }
+
+    /// `PPid` de `/proc/<pid>/status`
+    pub fn parent(self, ppid: u32) -> Self {
+        let status = format!("Name:\tfake\nState:\tS (sleeping)\nPPid:\t{ppid}\n");
+        std::fs::write(self.dir.join("status"), status).unwrap();
+        self
+    }
+
+    /// Lien `exe`; la cible n'a pas besoin d'exister
+    pub fn exe(self, target: &str) -> Self {
+        symlink(target, self.dir.join("exe")).unwrap();
+        self
+    }
+
+    /// Lien `cwd`; la cible n'a pas besoin d'exister
+    pub fn cwd(self, target: &str) -> Self {
+        symlink(target, self.dir.join("cwd")).unwrap();
+        self
+    }
+
+    /// Prochain fd, lien vers `target` accompagné de son fdinfo
+    pub fn fd(mut self, target: impl AsRef<Path>, fdinfo: &str) -> Self {
+        let fd = self.next_fd.to_string();
+        symlink(target, self.dir.join("fd").join(&fd)).unwrap();
+        std::fs::write(self.dir.join("fdinfo").join(&fd), fdinfo).unwrap();
+        self.next_fd += 1;
+        self
+    }
+
+    /// Prochain fd ouvert sur le nœud de rendu DRM
+    pub fn drm_fd(self, fdinfo: &str) -> Self {
+        let render_node = self.render_node.clone();
+        self.fd(render_node, fdinfo)
+    }
+
+    /// Prochain fd hors DRM (fichier ordinaire)
+    pub fn file_fd(self) -> Self {
+        self.fd("/dev/null", "pos:\t0\nflags:\t02\n")
+    }
+}
+
+/// Contenu de `testdata/fdinfo/<name>.txt`
+pub fn fdinfo_sample(name: &str) -> String {
+    let path = format!("{}/testdata/fdinfo/{name}.txt", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(path).unwrap()
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+
+use crate::policy::Policy;
+use crate::process_detection::GpuProcess;
+use crate::process_rules::{Field, Pattern, ProcessRule};
+use crate::profile_db::steam_app_id;
+
+/// Jeu visé par une section `[[game]]`, désigné comme dans la base de profils
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameSelector {
+    /// Nom du processus (celui de `read_process_name`)
+    Name(String),
+    /// Glob sur ce nom; sans `/`, sur son dernier composant
+    Glob(String),
+    /// Jeu Steam, quel que soit son nom (clé `steam:<appid>`)
+    SteamAppId(u64),
+}
+
+/// Réglages imposés à un jeu par la politique `process-aware` (section `[[game]]`)
+///
+/// Consultés avant la base de profils à chaque changement de processus.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GameOverride {
+    #[serde(flatten)]
+    selector: GameSelector,
+    #[serde(skip)]
+    glob: Option<ProcessRule>,
+    /// MHz, appliqués sans apprentissage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freq: Option<u16>,
+    /// MHz, bornes de toute fréquence choisie pour ce jeu
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_freq: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_freq: Option<u16>,
+    /// Politique à suivre pendant ce jeu à la place de l'apprentissage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<Policy>,
+    /// Faux: ni apprentissage ni réévaluation; sans profil connu, `threshold`
+    pub learning: bool,
+}
+
+impl GameOverride {
+    /// Sans réglage: à compléter par les champs publics
+    pub fn new(selector: GameSelector) -> Result<Self, regex::Error> {
+        let glob = match &selector {
+            GameSelector::Glob(glob) => {
+                Some(ProcessRule::new(Field::Name, Pattern::Glob(glob.clone()))?)
+            }
+            _ => None,
+        };
+        Ok(Self {
+            selector,
+            glob,
+            freq: None,
+            min_freq: None,
+            max_freq: None,
+            policy: None,
+            learning: true,
+        })
+    }
+
+    pub fn selector(&self) -> &GameSelector {
+        &self.selector
+    }
+
+    /// Processus de clé `key` (voir `profile_key`) et de nom `name`
+    pub fn matches(&self, key: &str, name: Option<&str>) -> bool {
+        let name = name.unwrap_or(key);
+        match &self.selector {
+            GameSelector::Name(expected) => name == expected || key == expected,
+            GameSelector::Glob(_) => self.glob.as_ref().is_some_and(|rule| {
+                rule.matches(&GpuProcess {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+            }),
+            GameSelector::SteamAppId(app_id) => steam_app_id(key) == Some(*app_id),
+        }
+    }
+
+    /// `freq` ramené entre `min-freq` et `max-freq`
+    pub fn clamp(&self, freq: u16) -> u16 {
+        freq.max(self.min_freq.unwrap_or(0))
+            .min(self.max_freq.unwrap_or(u16::MAX))
+    }
+}
+
+/// Première section `[[game]]` du processus, dans l'ordre de la config
+pub fn find<'a>(
+    games: &'a [GameOverride],
+    key: &str,
+    name: Option<&str>,
+) -> Option<&'a GameOverride> {
+    games.iter().find(|game| game.matches(key, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(selector: GameSelector) -> GameOverride {
+        GameOverride::new(selector).unwrap()
+    }
+
+    #[test]
+    fn test_matches() {
+        let by_name = game(GameSelector::Name("Hades/Hades".to_string()));
+        assert!(by_name.matches("steam:1145360", Some("Hades/Hades")));
+        assert!(by_name.matches("Hades/Hades", None));
+        assert!(!by_name.matches("Hades", None));
+
+        let by_glob = game(GameSelector::Glob("*dolphin*".to_string()));
+        assert!(by_glob.matches("/usr/bin/dolphin-emu", None));
+        assert!(!by_glob.matches("steam:1145360", Some("Hades/Hades")));
+
+        let by_app_id = game(GameSelector::SteamAppId(1145360));
+        assert!(by_app_id.matches("steam:1145360", Some("Hades/Hades")));
+        assert!(!by_app_id.matches("Hades/Hades", None));
+
+        let games = [by_glob, by_app_id, by_name];
+        assert_eq!(
+            find(&games, "steam:1145360", Some("Hades/Hades")).map(GameOverride::selector),
+            Some(&GameSelector::SteamAppId(1145360))
+        );
+        assert!(find(&games, "vkcube", None).is_none());
+    }
+
+    #[test]
+    fn test_clamp() {
+        let mut game = game(GameSelector::Name("dolphin-emu".to_string()));
+        assert_eq!(game.clamp(1500), 1500);
+        game.max_freq = Some(900);
+        assert_eq!(game.clamp(1500), 900);
+        game.min_freq = Some(600);
+        assert_eq!(game.clamp(350), 600);
+    }
+}
@@ -110,6 +110,18 @@ pub enum GovernorMode {
     Reevaluating, // Réévaluation d'un process connu
 }
 
+impl GovernorMode {
+    /// Nom du mode, tel qu'exposé par le socket de contrôle
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Applied => "applied",
+            Self::Learning => "learning",
+            Self::Reevaluating => "reevaluating",
+        }
+    }
+}
+
 /// Gouverneur adaptatif par processus
 pub struct ProcessAwareGovernor {
     pub current_freq: u16,
@@ -259,6 +271,18 @@ impl ProcessAwareGovernor {
         ))
     }
 
+    /// Avancement de l'apprentissage / réévaluation en cours (0-1)
+    pub fn learning_progress(&self) -> Option<f32> {
+        matches!(
+            self.mode,
+            GovernorMode::Learning | GovernorMode::Reevaluating
+        )
+        .then(|| {
+            let elapsed = self.mode_start.elapsed().as_secs_f32();
+            (elapsed / LEARNING_DURATION_SECS as f32).min(1.0)
+        })
+    }
+
     pub fn check_saturation(&self) -> bool {
         // Si on est en mode Applied et que la charge reste haute pendant 60 secondes
         matches!(self.mode, GovernorMode::Applied)
@@ -279,3 +303,63 @@ impl Default for ProcessAwareGovernor {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu_backend::{GpuBackend, MockBackend};
+    use crate::load_monitor::GpuLoadMonitor;
+
+    fn feed(governor: &mut ProcessAwareGovernor, backend: &dyn GpuBackend) {
+        let mut load_monitor = GpuLoadMonitor::new(SAMPLE_WINDOW_SIZE);
+        while let Ok(is_active) = backend.read_busy() {
+            load_monitor.add_sample(is_active);
+            if load_monitor.is_full() {
+                governor.add_load_sample(load_monitor.load_percent());
+            }
+        }
+    }
+
+    #[test]
+    fn test_learning_wants_more_under_full_load() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(
+            true,
+            SAMPLE_WINDOW_SIZE + LEARNING_HISTORY_SIZE,
+        ));
+
+        let mut governor = ProcessAwareGovernor::new();
+        governor.start_learning(MIN_FREQ_MHZ);
+        feed(&mut governor, &mock);
+
+        assert!(governor.should_increase());
+        assert!(!governor.should_decrease());
+    }
+
+    #[test]
+    fn test_learning_wants_less_when_idle() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(
+            false,
+            SAMPLE_WINDOW_SIZE + LEARNING_HISTORY_SIZE,
+        ));
+
+        let mut governor = ProcessAwareGovernor::new();
+        governor.start_learning(1000);
+        feed(&mut governor, &mock);
+
+        assert!(governor.should_decrease());
+        assert!(!governor.should_increase());
+    }
+
+    #[test]
+    fn test_learning_progress() {
+        let mut governor = ProcessAwareGovernor::new();
+        assert_eq!(governor.learning_progress(), None);
+        governor.start_learning(MIN_FREQ_MHZ);
+        assert!(governor.learning_progress().unwrap() < 0.1);
+        governor.mode_start -= Duration::from_secs(LEARNING_DURATION_SECS * 2);
+        assert_eq!(governor.learning_progress(), Some(1.0));
+        assert_eq!(governor.mode.as_str(), "learning");
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{Error as IoError, ErrorKind, Write};
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
+
+use crate::discovery::find_cyan_skillfish;
+use crate::kernel::{self, Quirk, Quirks};
+use crate::od_table::{OdTable, parse_unit};
+use crate::system_paths::SystemPaths;
+
+// cyan_skillfish.gfx1013.mmGRBM_STATUS
+pub const GRBM_STATUS_REG: u32 = 0x2004;
+// cyan_skillfish.gfx1013.mmGRBM_STATUS.GUI_ACTIVE (bit 31)
+pub const GUI_ACTIVE_BIT_MASK: u32 = 1 << 31;
+
+/// Accès matériel utilisé par les gouverneurs
+///
+/// Les méthodes prennent `&self` pour que la lecture de charge et l'écriture
+/// des fréquences puissent tourner dans deux threads distincts.
+pub trait GpuBackend: Send + Sync {
+    /// Lit le bit GUI_ACTIVE de GRBM_STATUS
+    fn read_busy(&self) -> Result<bool, IoError>;
+
+    /// Bornes d'horloge moteur rapportées par le noyau, en MHz (min, max)
+    fn clock_limits(&self) -> Result<(u64, u64), IoError>;
+
+    /// Prépare un point fréquence (MHz) / tension (mV), sans l'appliquer
+    fn apply(&self, freq: u16, voltage: u16) -> Result<(), IoError>;
+
+    /// Valide le dernier point préparé
+    fn commit(&self) -> Result<(), IoError>;
+
+    /// Fréquence moteur courante (`pp_dpm_sclk`), en MHz
+    fn read_sclk(&self) -> Result<u16, IoError>;
+
+    /// Contenu brut de `pp_od_clk_voltage`
+    fn read_od_state(&self) -> Result<String, IoError>;
+
+    /// Restaure et valide la table OD par défaut du noyau (commande `r`)
+    fn reset(&self) -> Result<(), IoError>;
+
+    /// Table `pp_od_clk_voltage` analysée
+    fn read_od_table(&self) -> Result<OdTable, IoError> {
+        OdTable::parse(&self.read_od_state()?)
+    }
+
+    /// Prépare puis valide un point fréquence / tension
+    fn set_frequency(&self, freq: u16, voltage: u16) -> Result<(), IoError> {
+        self.apply(freq, voltage)?;
+        self.commit()
+    }
+}
+
+/// Backend réel: registres via libdrm, fréquences via `pp_od_clk_voltage`
+///
+/// Les particularités du noyau (voir `kernel`) sont relevées à l'ouverture:
+/// sans overdrive les écritures échouent d'emblée avec un message explicite,
+/// et sans accès à GRBM_STATUS la charge est lue dans `gpu_busy_percent`.
+pub struct AmdgpuBackend {
+    dev_handle: DeviceHandle,
+    // Ouvert à la première écriture: les outils en lecture seule n'en ont pas besoin
+    pp_file: Mutex<Option<File>>,
+    quirks: Quirks,
+    // Reliquat de `gpu_busy_percent` pas encore rendu en échantillons actifs
+    busy_remainder: Mutex<u32>,
+}
+
+impl AmdgpuBackend {
+    /// Trouve le GPU Cyan Skillfish (ou vérifie l'adresse `device`) puis l'ouvre
+    ///
+    /// Les particularités du noyau sont détectées sous les mêmes racines
+    /// (voir `kernel::init`).
+    pub fn discover(paths: &SystemPaths, device: Option<&str>) -> Result<Self, IoError> {
+        kernel::init(paths);
+        let location = find_cyan_skillfish(&paths.sys, device)?;
+        Self::open(location)
+    }
+
+    /// Ouvre le GPU à l'emplacement PCI donné, sans vérifier son identité
+    pub fn open(location: BUS_INFO) -> Result<Self, IoError> {
+        let card = File::open(location.get_drm_render_path()?)?;
+        let (dev_handle, _, _) =
+            DeviceHandle::init(card.as_raw_fd()).map_err(IoError::from_raw_os_error)?;
+
+        let mut quirks = kernel::running().clone();
+        if dev_handle.read_mm_registers(GRBM_STATUS_REG).is_err() {
+            quirks.insert(Quirk::RegisterReadDenied);
+        }
+
+        Ok(Self {
+            dev_handle,
+            pp_file: Mutex::new(None),
+            quirks,
+            busy_remainder: Mutex::new(0),
+        })
+    }
+
+    /// Particularités du noyau, à afficher au démarrage
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    fn sysfs_path(&self) -> Result<PathBuf, IoError> {
+        self.dev_handle
+            .get_sysfs_path()
+            .map_err(IoError::from_raw_os_error)
+    }
+
+    fn pp_path(&self) -> Result<PathBuf, IoError> {
+        Ok(self.sysfs_path()?.join("pp_od_clk_voltage"))
+    }
+
+    /// Écrit les commandes d'un seul tenant, sans entrelacement avec un autre thread
+    fn write_pp(&self, commands: &[&str]) -> Result<(), IoError> {
+        if let Some(quirk) = [Quirk::NoOverdriveTable, Quirk::OverdriveDisabled]
+            .into_iter()
+            .find(|q| self.quirks.has(*q))
+        {
+            return Err(IoError::new(ErrorKind::Unsupported, quirk.description()));
+        }
+        let mut pp_file = self.pp_file.lock().unwrap();
+        if pp_file.is_none() {
+            *pp_file = Some(OpenOptions::new().write(true).open(self.pp_path()?)?);
+        }
+        let pp_file = pp_file.as_mut().unwrap();
+        for command in commands {
+            pp_file.write_all(command.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl GpuBackend for AmdgpuBackend {
+    fn read_busy(&self) -> Result<bool, IoError> {
+        if self.quirks.has(Quirk::RegisterReadDenied) {
+            let percent = std::fs::read_to_string(self.sysfs_path()?.join("gpu_busy_percent"))?
+                .trim()
+                .parse()
+                .map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+            return Ok(dither_busy(
+                &mut self.busy_remainder.lock().unwrap(),
+                percent,
+            ));
+        }
+        let res = self
+            .dev_handle
+            .read_mm_registers(GRBM_STATUS_REG)
+            .map_err(IoError::from_raw_os_error)?;
+        Ok((res & GUI_ACTIVE_BIT_MASK) != 0)
+    }
+
+    fn clock_limits(&self) -> Result<(u64, u64), IoError> {
+        let info = self
+            .dev_handle
+            .device_info()
+            .map_err(IoError::from_raw_os_error)?;
+        // given in kHz, we need MHz
+        Ok((info.min_engine_clock / 1000, info.max_engine_clock / 1000))
+    }
+
+    fn apply(&self, freq: u16, voltage: u16) -> Result<(), IoError> {
+        self.write_pp(&[&format!("vc 0 {freq} {voltage}")])
+    }
+
+    fn commit(&self) -> Result<(), IoError> {
+        self.write_pp(&["c"])
+    }
+
+    fn read_sclk(&self) -> Result<u16, IoError> {
+        parse_dpm_sclk(&std::fs::read_to_string(
+            self.sysfs_path()?.join("pp_dpm_sclk"),
+        )?)
+    }
+
+    fn read_od_state(&self) -> Result<String, IoError> {
+        std::fs::read_to_string(self.pp_path()?)
+    }
+
+    fn reset(&self) -> Result<(), IoError> {
+        self.write_pp(&["r", "c"])
+    }
+
+    fn set_frequency(&self, freq: u16, voltage: u16) -> Result<(), IoError> {
+        self.write_pp(&[&format!("vc 0 {freq} {voltage}"), "c"])
+    }
+}
+
+/// Niveau courant de `pp_dpm_sclk` (ligne marquée `*`), en MHz
+fn parse_dpm_sclk(content: &str) -> Result<u16, IoError> {
+    content
+        .lines()
+        .filter(|line| line.trim_end().ends_with('*'))
+        .find_map(|line| {
+            let (_, value) = line.split_once(':')?;
+            parse_unit(value.split_whitespace().next()?, "mhz")
+        })
+        .ok_or_else(|| {
+            IoError::new(
+                ErrorKind::InvalidData,
+                "pp_dpm_sclk has no current level (line marked '*')",
+            )
+        })
+}
+
+/// Convertit un pourcentage d'activité en échantillons actif / inactif
+///
+/// Le reliquat est reporté d'un appel à l'autre: sur 100 appels à 30 %, 30
+/// échantillons sont actifs, ce que la moyenne mobile des gouverneurs attend.
+fn dither_busy(remainder: &mut u32, percent: u32) -> bool {
+    *remainder += percent.min(100);
+    if *remainder >= 100 {
+        *remainder -= 100;
+        true
+    } else {
+        false
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    busy_trace: VecDeque<bool>,
+    pending: Option<(u16, u16)>,
+    committed: Vec<(u16, u16)>,
+}
+
+/// Backend en mémoire pour les tests et les machines sans Cyan Skillfish
+///
+/// Les lectures de charge consomment une trace d'échantillons; une fois la
+/// trace épuisée, `read_busy` renvoie `ErrorKind::UnexpectedEof`, ce qui
+/// termine proprement les boucles de contrôle.
+#[derive(Debug)]
+pub struct MockBackend {
+    limits: (u64, u64),
+    stock_point: (u16, u16),
+    ignore_writes: bool,
+    state: Mutex<MockState>,
+}
+
+impl MockBackend {
+    pub fn new(min_mhz: u64, max_mhz: u64) -> Self {
+        Self {
+            limits: (min_mhz, max_mhz),
+            stock_point: (0, 0),
+            ignore_writes: false,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Point fréquence / tension restauré par `reset` (défaut: 0 MHz @ 0 mV, « auto »)
+    pub fn with_stock_point(mut self, freq: u16, voltage: u16) -> Self {
+        self.stock_point = (freq, voltage);
+        self
+    }
+
+    /// Simule un noyau qui accepte `vc` sans effet: `read_sclk` reste au minimum
+    pub fn ignoring_writes(mut self) -> Self {
+        self.ignore_writes = true;
+        self
+    }
+
+    /// Ajoute des échantillons à la trace de charge
+    pub fn push_busy(&self, samples: impl IntoIterator<Item = bool>) {
+        self.state.lock().unwrap().busy_trace.extend(samples);
+    }
+
+    /// Points fréquence / tension validés, dans l'ordre
+    pub fn committed(&self) -> Vec<(u16, u16)> {
+        self.state.lock().unwrap().committed.clone()
+    }
+
+    /// Dernier point validé
+    pub fn current(&self) -> Option<(u16, u16)> {
+        self.state.lock().unwrap().committed.last().copied()
+    }
+}
+
+impl GpuBackend for MockBackend {
+    fn read_busy(&self) -> Result<bool, IoError> {
+        self.state
+            .lock()
+            .unwrap()
+            .busy_trace
+            .pop_front()
+            .ok_or_else(|| IoError::new(ErrorKind::UnexpectedEof, "mock busy trace exhausted"))
+    }
+
+    fn clock_limits(&self) -> Result<(u64, u64), IoError> {
+        Ok(self.limits)
+    }
+
+    fn apply(&self, freq: u16, voltage: u16) -> Result<(), IoError> {
+        self.state.lock().unwrap().pending = Some((freq, voltage));
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<(), IoError> {
+        let mut state = self.state.lock().unwrap();
+        let point = state
+            .pending
+            .take()
+            .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "commit without pending point"))?;
+        state.committed.push(point);
+        Ok(())
+    }
+
+    fn read_sclk(&self) -> Result<u16, IoError> {
+        let idle = u16::try_from(self.limits.0).map_err(IoError::other)?;
+        Ok(match self.current() {
+            Some((freq, _)) if freq != 0 && !self.ignore_writes => freq,
+            _ => idle,
+        })
+    }
+
+    fn read_od_state(&self) -> Result<String, IoError> {
+        let (freq, voltage) = self.current().unwrap_or(self.stock_point);
+        Ok(format!(
+            "OD_SCLK:\n0: {freq}Mhz *\nOD_VDDC:\n0: {voltage}mV *\nOD_RANGE:\n\
+            SCLK: {:>7}Mhz {:>10}Mhz\nVDDC: {:>7}mV  {:>10}mV\n",
+            self.limits.0, self.limits.1, 700, 1129
+        ))
+    }
+
+    fn reset(&self) -> Result<(), IoError> {
+        let mut state = self.state.lock().unwrap();
+        state.pending = None;
+        state.committed.push(self.stock_point);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_trace_and_commit() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy([true, false]);
+        assert!(mock.read_busy().unwrap());
+        assert!(!mock.read_busy().unwrap());
+        assert_eq!(
+            mock.read_busy().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        mock.apply(1000, 900).unwrap();
+        assert_eq!(mock.current(), None);
+        mock.commit().unwrap();
+        assert_eq!(mock.current(), Some((1000, 900)));
+        assert!(mock.commit().is_err());
+    }
+
+    #[test]
+    fn test_parse_dpm_sclk() {
+        assert_eq!(
+            parse_dpm_sclk("0: 350Mhz\n1: 1200Mhz *\n2: 2000Mhz\n").unwrap(),
+            1200
+        );
+        assert_eq!(parse_dpm_sclk("0: 1000Mhz \n1: 2000Mhz *\n").unwrap(), 2000);
+        assert!(parse_dpm_sclk("0: 350Mhz\n1: 2000Mhz\n").is_err());
+    }
+
+    #[test]
+    fn test_dither_busy_percent() {
+        let mut remainder = 0;
+        let busy = (0..100).filter(|_| dither_busy(&mut remainder, 30)).count();
+        assert_eq!(busy, 30);
+        assert!((0..10).all(|_| dither_busy(&mut remainder, 100)));
+        assert!(!(0..10).any(|_| dither_busy(&mut remainder, 0)));
+    }
+}
@@ -1,31 +1,198 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
-/// Vérifie si un lien symbolique pointe vers un device DRM
-pub fn is_drm_device(link_path: &Path) -> bool {
-    if let Ok(target) = std::fs::read_link(link_path) {
-        let target_str = target.to_string_lossy();
-        return target_str.contains("/dev/dri/");
+use crate::kernel;
+use crate::system_paths::SystemPaths;
+
+/// Moteurs dont l'activité signale un rendu (jeu, application 3D)
+///
+/// Les moteurs vidéo (`dec`, `enc`, `jpeg` …) et de copie (`dma`) sont
+/// mesurés mais ne font pas d'un processus un jeu.
+pub const RENDER_ENGINES: &[&str] = &["gfx", "compute"];
+
+/// Vérifie si un lien symbolique (`/proc/<pid>/fd/<fd>`) pointe vers un device DRM
+///
+/// La cible est le chemin vu par le processus: depuis un conteneur, un
+/// processus de l'hôte pointe vers `/dev/dri/renderD128`, pas vers
+/// `/host/dev/dri/…`. Seul le suffixe `dev/dri/<nœud>` est donc comparé, et
+/// le nœud doit exister sous `<dev>/dri`.
+pub fn is_drm_device(paths: &SystemPaths, link_path: &Path) -> bool {
+    let Ok(target) = std::fs::read_link(link_path) else {
+        return false;
+    };
+    match (target.parent(), target.file_name()) {
+        (Some(dir), Some(node)) => dir.ends_with("dev/dri") && paths.dri().join(node).exists(),
+        _ => false,
     }
-    false
 }
 
-/// Parse les cycles GPU depuis fdinfo
-pub fn parse_fdinfo_cycles(fdinfo_path: &str) -> u64 {
-    let Ok(content) = std::fs::read_to_string(fdinfo_path) else {
-        return 0;
-    };
+/// Statistiques DRM d'un descripteur de fichier (`/proc/<pid>/fdinfo/<fd>`)
+///
+/// Clés de `Documentation/gpu/drm-usage-stats.rst`; les autres lignes sont
+/// ignorées. Les tailles mémoire sont converties en octets.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FdInfo {
+    /// `drm-driver`
+    pub driver: Option<String>,
+    /// `drm-pdev`: adresse PCI du GPU
+    pub pdev: Option<String>,
+    /// `drm-client-id`: identique pour tous les fd d'un même contexte DRM
+    pub client_id: Option<u64>,
+    /// ns d'activité par moteur (`drm-engine-<moteur>`)
+    pub engines: BTreeMap<String, u64>,
+    /// Cycles par moteur (`drm-cycles-<moteur>`), une autre unité que `engines`
+    pub cycles: BTreeMap<String, u64>,
+    /// Octets par région (`drm-memory-<région>`, noyaux avant 6.9)
+    pub memory: BTreeMap<String, u64>,
+    /// Octets alloués par région (`drm-total-<région>`)
+    pub total: BTreeMap<String, u64>,
+    /// Octets résidents par région (`drm-resident-<région>`)
+    pub resident: BTreeMap<String, u64>,
+}
+
+impl FdInfo {
+    /// Lit un fdinfo; None s'il n'est pas lisible ou n'est pas celui d'un device DRM
+    ///
+    /// Sans compteurs d'activité (voir `kernel::Quirk::NoFdinfoUsage`),
+    /// `engines` et `cycles` restent vides.
+    pub fn read(path: impl AsRef<Path>) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let mut info = Self::parse(&content);
+        if !kernel::running().has_fdinfo_usage() {
+            info.engines.clear();
+            info.cycles.clear();
+        }
+        info.driver.is_some().then_some(info)
+    }
 
-    let mut total = 0u64;
-    for line in content.lines() {
-        if line.starts_with("drm-engine-") || line.starts_with("drm-cycles-") {
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() >= 2 {
-                let value_str = parts[1].trim().split_whitespace().next().unwrap_or("0");
-                if let Ok(value) = value_str.parse::<u64>() {
-                    total += value;
+    pub fn parse(content: &str) -> Self {
+        let mut info = Self::default();
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            let Some(key) = key.trim().strip_prefix("drm-") else {
+                continue;
+            };
+            if key == "driver" {
+                info.driver = Some(value.to_string());
+            } else if key == "pdev" {
+                info.pdev = Some(value.to_string());
+            } else if key == "client-id" {
+                info.client_id = value.parse().ok();
+            } else if let Some(engine) = key.strip_prefix("engine-") {
+                // `drm-engine-capacity-<moteur>` est un nombre d'anneaux, pas un temps
+                if !engine.starts_with("capacity-")
+                    && let Some(ns) = parse_number(value, "ns")
+                {
+                    info.engines.insert(engine.to_string(), ns);
+                }
+            } else if let Some(engine) = key.strip_prefix("cycles-") {
+                if let Some(cycles) = parse_number(value, "") {
+                    info.cycles.insert(engine.to_string(), cycles);
+                }
+            } else if let Some((kind, region)) = key.split_once('-') {
+                let map = match kind {
+                    "memory" => &mut info.memory,
+                    "total" => &mut info.total,
+                    "resident" => &mut info.resident,
+                    _ => continue,
+                };
+                if let Some(bytes) = parse_size(value) {
+                    map.insert(region.to_string(), bytes);
                 }
             }
         }
+        info
+    }
+
+    /// Identifie le client DRM, partagé par les fd dupliqués (`dup`, `fork`)
+    ///
+    /// Les identifiants ne sont uniques que par GPU, d'où `drm-pdev`.
+    pub fn client(&self) -> Option<(Option<&str>, u64)> {
+        Some((self.pdev.as_deref(), self.client_id?))
+    }
+
+    /// ns d'activité d'un moteur, 0 s'il n'est pas publié
+    pub fn engine_ns(&self, engine: &str) -> u64 {
+        self.engines.get(engine).copied().unwrap_or(0)
+    }
+}
+
+/// `<n>` ou `<n> <unit>`
+fn parse_number(value: &str, unit: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number = parts.next()?.parse().ok()?;
+    match parts.next() {
+        None => Some(number),
+        Some(u) if u == unit => Some(number),
+        Some(_) => None,
+    }
+}
+
+/// `<n>`, `<n> KiB` ou `<n> MiB`, en octets
+fn parse_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number: u64 = parts.next()?.parse().ok()?;
+    let unit = match parts.next() {
+        None => 1,
+        Some("KiB") => 1 << 10,
+        Some("MiB") => 1 << 20,
+        Some("GiB") => 1 << 30,
+        Some(_) => return None,
+    };
+    number.checked_mul(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &str) -> FdInfo {
+        let path = format!("{}/testdata/fdinfo/{name}.txt", env!("CARGO_MANIFEST_DIR"));
+        FdInfo::parse(&std::fs::read_to_string(path).unwrap())
+    }
+
+    #[test]
+    fn test_game() {
+        let info = sample("amdgpu_game");
+        assert_eq!(info.driver.as_deref(), Some("amdgpu"));
+        assert_eq!(info.client(), Some((Some("0000:01:00.0"), 42)));
+        assert_eq!(info.engine_ns("gfx"), 23_519_446_431);
+        assert_eq!(info.engine_ns("compute"), 21_384);
+        assert_eq!(info.engine_ns("dec"), 0);
+        assert_eq!(info.engines.len(), 5);
+        assert_eq!(info.memory["vram"], 148_060 * 1024);
+        assert_eq!(info.memory["gtt"], 10_476 * 1024);
+        assert!(info.total.is_empty());
+    }
+
+    #[test]
+    fn test_video_decode() {
+        let info = sample("amdgpu_video_decode");
+        assert_eq!(info.client_id, Some(57));
+        assert_eq!(info.engine_ns("dec"), 98_000_000_000);
+        assert_eq!(info.total["gtt"], 2 << 20);
+        assert_eq!(info.resident["vram"], 20_480 * 1024);
+        assert_eq!(info.total["cpu"], 0);
+        // drm-shared-* n'est pas retenu
+        assert!(info.memory.is_empty());
+    }
+
+    #[test]
+    fn test_without_usage_counters() {
+        // Noyau 5.15: format propre à amdgpu, sans clés drm-*
+        assert_eq!(sample("amdgpu_5_15"), FdInfo::default());
+    }
+
+    #[test]
+    fn test_units_are_not_mixed() {
+        let info = FdInfo::parse(
+            "drm-driver:\ti915\ndrm-engine-render:\t1000 ns\ndrm-engine-capacity-video:\t2\n\
+            drm-cycles-render:\t7\ndrm-engine-copy:\t3 ms\n",
+        );
+        assert_eq!(info.engines, BTreeMap::from([("render".to_string(), 1000)]));
+        assert_eq!(info.cycles, BTreeMap::from([("render".to_string(), 7)]));
     }
-    total
 }
@@ -1,17 +1,24 @@
 use std::collections::VecDeque;
 use std::fs::{self, File};
-use std::io::{Error as IoError, Write};
-use std::os::fd::AsRawFd;
+use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
-
-// Registre contenant le statut GRBM pour Cyan Skillfish (gfx1013)
-const GRBM_STATUS_REG: u32 = 0x2004;
-// Bit 31 indique si le GPU est actif
-const GUI_ACTIVE_BIT_MASK: u32 = 1 << 31;
+use crate::gpu_backend::{AmdgpuBackend, GpuBackend};
+use crate::system_paths::SystemPaths;
+
+/// Compteurs du daemon, exposés par l'exporteur de métriques
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SensorStats {
+    /// Pourcentage (0-100), dernière valeur écrite
+    pub load_percent: f64,
+    pub updates: u64,
+    pub read_errors: u64,
+    pub write_errors: u64,
+}
 
 /// Structure pour monitorer la charge GPU et l'exposer comme sonde système
 pub struct GpuSensor {
@@ -20,7 +27,8 @@ pub struct GpuSensor {
     samples: VecDeque<bool>,
     window_size: usize,
     active_count: u32,
-    dev_handle: DeviceHandle,
+    backend: Box<dyn GpuBackend>,
+    stats: Arc<Mutex<SensorStats>>,
 }
 
 impl GpuSensor {
@@ -35,51 +43,39 @@ impl GpuSensor {
         update_interval_ms: u64,
         window_size: usize,
     ) -> Result<Self, String> {
-        // Location PCI du GPU Cyan Skillfish (Steam Deck)
-        let location = BUS_INFO {
-            domain: 0,
-            bus: 1,
-            dev: 0,
-            func: 0,
-        };
-
-        // Vérifier que c'est bien un GPU Cyan Skillfish
-        let sysfs_path = location.get_sysfs_path();
-        let vendor = std::fs::read_to_string(sysfs_path.join("vendor"))
-            .map_err(|e| format!("Erreur lecture vendor: {}", e))?;
-        let device = std::fs::read_to_string(sysfs_path.join("device"))
-            .map_err(|e| format!("Erreur lecture device: {}", e))?;
-
-        if !((vendor == "0x1002\n") && (device == "0x13fe\n")) {
-            return Err(
-                "GPU Cyan Skillfish introuvable à l'emplacement PCI attendu (0000:01:00.0)"
-                    .to_string(),
-            );
-        }
+        // Recherche du GPU Cyan Skillfish sur le bus PCI
+        let backend = AmdgpuBackend::discover(&SystemPaths::default(), None)
+            .map_err(|e| format!("Erreur ouverture GPU: {}", e))?;
+
+        Ok(Self::with_backend(
+            sensor_path,
+            update_interval_ms,
+            window_size,
+            Box::new(backend),
+        ))
+    }
 
-        // Ouvrir le device DRM
-        let card = File::open(
-            location
-                .get_drm_render_path()
-                .map_err(|e| format!("Erreur get_drm_render_path: {:?}", e))?,
-        )
-        .map_err(|e| format!("Erreur ouverture DRM: {}", e))?;
-
-        let (dev_handle, _, _) = DeviceHandle::init(card.as_raw_fd()).map_err(|e| {
-            format!(
-                "Erreur init DeviceHandle: {:?}",
-                IoError::from_raw_os_error(e)
-            )
-        })?;
-
-        Ok(Self {
+    /// Créer un capteur sur un backend GPU arbitraire (ex: `MockBackend`)
+    pub fn with_backend(
+        sensor_path: &str,
+        update_interval_ms: u64,
+        window_size: usize,
+        backend: Box<dyn GpuBackend>,
+    ) -> Self {
+        Self {
             sensor_path: sensor_path.to_string(),
             update_interval: Duration::from_millis(update_interval_ms),
             samples: VecDeque::with_capacity(window_size),
             window_size,
             active_count: 0,
-            dev_handle,
-        })
+            backend,
+            stats: Arc::default(),
+        }
+    }
+
+    /// Compteurs partagés, mis à jour par `run_daemon`
+    pub fn stats(&self) -> Arc<Mutex<SensorStats>> {
+        Arc::clone(&self.stats)
     }
 
     /// Ajouter un échantillon d'activité GPU
@@ -108,19 +104,11 @@ impl GpuSensor {
         let samples_per_update = 50; // 50 échantillons = 100ms d'échantillonnage
 
         for _ in 0..samples_per_update {
-            // Lire le registre GRBM_STATUS
-            let status = self
-                .dev_handle
-                .read_mm_registers(GRBM_STATUS_REG)
-                .map_err(|e| {
-                    format!(
-                        "Erreur lecture registre: {:?}",
-                        IoError::from_raw_os_error(e)
-                    )
-                })?;
-
-            // Le bit 31 indique si le GPU est actif
-            let gpu_active = (status & GUI_ACTIVE_BIT_MASK) != 0;
+            // Lire le bit GUI_ACTIVE de GRBM_STATUS
+            let gpu_active = self
+                .backend
+                .read_busy()
+                .map_err(|e| format!("Erreur lecture registre: {:?}", e))?;
 
             // Ajouter l'échantillon
             self.add_sample(gpu_active);
@@ -204,8 +192,8 @@ impl GpuSensor {
         Ok(())
     }
 
-    /// Boucle principale du daemon
-    pub fn run_daemon(&mut self) -> Result<(), String> {
+    /// Boucle principale du daemon, jusqu'à ce que `running` passe à false
+    pub fn run_daemon(&mut self, running: &AtomicBool) -> Result<(), String> {
         println!("🚀 Démarrage du daemon GPU sensor");
         println!("📍 Fichier de sortie: {}", self.sensor_path);
         println!("⏱️  Intervalle: {:?}", self.update_interval);
@@ -215,38 +203,68 @@ impl GpuSensor {
         let _ = self.calculate_gpu_load();
         thread::sleep(Duration::from_millis(500));
 
-        loop {
+        while running.load(Ordering::SeqCst) {
             match self.calculate_gpu_load() {
                 Ok(load) => {
+                    let mut written = true;
                     // Écrire la valeur simple
                     if let Err(e) = self.write_sensor_value(load) {
                         eprintln!("❌ Erreur écriture sensor: {}", e);
+                        written = false;
                     }
 
                     // Écrire au format hwmon
                     if let Err(e) = self.write_hwmon_format(load) {
                         eprintln!("⚠️  Erreur écriture hwmon: {}", e);
+                        written = false;
                     } else {
                         println!("📊 GPU Load: {:.2}%", load);
                     }
+
+                    let mut stats = self.stats.lock().unwrap();
+                    stats.load_percent = load;
+                    if written {
+                        stats.updates += 1;
+                    } else {
+                        stats.write_errors += 1;
+                    }
                 }
                 Err(e) => {
                     eprintln!("❌ Erreur calcul charge: {}", e);
+                    self.stats.lock().unwrap().read_errors += 1;
                 }
             }
 
             thread::sleep(self.update_interval);
         }
+
+        println!("✓ Daemon GPU sensor arrêté");
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gpu_backend::MockBackend;
 
     #[test]
     fn test_sensor_creation() {
-        let sensor = GpuSensor::new("/tmp/test-sensor", 1000);
+        let sensor = GpuSensor::with_backend(
+            "/tmp/test-sensor",
+            1000,
+            100,
+            Box::new(MockBackend::new(350, 2000)),
+        );
         assert_eq!(sensor.sensor_path, "/tmp/test-sensor");
     }
+
+    #[test]
+    fn test_gpu_load_from_mock() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy((0..50).map(|i| i % 2 == 0));
+        let mut sensor = GpuSensor::with_backend("/tmp/test-sensor", 1000, 100, Box::new(mock));
+        assert_eq!(sensor.calculate_gpu_load().unwrap(), 50.0);
+        assert!(sensor.calculate_gpu_load().is_err());
+    }
 }
@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::system_paths::SystemPaths;
+
+/// Racine procfs par défaut
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
+
+/// Bit PP_OVERDRIVE_MASK de `amdgpu.ppfeaturemask`
+pub const PP_OVERDRIVE_MASK: u32 = 0x4000;
+
+/// Version du noyau (`major.minor.patch`), sans le suffixe de distribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KernelVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl KernelVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse `6.10.3-arch1-1`, `6.1.0-rc2`, `5.15` …
+    pub fn parse(release: &str) -> Option<Self> {
+        let numeric = release
+            .trim()
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()?;
+        let mut parts = numeric.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Noyau en cours d'exécution et paramètres du module amdgpu
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KernelInfo {
+    /// Contenu de `osrelease` (vide s'il n'a pas pu être lu)
+    pub release: String,
+    pub version: Option<KernelVersion>,
+    /// `/sys/module/amdgpu/parameters/*`
+    pub amdgpu_params: BTreeMap<String, String>,
+}
+
+impl KernelInfo {
+    /// Lit `/proc/sys/kernel/osrelease` et `/sys/module/amdgpu/parameters`
+    pub fn detect(paths: &SystemPaths) -> Self {
+        Self::read(&paths.proc, &paths.sys)
+    }
+
+    /// Comme `detect`, sous d'autres racines; les fichiers illisibles sont ignorés
+    pub fn read(proc_root: &Path, sysfs_root: &Path) -> Self {
+        let release = std::fs::read_to_string(proc_root.join("sys/kernel/osrelease"))
+            .map(|r| r.trim().to_string())
+            .unwrap_or_default();
+        let mut amdgpu_params = BTreeMap::new();
+        if let Ok(entries) = std::fs::read_dir(sysfs_root.join("module/amdgpu/parameters")) {
+            for entry in entries.flatten() {
+                // Certains paramètres ne sont lisibles que par root
+                if let Ok(value) = std::fs::read_to_string(entry.path()) {
+                    amdgpu_params.insert(
+                        entry.file_name().to_string_lossy().into_owned(),
+                        value.trim().to_string(),
+                    );
+                }
+            }
+        }
+        Self {
+            version: KernelVersion::parse(&release),
+            release,
+            amdgpu_params,
+        }
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.amdgpu_params.get(name).map(String::as_str)
+    }
+
+    /// Paramètre numérique, en décimal ou en hexadécimal (`0xfff7bfff`)
+    pub fn param_u32(&self, name: &str) -> Option<u32> {
+        let value = self.param(name)?;
+        match value.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        }
+    }
+}
+
+/// Particularité du noyau qui change la façon de piloter le GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quirk {
+    /// Pas de pilote SMU Cyan Skillfish: `pp_od_clk_voltage` absent
+    NoOverdriveTable,
+    /// Overdrive désactivé par `amdgpu.ppfeaturemask`: écritures OD refusées
+    OverdriveDisabled,
+    /// fdinfo sans compteurs `drm-engine-*`: pas de détection par processus
+    NoFdinfoUsage,
+    /// Lecture de GRBM_STATUS refusée: charge lue dans `gpu_busy_percent`
+    RegisterReadDenied,
+}
+
+impl Quirk {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NoOverdriveTable => "no-overdrive-table",
+            Self::OverdriveDisabled => "overdrive-disabled",
+            Self::NoFdinfoUsage => "no-fdinfo-usage",
+            Self::RegisterReadDenied => "register-read-denied",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::NoOverdriveTable => {
+                "kernel has no Cyan Skillfish power-play support, frequencies cannot be set"
+            }
+            Self::OverdriveDisabled => {
+                "overdrive is disabled, add amdgpu.ppfeaturemask=0xffffffff to the kernel \
+                command line to set frequencies"
+            }
+            Self::NoFdinfoUsage => {
+                "fdinfo has no drm-engine-* counters, per-process detection is unavailable"
+            }
+            Self::RegisterReadDenied => {
+                "reading GRBM_STATUS is not permitted, load is sampled from gpu_busy_percent"
+            }
+        }
+    }
+}
+
+enum Condition {
+    /// Noyau strictement antérieur à cette version
+    Before(KernelVersion),
+    /// Paramètre amdgpu présent mais sans ces bits
+    ParamLacksBits(&'static str, u32),
+}
+
+/// Particularités connues, évaluées dans l'ordre
+///
+/// `RegisterReadDenied` n'y figure pas: il est constaté à l'ouverture du GPU.
+const QUIRKS: &[(Quirk, Condition)] = &[
+    // cyan_skillfish_ppt.c est arrivé en 5.15
+    (
+        Quirk::NoOverdriveTable,
+        Condition::Before(KernelVersion::new(5, 15, 0)),
+    ),
+    (
+        Quirk::OverdriveDisabled,
+        Condition::ParamLacksBits("ppfeaturemask", PP_OVERDRIVE_MASK),
+    ),
+    // amdgpu publie les clés communes drm-engine-* depuis 5.19
+    (
+        Quirk::NoFdinfoUsage,
+        Condition::Before(KernelVersion::new(5, 19, 0)),
+    ),
+];
+
+/// Particularités actives pour un noyau donné
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Quirks {
+    release: String,
+    active: Vec<Quirk>,
+}
+
+impl Quirks {
+    /// Applique la table des particularités; un noyau de version inconnue n'en déclenche aucune
+    pub fn detect(info: &KernelInfo) -> Self {
+        let active = QUIRKS
+            .iter()
+            .filter(|(_, condition)| match condition {
+                Condition::Before(version) => info.version.is_some_and(|v| v < *version),
+                Condition::ParamLacksBits(name, bits) => {
+                    info.param_u32(name).is_some_and(|v| v & bits != *bits)
+                }
+            })
+            .map(|(quirk, _)| *quirk)
+            .collect();
+        Self {
+            release: info.release.clone(),
+            active,
+        }
+    }
+
+    /// Ajoute une particularité constatée à l'exécution
+    pub fn insert(&mut self, quirk: Quirk) {
+        if !self.has(quirk) {
+            self.active.push(quirk);
+        }
+    }
+
+    pub fn has(&self, quirk: Quirk) -> bool {
+        self.active.contains(&quirk)
+    }
+
+    pub fn active(&self) -> &[Quirk] {
+        &self.active
+    }
+
+    /// `pp_od_clk_voltage` accepte les écritures
+    pub fn can_write_od(&self) -> bool {
+        !self.has(Quirk::NoOverdriveTable) && !self.has(Quirk::OverdriveDisabled)
+    }
+
+    /// fdinfo porte des compteurs d'activité exploitables
+    pub fn has_fdinfo_usage(&self) -> bool {
+        !self.has(Quirk::NoFdinfoUsage)
+    }
+}
+
+impl fmt::Display for Quirks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let release = if self.release.is_empty() {
+            "unknown"
+        } else {
+            &self.release
+        };
+        if self.active.is_empty() {
+            return write!(f, "kernel {release}, no quirks");
+        }
+        write!(f, "kernel {release}, quirks:")?;
+        for quirk in &self.active {
+            write!(f, "\n  * {}: {}", quirk.name(), quirk.description())?;
+        }
+        Ok(())
+    }
+}
+
+static RUNNING: OnceLock<Quirks> = OnceLock::new();
+
+/// Détecte les particularités du noyau sous `paths`, à appeler au démarrage
+///
+/// Sans effet si elles ont déjà été détectées.
+pub fn init(paths: &SystemPaths) -> &'static Quirks {
+    RUNNING.get_or_init(|| Quirks::detect(&KernelInfo::detect(paths)))
+}
+
+/// Particularités du noyau en cours d'exécution (détectées une seule fois)
+///
+/// Sous les racines par défaut si `init` n'a pas été appelé.
+pub fn running() -> &'static Quirks {
+    init(&SystemPaths::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::FakeSystem;
+
+    fn kernel(name: &str, release: &str, params: &[(&str, &str)]) -> KernelInfo {
+        let system = FakeSystem::new(name);
+        system.kernel_release(release);
+        for (name, value) in params {
+            system.amdgpu_param(name, value);
+        }
+        KernelInfo::detect(system.paths())
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            KernelVersion::parse("6.10.3-arch1-1"),
+            Some(KernelVersion::new(6, 10, 3))
+        );
+        assert_eq!(
+            KernelVersion::parse("6.1-rc2"),
+            Some(KernelVersion::new(6, 1, 0))
+        );
+        assert_eq!(KernelVersion::parse("linux"), None);
+    }
+
+    #[test]
+    fn test_recent_kernel_without_quirks() {
+        let info = kernel(
+            "kernel-recent",
+            "6.10.3-arch1-1",
+            &[("ppfeaturemask", "0xffffffff")],
+        );
+        assert_eq!(info.version, Some(KernelVersion::new(6, 10, 3)));
+        let quirks = Quirks::detect(&info);
+        assert!(quirks.active().is_empty());
+        assert_eq!(quirks.to_string(), "kernel 6.10.3-arch1-1, no quirks");
+    }
+
+    #[test]
+    fn test_old_kernel_and_stock_featuremask() {
+        let info = kernel("kernel-old", "5.16.0", &[("ppfeaturemask", "0xfff7bfff")]);
+        let quirks = Quirks::detect(&info);
+        assert_eq!(
+            quirks.active(),
+            [Quirk::OverdriveDisabled, Quirk::NoFdinfoUsage]
+        );
+        assert!(!quirks.can_write_od());
+        assert!(!quirks.has_fdinfo_usage());
+    }
+
+    #[test]
+    fn test_unknown_kernel() {
+        let quirks = Quirks::detect(&KernelInfo::read(
+            Path::new("/nonexistent"),
+            Path::new("/nonexistent"),
+        ));
+        assert!(quirks.active().is_empty());
+        assert!(quirks.to_string().starts_with("kernel unknown"));
+    }
+}
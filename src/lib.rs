@@ -1,11 +1,33 @@
 // Public modules
+pub mod clock_guard;
+pub mod config;
+pub mod control;
+pub mod discovery;
+#[cfg(any(test, feature = "test-support"))]
+pub mod fake_system;
+pub mod game_overrides;
 pub mod governor;
+pub mod gpu_backend;
 pub mod gpu_info;
 pub mod gpu_sensor;
+pub mod kernel;
 pub mod load_monitor;
+pub mod metrics;
+pub mod od_table;
+pub mod pid;
+pub mod policy;
 pub mod process_detection;
 pub mod process_monitor;
+pub mod process_rules;
+pub mod profile_bundle;
 pub mod profile_db;
+pub mod reload;
+pub mod sampler;
+pub mod simulation;
+pub mod system_paths;
+pub mod trace;
+pub mod verify;
+pub mod voltage;
 
 // Re-export constants commonly used
 pub mod constants {
@@ -1,60 +1,24 @@
 use std::{
-    collections::{BTreeMap, VecDeque},
-    fs::File,
-    io::{Error as IoError, ErrorKind, Write},
-    os::fd::AsRawFd,
+    io::Error as IoError,
+    sync::Arc,
     thread::JoinHandle,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use libdrm_amdgpu_sys::{AMDGPU::DeviceHandle, PCI::BUS_INFO};
-use toml::Table;
-
-// cyan_skillfish.gfx1013.mmGRBM_STATUS
-const GRBM_STATUS_REG: u32 = 0x2004;
-// cyan_skillfish.gfx1013.mmGRBM_STATUS.GUI_ACTIVE (bit 31)
-const GUI_ACTIVE_BIT_MASK: u32 = 1 << 31;
-
-/// Structure to calculate GPU statistics with moving average
-struct GpuStats {
-    samples: VecDeque<bool>,
-    window_size: usize,
-    active_count: u32,
-}
-
-impl GpuStats {
-    fn new(window_size: usize) -> Self {
-        Self {
-            samples: VecDeque::with_capacity(window_size),
-            window_size,
-            active_count: 0,
-        }
-    }
-
-    fn add_sample(&mut self, is_active: bool) {
-        // If buffer is full, remove the oldest sample
-        if self.samples.len() >= self.window_size {
-            if let Some(old_sample) = self.samples.pop_front() {
-                if old_sample {
-                    self.active_count -= 1;
-                }
-            }
-        }
-
-        // Add the new sample
-        self.samples.push_back(is_active);
-        if is_active {
-            self.active_count += 1;
-        }
-    }
-
-    fn gpu_percent(&self) -> f32 {
-        if self.samples.is_empty() {
-            return 0.0;
-        }
-        (self.active_count as f32 / self.samples.len() as f32) * 100.0
-    }
-}
+use cyan_skillfish_governor::clock_guard::ClockGuard;
+use cyan_skillfish_governor::config::GovernorConfig;
+use cyan_skillfish_governor::control::{self, ControlState};
+use cyan_skillfish_governor::gpu_backend::{AmdgpuBackend, GpuBackend};
+use cyan_skillfish_governor::metrics;
+use cyan_skillfish_governor::od_table::OdRange;
+use cyan_skillfish_governor::policy::{FrequencyPolicy, Policy};
+use cyan_skillfish_governor::process_monitor::ProcessMonitor;
+use cyan_skillfish_governor::reload::{ConfigWatcher, install_sighup_handler};
+use cyan_skillfish_governor::sampler::{SampleInput, Sampler};
+use cyan_skillfish_governor::trace::{TraceRecord, TraceRecorder};
+use cyan_skillfish_governor::verify::ClockVerifier;
+use cyan_skillfish_governor::voltage::VoltageCurve;
+use watch::{WatchReceiver, WatchSender};
 
 /// Structure to manage logging rate limiting (max 1 log per second)
 struct LogThrottle {
@@ -82,583 +46,659 @@ impl LogThrottle {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = std::env::args()
-        .nth(1)
-        .map(std::fs::read_to_string)
-        .unwrap_or(Ok("".to_string()))?
-        .parse::<Table>()?;
-
-    let timing = config.get("timing").and_then(|t| t.as_table());
-    let intervals = timing
-        .and_then(|t| t.get("intervals"))
-        .and_then(|t| t.as_table());
-    // us
-    let sampling_interval: u16 = intervals
-        .and_then(|t| t.get("sample"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| v.is_positive().then_some(v).ok_or("must be positive"))
-        .and_then(|v| {
-            u16::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u16::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!("timing.intervals.sample {s}, replaced with the default value of 2 ms");
-            2000
-        });
-    // us
-    let adjustment_interval = intervals
-        .and_then(|t| t.get("adjust"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| v.is_positive().then_some(v).ok_or("must be positive"))
-        .and_then(|v| {
-            (v >= i64::from(sampling_interval))
-                .then_some(v)
-                .ok_or("must be at least as high as timing.intervals.sample")
-        })
-        .and_then(|v| {
-            u64::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u64::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!(
-                "timing.intervals.adjust {s}, replaced with the default of \
-                10 * timing.intervals.sample"
-            );
-            10 * u64::from(sampling_interval)
-        });
-    // us
-    let finetune_interval = intervals
-        .and_then(|t| t.get("finetune"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| v.is_positive().then_some(v).ok_or("must be positive"))
-        .and_then(|v| {
-            (v >= i64::from(sampling_interval))
-                .then_some(v)
-                .ok_or("must be at least as high as timing.intervals.sample")
-        })
-        .and_then(|v| {
-            u64::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u64::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!(
-                "timing.intervals.finetune {s}, replaced with the default of \
-                50_000 * timing.intervals.adjust"
-            );
-            50_000 * u64::from(sampling_interval)
-        });
-    // seconds
-    let log_interval: u64 = intervals
-        .and_then(|t| t.get("log"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| {
-            (!v.is_negative())
-                .then_some(v)
-                .ok_or("must not be negative")
-        })
-        .and_then(|v| {
-            u64::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u64::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!("timing.intervals.log {s}, replaced with the default of 60 second");
-            60
-        });
-    // us - optimization interval
-    let optimize_interval: u64 = intervals
-        .and_then(|t| t.get("optimize"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| {
-            (!v.is_negative())
-                .then_some(v)
-                .ok_or("must not be negative")
-        })
-        .and_then(|v| {
-            u64::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u64::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!(
-                "timing.intervals.optimize {s}, replaced with the default of 30 seconds (0 = disabled)"
-            );
-            30_000_000
-        });
-    let optimize_enabled = optimize_interval > 0;
-    // samples - window size for GPU load moving average
-    let window_samples: usize = timing
-        .and_then(|t| t.get("window-samples"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| v.is_positive().then_some(v).ok_or("must be positive"))
-        .and_then(|v| {
-            usize::try_from(v)
-                .map_err(|_| &*format!("cannot be greater than {}", usize::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!("timing.window-samples {s}, replaced with the default of 100 samples");
-            100
-        });
+    let config_path = std::env::args().nth(1);
+    let config = match &config_path {
+        Some(path) => GovernorConfig::load(path)?,
+        None => GovernorConfig::parse("")?,
+    };
 
-    // samples
-    let burst_mask = match timing
-        .and_then(|t| t.get("burst-samples"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| {
-            (!v.is_negative())
-                .then_some(v)
-                .ok_or("must not be negative")
-        }) {
-        Err(s) => {
-            println!(
-                "timing.burst-samples {s}, replaced with the default of \
-            48"
-            );
-            Some(48)
-        }
-        Ok(0) => None, // 0 = burst disabled
-        Ok(v @ 1..64) => Some(!(u64::MAX << v)),
-        Ok(64) => Some(u64::MAX),
-        Ok(65..) => {
-            println!("timing.burst-samples can be at most 64, clamping");
-            Some(64)
+    let backend = Arc::new(AmdgpuBackend::discover(
+        &config.paths,
+        config.device.as_deref(),
+    )?);
+    println!("[KERNEL] {}", backend.quirks());
+    // Puts the original clocks back on exit, signal or panic
+    let guard = Arc::new(ClockGuard::new(backend.clone()));
+    guard.install_handlers()?;
+    // After the guard: ctrlc's termination handler also takes SIGHUP
+    install_sighup_handler()?;
+
+    let od_table = backend.read_od_table()?;
+    println!("[OD] {od_table}");
+    let limits = GpuLimits {
+        engine_clock: backend.clock_limits()?,
+        od_range: od_table.range,
+    };
+    let active = ActiveConfig::new(config, &limits)?;
+    let mut watcher = config_path.map(|path| ConfigWatcher::new(path, active.config.reload.watch));
+    let (send, recv) = watch::channel(active.min_freq);
+    let control = Arc::new(ControlState::new(&Policy::ALL.map(|p| p.name())));
+    // So that a pin sent before the first sample is checked against the real range
+    control.update_status(|status| {
+        (status.min_freq, status.max_freq) = (active.min_freq, active.max_freq)
+    });
+    let socket = &active.config.control.socket;
+    if !socket.is_empty() {
+        control::serve(socket.as_ref(), Arc::clone(&control))?;
+        println!("[CONTROL] Listening on {socket}");
+    }
+    if active.config.metrics.enabled {
+        let status = Arc::clone(&control);
+        let addr = metrics::serve(&active.config.metrics.listen, move |format| {
+            metrics::governor_metrics(&status.status(), format)
+        })?;
+        println!("[METRICS] Listening on http://{addr}/metrics");
+    }
+    let (config_send, config_recv) = watch::channel(Arc::new(active));
+
+    let set_control = Arc::clone(&control);
+    let gov_backend = Arc::clone(&backend);
+    let gov_configs = config_recv.clone();
+    let jh_gov: JoinHandle<Result<(), IoError>> =
+        std::thread::spawn(move || run_governor(&*gov_backend, gov_configs, &send, &control));
+    // Writes go through the guard so none lands after the clocks are restored
+    let set_guard = Arc::clone(&guard);
+    let jh_set: JoinHandle<Result<(), IoError>> =
+        std::thread::spawn(move || run_setter(&*set_guard, config_recv, recv, &set_control));
+
+    // The main thread only handles config reloads (SIGHUP or file change)
+    while !jh_gov.is_finished() && !jh_set.is_finished() {
+        if let Some(result) = watcher.as_mut().and_then(ConfigWatcher::poll) {
+            match result.and_then(|config| ActiveConfig::new(config, &limits)) {
+                Ok(active) => {
+                    println!(
+                        "[CONFIG] Reloaded | Range: {}-{} MHz | Load target: {:.1}-{:.1}% | Policy: {}",
+                        active.min_freq,
+                        active.max_freq,
+                        active.config.load_target.lower,
+                        active.config.load_target.upper,
+                        active.config.policy.name()
+                    );
+                    if let Some(watcher) = watcher.as_mut() {
+                        watcher.set_watch_file(active.config.reload.watch);
+                    }
+                    config_send.send(Arc::new(active));
+                }
+                Err(e) => eprintln!("[CONFIG] Reload failed, keeping previous config: {e}"),
+            }
         }
-        Ok(i64::MIN..0) => unreachable!(),
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    let (name, finished) = if jh_set.is_finished() {
+        ("setter", jh_set)
+    } else {
+        ("governor", jh_gov)
     };
+    let result = finished.join();
+    if let Err(e) = guard.restore() {
+        eprintln!("Failed to restore original clocks: {e}");
+    }
+    match result {
+        Ok(result) => Ok(result?),
+        // The panic message was already printed by the hook
+        Err(_) => {
+            eprintln!("[FATAL] The {name} thread panicked");
+            Err(format!("{name} thread panicked").into())
+        }
+    }
+}
 
-    let ramp_rates = timing
-        .and_then(|t| t.get("ramp-rates"))
-        .and_then(|t| t.as_table());
-    // MHz/ms
-    let ramp_rate = ramp_rates
-        .and_then(|t| t.get("normal"))
-        .ok_or("is missing")
-        .and_then(|v| {
-            v.as_float()
-                .or_else(|| v.as_integer().map(|v| v as f64))
-                .ok_or("must be a number")
-        })
-        .and_then(|v| {
-            v.is_sign_positive()
-                .then_some(v)
-                .ok_or("must have positive sign")
-        })
-        .map(|v| v as f32)
-        .unwrap_or_else(|s| {
-            println!(
-                "timing.ramp-rates.normal {s}, replaced with the default value of \
-                1 MHz/ms"
-            );
-            1.0
-        });
-    // MHz/ms
-    let ramp_rate_burst = ramp_rates
-        .and_then(|t| t.get("burst"))
-        .ok_or("is missing")
-        .and_then(|v| {
-            v.as_float()
-                .or_else(|| v.as_integer().map(|v| v as f64))
-                .ok_or("must be a number")
-        })
-        .and_then(|v| {
-            v.is_sign_positive()
-                .then_some(v)
-                .ok_or("must have positive sign")
-        })
-        .map(|v| v as f32)
-        .and_then(|v| {
-            (v > ramp_rate || burst_mask.is_none()).then_some(v).ok_or(
-                "must, if bursting is active, be greater than timing.ramp-rates.normal \
-                (if you want to turn bursting off, set timing.burst-samples = 0)",
-            )
-        })
-        .unwrap_or_else(|s| {
-            println!(
-                "timing.ramp-rates.burst {s}, replaced with the default value of \
-                50 * timing.ramp-rates.normal"
-            );
-            50.0 * ramp_rate
-        });
+/// What the GPU and the kernel accept, read once at startup
+struct GpuLimits {
+    /// MHz (min, max)
+    engine_clock: (u64, u64),
+    od_range: OdRange,
+}
 
-    let freq_threshs = config
-        .get("frequency-thresholds")
-        .and_then(|t| t.as_table());
-    // MHz
-    let small_change = freq_threshs
-        .and_then(|t| t.get("finetune"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| v.is_positive().then_some(v).ok_or("must be positive"))
-        .and_then(|v| {
-            u16::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u16::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!(
-                "frequency-thresholds.finetune {s}, replaced with the default of \
-                10 MHz"
-            );
-            10
-        });
+/// Config together with the frequency range it allows on this GPU
+struct ActiveConfig {
+    config: GovernorConfig,
+    curve: VoltageCurve,
     // MHz
-    let significant_change = freq_threshs
-        .and_then(|t| t.get("adjust"))
-        .ok_or("is missing")
-        .and_then(|v| v.as_integer().ok_or("must be an integer"))
-        .and_then(|v| v.is_positive().then_some(v).ok_or("must be positive"))
-        .and_then(|v| {
-            u16::try_from(v).map_err(|_| &*format!("cannot be greater than {}", u16::MAX).leak())
-        })
-        .unwrap_or_else(|s| {
-            println!(
-                "frequency-thresholds.adjust {s}, replaced with the default of \
-                10 * frequency-thresholds.finetune"
-            );
-            10 * small_change
-        });
+    min_freq: u16,
+    max_freq: u16,
+}
 
-    let load_threshs = config.get("load-target").and_then(|t| t.as_table());
-    // percentage (0-100)
-    let up_thresh = load_threshs
-        .and_then(|t| t.get("upper"))
-        .ok_or("is missing")
-        .and_then(|v| {
-            v.as_float()
-                .or_else(|| v.as_integer().map(|v| v as f64))
-                .ok_or("must be a number")
-        })
-        .and_then(|v| {
-            (0.0..=100.0)
-                .contains(&v)
-                .then_some(v)
-                .ok_or("must be between 0 and 100")
-        })
-        .map(|v| v as f32)
-        .unwrap_or_else(|s| {
-            println!(
-                "load-target.upper {s}, replaced with the default value of \
-                90%"
-            );
-            90.0
-        });
-    // percentage (0-100)
-    let down_thresh = load_threshs
-        .and_then(|t| t.get("lower"))
-        .ok_or("is missing")
-        .and_then(|v| {
-            v.as_float()
-                .or_else(|| v.as_integer().map(|v| v as f64))
-                .ok_or("must be a number")
-        })
-        .and_then(|v| {
-            (0.0..=100.0)
-                .contains(&v)
-                .then_some(v)
-                .ok_or("must be between 0 and 100")
+impl ActiveConfig {
+    /// Clamps the safe-point range to the engine clock limits reported by the GPU
+    ///
+    /// Safe points outside the kernel's `OD_RANGE` voltage range are refused.
+    fn new(config: GovernorConfig, limits: &GpuLimits) -> Result<Self, IoError> {
+        let (min_engine_clock, max_engine_clock) = limits.engine_clock;
+        limits.od_range.check_safe_points(&config.safe_points)?;
+        let mut min_freq = config.min_safe_freq();
+        if u64::from(min_freq) < min_engine_clock {
+            eprintln!("GPU minimum frequency higher than lowest safe frequency, clamping");
+            min_freq = u16::try_from(min_engine_clock).map_err(IoError::other)?;
+        }
+        let mut max_freq = config.max_safe_freq();
+        if u64::from(max_freq) > max_engine_clock {
+            eprintln!("GPU maximum frequency lower than highest safe frequency, clamping");
+            max_freq = u16::try_from(max_engine_clock).map_err(IoError::other)?;
+        }
+        Ok(Self {
+            curve: config.voltage_curve(),
+            config,
+            min_freq,
+            max_freq,
         })
-        .map(|v| v as f32)
-        .unwrap_or_else(|s| {
-            println!(
-                "load-target.lower {s}, replaced with the default value of \
-                upper - 10%"
-            );
-            (up_thresh - 10.0).max(0.0)
-        });
-    let down_thresh = if down_thresh > up_thresh {
-        println!("load-target.lower can't be greater than load-target.upper, clamping");
-        up_thresh
-    } else {
-        down_thresh
-    };
+    }
+}
 
-    // MHz, mV
-    let safe_points: BTreeMap<u16, u16> = if let Some(array) = config.get("safe-points") {
-        let array = array.as_array().ok_or(IoError::new(
-            ErrorKind::InvalidInput,
-            "safe-points must be an array",
-        ))?;
-        if array.is_empty() {
-            Err(IoError::new(
-                ErrorKind::InvalidInput,
-                "safe-points must not be empty",
-            ))?;
+/// Samples the GPU and publishes target frequencies until the backend fails
+///
+/// Each sample goes through a `Sampler` running the policy chosen by the
+/// config or through `set-policy` on the control socket; a new policy is built
+/// on its own thread so that loading profiles never stalls sampling. The state
+/// is published to `control` every sample, and a frequency pinned through it
+/// replaces the computed target until it expires. While `[trace]` or the
+/// `trace` command asks for it, every sample is also queued to the trace
+/// writer thread; a trace that cannot be written is stopped without stopping
+/// the governor.
+fn run_governor<B: GpuBackend + ?Sized>(
+    backend: &B,
+    mut configs: WatchReceiver<Arc<ActiveConfig>>,
+    send: &WatchSender<u16>,
+    control: &ControlState,
+) -> Result<(), IoError> {
+    let mut active = configs.get();
+    // `[paths]` is only read at startup, like the device
+    let paths = active.config.paths.clone();
+    let mut sampler = Sampler::new(
+        active.config.policy.build(active.min_freq, &active.config),
+        active.config.timing.window_samples,
+        active.min_freq,
+        Instant::now(),
+    );
+    control.update_status(|status| {
+        status.policy = sampler.policy().name().to_string();
+        status.trace = active.config.trace.enabled;
+    });
+    let mut tracer: Option<TraceRecorder> = None;
+    // Policy being built off the sampling loop (`process-aware` reads its database)
+    let mut pending: Option<(Policy, JoinHandle<Box<dyn FrequencyPolicy>>)> = None;
+    let mut processes: Option<ProcessMonitor> = None;
+    let mut log_throttle = LogThrottle::new(active.config.timing.intervals.log);
+    let mut last_reasons: Vec<&str> = Vec::new();
+
+    loop {
+        // Swap in a reloaded config, keeping the current frequency state
+        if let Some(new) = configs.get_if_new() {
+            sampler.set_window(new.config.timing.window_samples);
+            if new.config.timing.intervals.log != active.config.timing.intervals.log {
+                log_throttle = LogThrottle::new(new.config.timing.intervals.log);
+            }
+            if new.config.policy != active.config.policy {
+                control.update_status(|status| status.policy = new.config.policy.name().into());
+            }
+            if new.config.processes != active.config.processes
+                && let Some(monitor) = processes.as_mut()
+            {
+                monitor.set_rules(new.config.processes.clone());
+            }
+            if new.config.trace != active.config.trace {
+                // Reopened below with the new path and limits
+                if let Some(recorder) = tracer.take() {
+                    stop_trace(recorder);
+                }
+                control.update_status(|status| status.trace = new.config.trace.enabled);
+            }
+            active = new;
         }
-        let mut safe_points = BTreeMap::new();
-        for (i, t) in array.iter().enumerate() {
-            let t = t.as_table().ok_or_else(|| {
-                IoError::new(
-                    ErrorKind::InvalidInput,
-                    format!("safe-points[{i}] must be a table"),
-                )
-            })?;
-
-            // MHz
-            let frequency = t
-                .get("frequency")
-                .ok_or_else(|| {
-                    IoError::new(
-                        ErrorKind::InvalidInput,
-                        format!("safe-points[{i}].frequency must exist"),
-                    )
-                })?
-                .as_integer()
-                .ok_or_else(|| {
-                    IoError::new(
-                        ErrorKind::InvalidInput,
-                        format!("safe-points[{i}].frequency must be an integer"),
-                    )
-                })?;
-            let frequency = u16::try_from(frequency).map_err(|_| {
-                IoError::new(
-                    ErrorKind::InvalidInput,
-                    format!(
-                        "safe-points[{i}].frequency must be between 0 and {} inclusive",
-                        u16::MAX
-                    ),
-                )
-            })?;
-
-            // mV
-            let voltage = t
-                .get("voltage")
-                .ok_or_else(|| {
-                    IoError::new(
-                        ErrorKind::InvalidInput,
-                        format!("safe-points[{i}].voltage must exist"),
-                    )
-                })?
-                .as_integer()
-                .ok_or_else(|| {
-                    IoError::new(
-                        ErrorKind::InvalidInput,
-                        format!("safe-points[{i}].voltage must be an integer"),
-                    )
-                })?;
-            let voltage = u16::try_from(voltage).map_err(|_| {
-                IoError::new(
-                    ErrorKind::InvalidInput,
-                    format!(
-                        "safe-points[{i}].voltage must be between 0 and {} inclusive",
-                        u16::MAX
-                    ),
-                )
-            })?;
-
-            if safe_points.insert(frequency, voltage).is_some() {
-                Err(IoError::new(
-                    ErrorKind::InvalidInput,
-                    format!("multiple supposedly safe voltages for {frequency} MHz"),
-                ))?;
+        // Switch policy when the config or `set-policy` asked for another one;
+        // the current policy keeps running until the new one is built
+        if let Some(wanted) = Policy::from_name(&control.policy()) {
+            if wanted.name() == sampler.policy().name() {
+                pending = None;
+            } else if pending.as_ref().is_none_or(|(policy, _)| *policy != wanted) {
+                println!("[POLICY] {} → {}", sampler.policy().name(), wanted.name());
+                let (freq, config) = (sampler.current_freq(), Arc::clone(&active));
+                pending = Some((
+                    wanted,
+                    std::thread::spawn(move || wanted.build(freq, &config.config)),
+                ));
             }
         }
-        let mut highest_pair = (0, 0);
-        for (frequency, voltage) in &safe_points {
-            let pair = (*voltage, *frequency);
-            if pair < highest_pair {
-                Err(IoError::new(
-                    ErrorKind::InvalidInput,
-                    format!(
-                        "supposedly safe voltage {} mV for {} MHz is higher than \
-                        {voltage} mV for {frequency} MHz",
-                        highest_pair.0, highest_pair.1,
-                    ),
-                ))?;
-            } else {
-                highest_pair = pair;
+        if pending
+            .as_ref()
+            .is_some_and(|(_, build)| build.is_finished())
+        {
+            let (wanted, build) = pending.take().unwrap();
+            match build.join() {
+                Ok(mut policy) => {
+                    policy.reset(sampler.current_freq());
+                    sampler.set_policy(policy);
+                }
+                Err(_) => {
+                    eprintln!(
+                        "[POLICY] Building {} failed, keeping {}",
+                        wanted.name(),
+                        sampler.policy().name()
+                    );
+                    control.update_status(|status| status.policy = sampler.policy().name().into());
+                }
             }
         }
-        safe_points
-    } else {
-        println!(
-            "safe-points undefined, using conservative defaults:\n\
-            * 350 MHz @ 700 mV\n\
-            * 2000 MHz @ 1000 mV"
-        );
-        BTreeMap::from([(350, 700), (2000, 1000)])
-    };
+        if !sampler.policy().uses_processes() {
+            processes = None;
+        } else if processes.is_none() {
+            let mut monitor = ProcessMonitor::with_paths(paths.clone());
+            monitor.set_rules(active.config.processes.clone());
+            processes = Some(monitor);
+        }
+        if !control.tracing() {
+            if let Some(recorder) = tracer.take() {
+                stop_trace(recorder);
+            }
+        } else if tracer.is_none() {
+            match TraceRecorder::start(&active.config.trace) {
+                Ok(writer) => {
+                    println!("[TRACE] Recording to {}", writer.path().display());
+                    tracer = Some(writer);
+                }
+                Err(e) => {
+                    eprintln!("[TRACE] Could not open {}: {e}", active.config.trace.path);
+                    control.update_status(|status| status.trace = false);
+                }
+            }
+        }
+        let (min_freq, max_freq) = (active.min_freq, active.max_freq);
+
+        let gui_busy = backend.read_busy()?;
+        // Only refreshed once per PROCESS_UPDATE_INTERVAL_SECS
+        let active_process = processes.as_mut().and_then(ProcessMonitor::update);
+        let active_name = processes
+            .as_ref()
+            .and_then(ProcessMonitor::current_name)
+            .map(str::to_string);
+        let step = sampler.step(&SampleInput {
+            busy: gui_busy,
+            timestamp: Instant::now(),
+            config: &active.config,
+            min_freq,
+            max_freq,
+            pinned: control.pinned_freq(),
+            active_process: active_process.as_deref(),
+            active_process_name: active_name.as_deref(),
+            process_stable: processes
+                .as_ref()
+                .is_some_and(ProcessMonitor::is_process_stable),
+        });
 
-    let location = BUS_INFO {
-        domain: 0,
-        bus: 1,
-        dev: 0,
-        func: 0,
-    };
-    let sysfs_path = location.get_sysfs_path();
-    let vendor = std::fs::read_to_string(sysfs_path.join("vendor"))?;
-    let device = std::fs::read_to_string(sysfs_path.join("device"))?;
-    if !((vendor == "0x1002\n") && (device == "0x13fe\n")) {
-        Err(IoError::other(
-            "Cyan Skillfish GPU not found at expected PCI bus location",
-        ))?;
+        if let Some(recorder) = tracer.as_mut() {
+            let record = TraceRecord {
+                timestamp_us: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |t| t.as_micros() as u64),
+                busy: gui_busy,
+                load_percent: step.load_percent,
+                target_freq: step.target_freq,
+                freq: sampler.current_freq(),
+                reasons: step
+                    .change
+                    .iter()
+                    .flat_map(|c| &c.reasons)
+                    .map(|r| r.to_string())
+                    .collect(),
+            };
+            if let Err(e) = recorder.record(record) {
+                eprintln!("[TRACE] Write failed, stopping: {e}");
+                tracer = None;
+                control.update_status(|status| {
+                    status.trace = false;
+                    status.trace_errors += 1;
+                });
+            }
+        }
+
+        if let Some(change) = step.change {
+            // Frequency change logging (rate limited to 1/sec)
+            if log_throttle.should_log() {
+                let direction = if change.to > change.from {
+                    "↑"
+                } else if change.to < change.from {
+                    "↓"
+                } else {
+                    "="
+                };
+                println!(
+                    "[FREQ] {} MHz {} {} MHz | GPU Load: {:.1}% | Reasons: {}",
+                    change.from,
+                    direction,
+                    change.to,
+                    step.load_percent,
+                    change.reasons.join(", ")
+                );
+            }
+            last_reasons = change.reasons;
+            send.send(change.to);
+        }
+
+        control.update_status(|status| {
+            status.current_freq = sampler.current_freq();
+            status.target_freq = step.target_freq;
+            status.min_freq = min_freq;
+            status.max_freq = max_freq;
+            status.load_percent = step.load_percent;
+            status.mode = step.decision.mode.to_string();
+            status.active_process = active_name.or(active_process);
+            status.learning_progress = sampler.policy().learning_progress();
+            status.learned_profiles = sampler.policy().learned_profiles();
+            status.reasons = last_reasons.iter().map(|r| r.to_string()).collect();
+        });
+
+        let sampling_interval = active.config.timing.intervals.sample;
+        std::thread::sleep(Duration::from_micros(u64::from(sampling_interval)));
     }
-    let card = File::open(location.get_drm_render_path()?)?;
-    let (dev_handle, _, _) =
-        DeviceHandle::init(card.as_raw_fd()).map_err(IoError::from_raw_os_error)?;
-
-    let info = dev_handle
-        .device_info()
-        .map_err(IoError::from_raw_os_error)?;
-    // given in kHz, we need MHz
-    let min_engine_clock = info.min_engine_clock / 1000;
-    let max_engine_clock = info.max_engine_clock / 1000;
-    let mut min_freq = *safe_points.first_key_value().unwrap().0;
-    if u64::from(min_freq) < min_engine_clock {
-        eprintln!("GPU minimum frequency higher than lowest safe frequency, clamping");
-        min_freq = u16::try_from(min_engine_clock)?;
+}
+
+/// Waits for the queued samples to be written and reports the dropped ones
+fn stop_trace(recorder: TraceRecorder) {
+    match recorder.dropped() {
+        0 => println!("[TRACE] Stopped"),
+        dropped => println!("[TRACE] Stopped, {dropped} samples dropped while the writer lagged"),
     }
-    let mut max_freq = *safe_points.last_key_value().unwrap().0;
-    if u64::from(max_freq) > max_engine_clock {
-        eprintln!("GPU maximum frequency lower than highest safe frequency, clamping");
-        max_freq = u16::try_from(max_engine_clock)?;
+}
+
+/// Applies every frequency published by the governor thread
+///
+/// Clocks that never show up in `pp_dpm_sclk` (with `[verify]` enabled) are
+/// logged and reported through `control`, the governor keeps running.
+fn run_setter<B: GpuBackend + ?Sized>(
+    backend: &B,
+    mut configs: WatchReceiver<Arc<ActiveConfig>>,
+    mut recv: WatchReceiver<u16>,
+    control: &ControlState,
+) -> Result<(), IoError> {
+    let mut verifier = ClockVerifier::new();
+    loop {
+        let freq = recv.wait();
+        let active = configs.get();
+        if !apply_frequency(backend, &mut verifier, &active, freq)? {
+            eprintln!(
+                "[VERIFY] {freq} MHz was not applied by the kernel | Mismatches: {}",
+                verifier.stats().mismatches
+            );
+        }
+        let stats = verifier.stats();
+        control.update_status(|status| {
+            status.voltage = active.curve.voltage(freq).unwrap_or_default();
+            status.frequency_writes += 1;
+            status.verify_mismatches = stats.mismatches;
+            status.verify_last_error = stats.last_error.clone();
+        });
     }
-    let (min_freq, max_freq) = (min_freq, max_freq);
-
-    let mut pp_file = std::fs::OpenOptions::new().write(true).open(
-        dev_handle
-            .get_sysfs_path()
-            .map_err(IoError::from_raw_os_error)?
-            .join("pp_od_clk_voltage"),
-    )?;
-    let (send, mut recv) = watch::channel(min_freq);
-
-    // Capture variables for thread
-    let optimize_enabled_capture = optimize_enabled;
-    let optimize_interval_capture = optimize_interval;
-
-    let jh_gov: JoinHandle<Result<(), IoError>> = std::thread::spawn(move || {
-        let mut curr_freq = min_freq;
-        let mut target_freq = f32::from(min_freq);
-        let mut samples: u64 = 0;
-        let mut stats = GpuStats::new(window_samples);
-        let mut last_adjustment = Instant::now();
-        let mut last_finetune = Instant::now();
-        let mut last_freq_change = Instant::now();
-        let mut log_throttle = LogThrottle::new(log_interval);
-
-        // Stability zone: avoids oscillations between thresholds
-        // Between lower and upper, do nothing (target zone)
-        // Except if optimization mode enabled: slowly decrease to optimize
-        loop {
-            let res = dev_handle
-                .read_mm_registers(GRBM_STATUS_REG)
-                .map_err(IoError::from_raw_os_error)?;
-            let gui_busy = (res & GUI_ACTIVE_BIT_MASK) != 0;
-
-            // Radeontop method: counting for percentage
-            stats.add_sample(gui_busy);
-
-            // Buffer for burst detection (keeps old method)
-            samples <<= 1;
-            if gui_busy {
-                samples |= 1;
-            }
+}
 
-            // GPU percentage calculation (0-100)
-            let gpu_percent = stats.gpu_percent();
-            let burst = burst_mask
-                .map(|mask| samples & mask == mask)
-                .unwrap_or(false);
-
-            // Apply frequency changes
-            let in_stable_zone = gpu_percent >= down_thresh && gpu_percent <= up_thresh;
-            let stable_duration = last_freq_change.elapsed();
-            let can_optimize = optimize_enabled_capture
-                && in_stable_zone
-                && stable_duration >= Duration::from_micros(optimize_interval_capture)
-                && gpu_percent < (up_thresh - 2.0); // 2% margin: if already close to target, do nothing
-
-            if burst {
-                // Burst: fast ramp up
-                target_freq += ramp_rate_burst * f32::from(sampling_interval) / 1000.0;
-            } else if gpu_percent > up_thresh {
-                // Above upper threshold: ramp up
-                target_freq += ramp_rate * f32::from(sampling_interval) / 1000.0;
-            } else if gpu_percent < down_thresh {
-                // Below lower threshold: ramp down
-                target_freq -= ramp_rate * f32::from(sampling_interval) / 1000.0;
-            } else if can_optimize {
-                // Stable zone AND stable for a long time: optimization
-                // Slow decrease (10% of normal speed) to increase load
-                target_freq -= ramp_rate * 0.1 * f32::from(sampling_interval) / 1000.0;
-            }
-            // Otherwise: between down_thresh and up_thresh, do nothing
-
-            target_freq = target_freq.clamp(f32::from(min_freq), f32::from(max_freq));
-
-            let adj_now = last_adjustment.elapsed() >= Duration::from_micros(adjustment_interval);
-            if adj_now || burst {
-                let target_freq = target_freq as u16;
-                let hit_bounds = target_freq != curr_freq
-                    && (target_freq == min_freq || target_freq == max_freq);
-                let big_change = curr_freq.abs_diff(target_freq) >= significant_change;
-                let finetune = (last_finetune.elapsed()
-                    >= Duration::from_micros(finetune_interval))
-                    && curr_freq.abs_diff(target_freq) >= small_change;
-                let burst_up = burst && curr_freq != target_freq;
-                if hit_bounds || big_change || finetune || burst_up {
-                    // Frequency change logging (rate limited to 1/sec)
-                    if log_throttle.should_log() {
-                        let direction = if target_freq > curr_freq {
-                            "↑"
-                        } else if target_freq < curr_freq {
-                            "↓"
-                        } else {
-                            "="
-                        };
-                        let mut reasons = Vec::new();
-                        if burst_up {
-                            reasons.push("activity burst detected");
-                        }
-                        if hit_bounds {
-                            if target_freq == min_freq {
-                                reasons.push("min limit reached");
-                            } else {
-                                reasons.push("max limit reached");
-                            }
-                        }
-                        if big_change {
-                            reasons.push("significant change");
-                        }
-                        if finetune {
-                            reasons.push("fine adjustment");
-                        }
-                        if can_optimize && !burst_up && !big_change && !finetune && !hit_bounds {
-                            reasons.push("energy optimization");
-                        }
-
-                        let reason_str = reasons.join(", ");
-                        println!(
-                            "[FREQ] {} MHz {} {} MHz | GPU Load: {:.1}% | Reasons: {}",
-                            curr_freq, direction, target_freq, gpu_percent, reason_str
-                        );
-                    }
+/// Writes `freq` with the voltage the safe-point curve gives for it
+///
+/// Returns false when read-back verification never saw the clock change.
+fn apply_frequency<B: GpuBackend + ?Sized>(
+    backend: &B,
+    verifier: &mut ClockVerifier,
+    active: &ActiveConfig,
+    freq: u16,
+) -> Result<bool, IoError> {
+    let vol = voltage_for(&active.curve, freq)?;
+    verifier.apply(backend, &active.config.verify, freq, vol)
+}
 
-                    send.send(target_freq);
-                    curr_freq = target_freq;
-                    last_finetune = Instant::now();
-                    last_freq_change = Instant::now();
-                }
-                last_adjustment = Instant::now();
+fn voltage_for(curve: &VoltageCurve, freq: u16) -> Result<u16, IoError> {
+    curve.voltage(freq).ok_or(IoError::other(
+        "tried to set a frequency beyond max safe point",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cyan_skillfish_governor::fake_system::TempDir;
+    use cyan_skillfish_governor::gpu_backend::MockBackend;
+    use cyan_skillfish_governor::voltage::Interpolation;
+    use std::collections::BTreeMap;
+    use std::io::ErrorKind;
+
+    fn test_config(extra: &str) -> Arc<ActiveConfig> {
+        let config = GovernorConfig::parse(&format!(
+            "{extra}\n[timing]\nburst-samples = 0\nwindow-samples = 10\n\
+            [timing.intervals]\nsample = 100\nadjust = 100\nfinetune = 1000\noptimize = 0\n\
+            [timing.ramp-rates]\nnormal = 100\n\
+            [load-target]\nupper = 90\nlower = 80\n"
+        ))
+        .unwrap();
+        let limits = GpuLimits {
+            engine_clock: (350, 2000),
+            od_range: MockBackend::new(350, 2000).read_od_table().unwrap().range,
+        };
+        Arc::new(ActiveConfig::new(config, &limits).unwrap())
+    }
+
+    #[test]
+    fn test_full_load_ramps_to_max() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(true, 400));
+        let (send, mut recv) = watch::channel(350);
+        let (_config_send, configs) = watch::channel(test_config(""));
+
+        let err =
+            run_governor(&mock, configs, &send, &ControlState::new(&["threshold"])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let mut verifier = ClockVerifier::new();
+        apply_frequency(&mock, &mut verifier, &test_config(""), recv.get()).unwrap();
+        assert_eq!(mock.current(), Some((2000, 1000)));
+    }
+
+    #[test]
+    fn test_idle_stays_at_min() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(false, 100));
+        let (send, mut recv) = watch::channel(350);
+        recv.get();
+        let (_config_send, configs) = watch::channel(test_config(""));
+
+        run_governor(&mock, configs, &send, &ControlState::new(&["threshold"])).unwrap_err();
+        assert_eq!(recv.get_if_new(), None);
+    }
+
+    #[test]
+    fn test_reload_swaps_bounds() {
+        let mock = Arc::new(MockBackend::new(350, 2000));
+        mock.push_busy(std::iter::repeat_n(true, 2000));
+        let (send, mut recv) = watch::channel(350);
+        let (config_send, configs) = watch::channel(test_config(""));
+
+        let gov_mock = Arc::clone(&mock);
+        let jh = std::thread::spawn(move || {
+            run_governor(
+                &*gov_mock,
+                configs,
+                &send,
+                &ControlState::new(&["threshold"]),
+            )
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        config_send.send(test_config(
+            "[[safe-points]]\nfrequency = 350\nvoltage = 700\n\
+            [[safe-points]]\nfrequency = 1000\nvoltage = 850\n",
+        ));
+        jh.join().unwrap().unwrap_err();
+
+        assert_eq!(recv.get(), 1000);
+    }
+
+    #[test]
+    fn test_pin_overrides_load() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(true, 400));
+        let (send, mut recv) = watch::channel(350);
+        let (_config_send, configs) = watch::channel(test_config(""));
+        let control = ControlState::new(&["threshold"]);
+        control.update_status(|s| (s.min_freq, s.max_freq) = (350, 2000));
+        assert!(control.handle_line(r#"{"cmd":"pin","freq":1200}"#).ok);
+
+        run_governor(&mock, configs, &send, &control).unwrap_err();
+        assert_eq!(recv.get(), 1200);
+        let status = control.status();
+        assert_eq!(status.mode, "pinned");
+        assert_eq!(status.current_freq, 1200);
+        assert_eq!(status.load_percent, 100.0);
+    }
+
+    #[test]
+    fn test_pid_policy() {
+        let pid_config = || test_config("policy = \"pid\"\n[pid]\nkp = 10\nki = 5000\n");
+        let control = ControlState::new(&["pid"]);
+
+        // Setpoint 85%: full load reaches the max …
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(true, 400));
+        let (send, mut recv) = watch::channel(350);
+        let (_config_send, configs) = watch::channel(pid_config());
+        run_governor(&mock, configs, &send, &control).unwrap_err();
+        assert_eq!(recv.get(), 2000);
+        let status = control.status();
+        assert_eq!(status.policy, "pid");
+        assert_eq!(status.mode, "pid");
+
+        // … and the integral is not wound up there once the GPU goes idle
+        mock.push_busy(std::iter::repeat_n(true, 400));
+        mock.push_busy(std::iter::repeat_n(false, 400));
+        let (_config_send, configs) = watch::channel(pid_config());
+        run_governor(&mock, configs, &send, &control).unwrap_err();
+        assert_eq!(recv.get(), 350);
+    }
+
+    #[test]
+    fn test_set_policy_switches_at_runtime() {
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(true, 2000));
+        let (send, _recv) = watch::channel(350);
+        let (_config_send, configs) = watch::channel(test_config(""));
+        let control = ControlState::new(&Policy::ALL.map(|p| p.name()));
+
+        std::thread::scope(|s| {
+            let governor = s.spawn(|| run_governor(&mock, configs, &send, &control));
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while control.status().mode != "ramp-up" && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
             }
+            let response = control.handle_line(r#"{"cmd":"set-policy","policy":"pid"}"#);
+            assert!(response.ok);
+            governor.join().unwrap().unwrap_err();
+        });
+        let status = control.status();
+        assert_eq!(status.policy, "pid");
+        assert_eq!(status.mode, "pid");
+    }
 
-            std::thread::sleep(Duration::from_micros(u64::from(sampling_interval)));
-        }
-    });
-    let jh_set: JoinHandle<Result<(), IoError>> = std::thread::spawn(move || {
-        loop {
-            let freq = recv.wait();
-            let vol = *safe_points
-                .range(freq..)
-                .next()
-                .ok_or(IoError::other(
-                    "tried to set a frequency beyond max safe point",
-                ))?
-                .1;
-            pp_file.write_all(format!("vc 0 {freq} {vol}").as_bytes())?;
-            pp_file.write_all("c".as_bytes())?;
+    #[test]
+    fn test_trace_records_every_sample() {
+        let dir = TempDir::new("main-trace");
+        let path = dir.join("trace.csv");
+        let mock = MockBackend::new(350, 2000);
+        mock.push_busy(std::iter::repeat_n(true, 100));
+        let (send, mut recv) = watch::channel(350);
+        let (_config_send, configs) = watch::channel(test_config(&format!(
+            "[trace]\nenabled = true\npath = \"{}\"\n",
+            path.display()
+        )));
+        let control = ControlState::new(&["threshold"]);
+
+        run_governor(&mock, configs, &send, &control).unwrap_err();
+        let records = cyan_skillfish_governor::trace::read_trace(&path).unwrap();
+        assert_eq!(records.len(), 100);
+        assert!(records.iter().all(|r| r.busy));
+        let last = records.last().unwrap();
+        assert_eq!(last.freq, recv.get());
+        assert!(records.iter().any(|r| !r.reasons.is_empty()));
+        assert!(control.status().trace);
+
+        // Stopped through the control socket: nothing more is written
+        control.handle_line(r#"{"cmd":"trace","enabled":false}"#);
+        mock.push_busy(std::iter::repeat_n(true, 10));
+        let (_config_send, configs) = watch::channel(test_config(""));
+        run_governor(&mock, configs, &send, &control).unwrap_err();
+        assert_eq!(
+            cyan_skillfish_governor::trace::read_trace(&path)
+                .unwrap()
+                .len(),
+            100
+        );
+    }
+
+    #[test]
+    fn test_bounds_clamped_to_engine_clock() {
+        let config = GovernorConfig::parse("").unwrap();
+        let limits = GpuLimits {
+            engine_clock: (500, 1600),
+            od_range: OdRange::default(),
+        };
+        let active = ActiveConfig::new(config, &limits).unwrap();
+        assert_eq!((active.min_freq, active.max_freq), (500, 1600));
+    }
+
+    #[test]
+    fn test_safe_points_outside_od_range_refused() {
+        let config = GovernorConfig::parse(
+            "[[safe-points]]\nfrequency = 350\nvoltage = 650\n\
+            [[safe-points]]\nfrequency = 2000\nvoltage = 1000\n",
+        )
+        .unwrap();
+        let limits = GpuLimits {
+            engine_clock: (350, 2000),
+            od_range: OdRange {
+                sclk: Some((350, 2000)),
+                vddc: Some((700, 1129)),
+            },
+        };
+        let err = ActiveConfig::new(config, &limits).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_voltage_from_curve() {
+        let safe_points = BTreeMap::from([(350, 700), (1000, 850), (2000, 1000)]);
+        let step = VoltageCurve::new(safe_points.clone(), Interpolation::Step);
+        assert_eq!(voltage_for(&step, 900).unwrap(), 850);
+        assert_eq!(voltage_for(&step, 1001).unwrap(), 1000);
+        assert!(voltage_for(&step, 2001).is_err());
+
+        let linear = VoltageCurve::new(safe_points, Interpolation::Linear);
+        assert_eq!(voltage_for(&linear, 675).unwrap(), 775);
+    }
+
+    #[test]
+    fn test_setter_reports_unapplied_clock() {
+        let mock = Arc::new(MockBackend::new(350, 2000).ignoring_writes());
+        let guard = Arc::new(ClockGuard::new(mock.clone()));
+        let (send, recv) = watch::channel(1200);
+        let (_config_send, configs) = watch::channel(test_config(
+            "[verify]\nenabled = true\nretries = 1\nbackoff = 0\n",
+        ));
+        let control = Arc::new(ControlState::new(&["threshold"]));
+
+        let (set_guard, set_control) = (Arc::clone(&guard), Arc::clone(&control));
+        let setter =
+            std::thread::spawn(move || run_setter(&*set_guard, configs, recv, &set_control));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while control.status().frequency_writes < 1 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
         }
-    });
 
-    let () = jh_set.join().unwrap()?;
-    let () = jh_gov.join().unwrap()?;
-    Ok(())
+        let status = control.status();
+        assert_eq!(status.verify_mismatches, 1);
+        assert_eq!(
+            status.verify_last_error.as_deref(),
+            Some("requested 1200 MHz, kernel reports 350 MHz")
+        );
+
+        // Once the clocks are restored the next write stops the setter
+        guard.restore().unwrap();
+        send.send(1300);
+        let err = setter.join().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        assert_eq!(mock.current(), Some((0, 0)));
+    }
 }
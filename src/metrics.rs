@@ -0,0 +1,370 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::control::GovernorStatus;
+use crate::gpu_sensor::SensorStats;
+
+/// Adresse par défaut de `metrics.listen`: boucle locale uniquement
+pub const DEFAULT_METRICS_LISTEN: &str = "127.0.0.1:9617";
+
+/// Préfixe de toutes les métriques
+const PREFIX: &str = "cyan_skillfish";
+
+/// Délai accordé à un client pour envoyer sa requête
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Taille maximale des en-têtes d'une requête
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Exporteur Prometheus (section `[metrics]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// `adresse:port` d'écoute HTTP. Pris en compte au démarrage seulement
+    pub listen: String,
+}
+
+/// Format d'exposition, selon l'en-tête `Accept` du client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `text/plain; version=0.0.4`
+    Prometheus,
+    /// `application/openmetrics-text; version=1.0.0`
+    OpenMetrics,
+}
+
+impl Format {
+    /// OpenMetrics seulement si le client l'accepte explicitement
+    pub fn negotiate(accept: &str) -> Self {
+        if accept.contains("application/openmetrics-text") {
+            Format::OpenMetrics
+        } else {
+            Format::Prometheus
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            Format::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+}
+
+/// Texte d'exposition, une famille de métriques à la fois
+pub struct Exposition {
+    format: Format,
+    out: String,
+}
+
+impl Exposition {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            out: String::new(),
+        }
+    }
+
+    /// Jauge sans étiquette
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, "gauge", help, &[(&[], value)]);
+    }
+
+    /// Jauge à une série par jeu d'étiquettes
+    pub fn gauge_with(&mut self, name: &str, help: &str, series: &[(&[(&str, &str)], f64)]) {
+        self.family(name, "gauge", help, series);
+    }
+
+    /// Compteur: `name` sans le suffixe `_total`, ajouté aux échantillons
+    pub fn counter_with(&mut self, name: &str, help: &str, series: &[(&[(&str, &str)], f64)]) {
+        self.family(name, "counter", help, series);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: f64) {
+        self.counter_with(name, help, &[(&[], value)]);
+    }
+
+    fn family(&mut self, name: &str, kind: &str, help: &str, series: &[(&[(&str, &str)], f64)]) {
+        let name = format!("{PREFIX}_{name}");
+        let sample = if kind == "counter" {
+            format!("{name}_total")
+        } else {
+            name.clone()
+        };
+        // Prometheus 0.0.4 type le nom des échantillons, OpenMetrics celui de la famille
+        let family = match self.format {
+            Format::Prometheus => &sample,
+            Format::OpenMetrics => &name,
+        };
+        let _ = writeln!(self.out, "# HELP {family} {help}");
+        let _ = writeln!(self.out, "# TYPE {family} {kind}");
+        for (labels, value) in series {
+            self.out.push_str(&sample);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(k, v)| format!("{k}=\"{}\"", escape(v)))
+                    .collect();
+                let _ = write!(self.out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(self.out, " {value}");
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        if self.format == Format::OpenMetrics {
+            self.out.push_str("# EOF\n");
+        }
+        self.out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Métriques du gouverneur, depuis l'état publié sur le socket de contrôle
+pub fn governor_metrics(status: &GovernorStatus, format: Format) -> String {
+    let mut out = Exposition::new(format);
+    out.gauge(
+        "gpu_load_percent",
+        "GPU load over the sampling window, in percent.",
+        f64::from(status.load_percent),
+    );
+    out.gauge(
+        "frequency_mhz",
+        "Last frequency published by the governor, in MHz.",
+        f64::from(status.current_freq),
+    );
+    out.gauge(
+        "target_frequency_mhz",
+        "Frequency the policy asks for, in MHz.",
+        f64::from(status.target_freq),
+    );
+    out.gauge(
+        "voltage_millivolts",
+        "Voltage written with the last applied frequency, in mV.",
+        f64::from(status.voltage),
+    );
+    out.gauge_with(
+        "governor_mode",
+        "Current policy and mode of the governor (always 1).",
+        &[(&[("policy", &status.policy), ("mode", &status.mode)], 1.0)],
+    );
+    out.gauge(
+        "pinned",
+        "1 while a frequency is pinned through the control socket.",
+        f64::from(u8::from(status.pinned_freq.is_some())),
+    );
+    if let Some(process) = &status.active_process {
+        out.gauge_with(
+            "active_process",
+            "GPU process followed by the process-aware policy (always 1).",
+            &[(&[("process", process)], 1.0)],
+        );
+    }
+    if let Some(profiles) = status.learned_profiles {
+        out.gauge(
+            "learned_profiles",
+            "Profiles in the process database.",
+            profiles as f64,
+        );
+    }
+    out.counter(
+        "frequency_writes",
+        "Frequencies written to pp_od_clk_voltage.",
+        status.frequency_writes as f64,
+    );
+    out.counter_with(
+        "errors",
+        "Errors by kind: verify for clocks the kernel did not apply, trace for failed trace writes.",
+        &[
+            (&[("kind", "verify")], status.verify_mismatches as f64),
+            (&[("kind", "trace")], status.trace_errors as f64),
+        ],
+    );
+    out.finish()
+}
+
+/// Métriques de `gpu_sensor_daemon`
+pub fn sensor_metrics(stats: &SensorStats, format: Format) -> String {
+    let mut out = Exposition::new(format);
+    out.gauge(
+        "gpu_load_percent",
+        "GPU load over the sampling window, in percent.",
+        stats.load_percent,
+    );
+    out.counter(
+        "sensor_updates",
+        "Load values written to the sensor files.",
+        stats.updates as f64,
+    );
+    out.counter_with(
+        "errors",
+        "Errors by kind: read for GPU register reads, write for sensor files.",
+        &[
+            (&[("kind", "read")], stats.read_errors as f64),
+            (&[("kind", "write")], stats.write_errors as f64),
+        ],
+    );
+    out.finish()
+}
+
+/// Sert `GET /metrics` en HTTP/1.1 dans un thread dédié
+///
+/// Les clients sont servis un par un et chaque connexion est fermée après la
+/// réponse: c'est tout ce qu'il faut pour un scrape toutes les quelques
+/// secondes. Renvoie l'adresse réellement écoutée (utile avec le port 0).
+pub fn serve(
+    listen: &str,
+    render: impl Fn(Format) -> String + Send + 'static,
+) -> Result<SocketAddr, IoError> {
+    let listener = TcpListener::bind(listen)?;
+    let addr = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| handle_client(stream, &render));
+            if let Err(e) = result {
+                eprintln!("⚠️  Erreur client métriques: {e}");
+            }
+        }
+    });
+    Ok(addr)
+}
+
+fn handle_client(stream: TcpStream, render: &impl Fn(Format) -> String) -> Result<(), IoError> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream).take(MAX_REQUEST_BYTES as u64);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut accept = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(IoError::new(ErrorKind::InvalidData, "incomplete request"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("accept")
+        {
+            accept = value.trim().to_string();
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (status, content_type, body) = match (method, path.split('?').next()) {
+        ("GET" | "HEAD", Some("/metrics")) => {
+            let format = Format::negotiate(&accept);
+            ("200 OK", format.content_type(), render(format))
+        }
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain", "see /metrics\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "only GET is supported\n".to_string(),
+        ),
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    if method != "HEAD" {
+        writer.write_all(body.as_bytes())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> GovernorStatus {
+        GovernorStatus {
+            current_freq: 1200,
+            load_percent: 87.5,
+            policy: "process-aware".to_string(),
+            mode: "learning".to_string(),
+            active_process: Some("my \"game\"".to_string()),
+            learned_profiles: Some(3),
+            frequency_writes: 42,
+            verify_mismatches: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_governor_metrics() {
+        let text = governor_metrics(&status(), Format::Prometheus);
+        assert!(text.contains("# TYPE cyan_skillfish_gpu_load_percent gauge\n"));
+        assert!(text.contains("\ncyan_skillfish_gpu_load_percent 87.5\n"));
+        assert!(text.contains("\ncyan_skillfish_frequency_mhz 1200\n"));
+        assert!(text.contains(
+            "\ncyan_skillfish_governor_mode{policy=\"process-aware\",mode=\"learning\"} 1\n"
+        ));
+        assert!(text.contains("\ncyan_skillfish_active_process{process=\"my \\\"game\\\"\"} 1\n"));
+        assert!(text.contains("\ncyan_skillfish_learned_profiles 3\n"));
+        assert!(text.contains("# TYPE cyan_skillfish_frequency_writes_total counter\n"));
+        assert!(text.contains("\ncyan_skillfish_frequency_writes_total 42\n"));
+        assert!(text.contains("\ncyan_skillfish_errors_total{kind=\"verify\"} 1\n"));
+        assert!(!text.contains("# EOF"));
+
+        let text = governor_metrics(&GovernorStatus::default(), Format::OpenMetrics);
+        assert!(text.contains("# TYPE cyan_skillfish_frequency_writes counter\n"));
+        assert!(!text.contains("active_process"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_sensor_metrics() {
+        let stats = SensorStats {
+            load_percent: 12.0,
+            updates: 5,
+            read_errors: 2,
+            write_errors: 0,
+        };
+        let text = sensor_metrics(&stats, Format::Prometheus);
+        assert!(text.contains("\ncyan_skillfish_gpu_load_percent 12\n"));
+        assert!(text.contains("\ncyan_skillfish_sensor_updates_total 5\n"));
+        assert!(text.contains("\ncyan_skillfish_errors_total{kind=\"read\"} 2\n"));
+    }
+
+    #[test]
+    fn test_http() {
+        let addr = serve("127.0.0.1:0", |format| governor_metrics(&status(), format)).unwrap();
+        let get = |request: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = get("GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains("\ncyan_skillfish_frequency_mhz 1200\n"));
+
+        let response = get(
+            "GET /metrics HTTP/1.1\r\nAccept: application/openmetrics-text; version=1.0.0\r\n\r\n",
+        );
+        assert!(response.contains("Content-Type: application/openmetrics-text"));
+        assert!(response.ends_with("# EOF\n"));
+
+        assert!(get("GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+        assert!(get("POST /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405"));
+    }
+}
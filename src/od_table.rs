@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind};
+
+/// Point de la courbe de tension (`OD_VDDC` / `OD_VDDC_CURVE`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VddcPoint {
+    /// MHz, absent sur Cyan Skillfish (`0: 900mV`)
+    pub freq: Option<u16>,
+    /// mV
+    pub voltage: u16,
+}
+
+/// Bornes acceptées par le noyau (`OD_RANGE`), (min, max)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OdRange {
+    /// MHz
+    pub sclk: Option<(u16, u16)>,
+    /// mV
+    pub vddc: Option<(u16, u16)>,
+}
+
+/// Contenu de `pp_od_clk_voltage`
+///
+/// Les sections et lignes inconnues (`OD_MCLK`, …) sont ignorées. Un fichier
+/// vide (overdrive désactivé) donne une table vide.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OdTable {
+    /// `OD_SCLK`, MHz par index
+    pub sclk: Vec<u16>,
+    pub vddc_curve: Vec<VddcPoint>,
+    pub range: OdRange,
+}
+
+impl OdRange {
+    /// Vérifie que les `safe-points` restent dans la plage de tension du noyau
+    ///
+    /// Sans `OD_RANGE` (overdrive désactivé), rien n'est vérifié.
+    pub fn check_safe_points(&self, safe_points: &BTreeMap<u16, u16>) -> Result<(), IoError> {
+        let Some((min, max)) = self.vddc else {
+            return Ok(());
+        };
+        for (freq, voltage) in safe_points {
+            if !(min..=max).contains(voltage) {
+                return Err(IoError::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "safe point {freq} MHz @ {voltage} mV is outside the kernel voltage \
+                        range [{min} - {max}] mV"
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OdTable {
+    pub fn parse(content: &str) -> Result<Self, IoError> {
+        let mut table = Self::default();
+        // VDDC_CURVE_VOLT[i], réunis si `VDDC` est absent
+        let mut curve_volt: Option<(u16, u16)> = None;
+        let mut section = "";
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let invalid = |what: &str| {
+                IoError::new(
+                    ErrorKind::InvalidData,
+                    format!("pp_od_clk_voltage line {}: {what} in \"{line}\"", i + 1),
+                )
+            };
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_suffix(':')
+                && !header.contains(' ')
+            {
+                section = header;
+                continue;
+            }
+            let (key, values) = line.split_once(':').ok_or_else(|| invalid("missing ':'"))?;
+            let values: Vec<&str> = values.split_whitespace().filter(|v| *v != "*").collect();
+
+            match section {
+                "OD_SCLK" => {
+                    let [freq] = values[..] else {
+                        return Err(invalid("expected one frequency"));
+                    };
+                    table
+                        .sclk
+                        .push(parse_unit(freq, "mhz").ok_or_else(|| invalid("bad frequency"))?);
+                }
+                "OD_VDDC" | "OD_VDDC_CURVE" => {
+                    let point = match values[..] {
+                        [voltage] => VddcPoint {
+                            freq: None,
+                            voltage: parse_unit(voltage, "mv")
+                                .ok_or_else(|| invalid("bad voltage"))?,
+                        },
+                        [freq, voltage] => VddcPoint {
+                            freq: Some(
+                                parse_unit(freq, "mhz").ok_or_else(|| invalid("bad frequency"))?,
+                            ),
+                            voltage: parse_unit(voltage, "mv")
+                                .ok_or_else(|| invalid("bad voltage"))?,
+                        },
+                        _ => return Err(invalid("expected a voltage")),
+                    };
+                    table.vddc_curve.push(point);
+                }
+                "OD_RANGE" => {
+                    let unit = if key == "SCLK" || key.starts_with("VDDC_CURVE_SCLK") {
+                        "mhz"
+                    } else if key == "VDDC" || key.starts_with("VDDC_CURVE_VOLT") {
+                        "mv"
+                    } else {
+                        continue;
+                    };
+                    let [min, max] = values[..] else {
+                        return Err(invalid("expected a min and a max"));
+                    };
+                    let bounds = (
+                        parse_unit(min, unit).ok_or_else(|| invalid("bad minimum"))?,
+                        parse_unit(max, unit).ok_or_else(|| invalid("bad maximum"))?,
+                    );
+                    match key {
+                        "SCLK" => table.range.sclk = Some(bounds),
+                        "VDDC" => table.range.vddc = Some(bounds),
+                        _ if unit == "mv" => {
+                            curve_volt = Some(match curve_volt {
+                                None => bounds,
+                                Some((min, max)) => (min.min(bounds.0), max.max(bounds.1)),
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        table.range.vddc = table.range.vddc.or(curve_volt);
+        Ok(table)
+    }
+
+    /// Point fréquence (MHz) / tension (mV) réglé par l'utilisateur
+    ///
+    /// 0 MHz ou 0 mV signifie « pas de réglage manuel ».
+    pub fn user_point(&self) -> Option<(u16, u16)> {
+        match (*self.sclk.first()?, self.vddc_curve.first()?.voltage) {
+            (0, _) | (_, 0) => None,
+            point => Some(point),
+        }
+    }
+}
+
+/// `1000Mhz`, `875MHz`, `900mV` (unité insensible à la casse)
+pub(crate) fn parse_unit(value: &str, unit: &str) -> Option<u16> {
+    let split = value.len().checked_sub(unit.len())?;
+    if !value.is_char_boundary(split) || !value[split..].eq_ignore_ascii_case(unit) {
+        return None;
+    }
+    value[..split].parse().ok()
+}
+
+impl fmt::Display for OdTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Self::default() {
+            return write!(f, "OD table: empty (overdrive disabled?)");
+        }
+        write!(f, "OD table:")?;
+        for (i, freq) in self.sclk.iter().enumerate() {
+            write!(f, "\n  SCLK[{i}]: {freq} MHz")?;
+        }
+        for (i, point) in self.vddc_curve.iter().enumerate() {
+            match point.freq {
+                Some(freq) => write!(f, "\n  VDDC[{i}]: {freq} MHz @ {} mV", point.voltage)?,
+                None => write!(f, "\n  VDDC[{i}]: {} mV", point.voltage)?,
+            }
+        }
+        if let Some((min, max)) = self.range.sclk {
+            write!(f, "\n  SCLK range: {min} - {max} MHz")?;
+        }
+        if let Some((min, max)) = self.range.vddc {
+            write!(f, "\n  VDDC range: {min} - {max} mV")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &str) -> OdTable {
+        let path = format!(
+            "{}/testdata/pp_od_clk_voltage/{name}.txt",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        OdTable::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_cyan_skillfish_stock() {
+        let table = sample("cyan_skillfish_stock");
+        assert_eq!(table.sclk, [1000]);
+        assert_eq!(
+            table.vddc_curve,
+            [VddcPoint {
+                freq: None,
+                voltage: 900
+            }]
+        );
+        assert_eq!(table.range.sclk, Some((1000, 2000)));
+        assert_eq!(table.range.vddc, Some((700, 1129)));
+        assert_eq!(table.user_point(), Some((1000, 900)));
+    }
+
+    #[test]
+    fn test_cyan_skillfish_auto() {
+        let table = sample("cyan_skillfish_auto");
+        assert_eq!(table.user_point(), None);
+        assert_eq!(table.range.sclk, Some((350, 2000)));
+    }
+
+    #[test]
+    fn test_vddc_curve_format() {
+        let table = sample("navi10_vddc_curve");
+        assert_eq!(table.sclk, [300, 2000]);
+        assert_eq!(table.vddc_curve.len(), 3);
+        assert_eq!(table.vddc_curve[2].freq, Some(2000));
+        assert_eq!(table.range.sclk, Some((300, 2150)));
+        assert_eq!(table.range.vddc, Some((750, 1200)));
+    }
+
+    #[test]
+    fn test_overdrive_disabled() {
+        let table = sample("overdrive_disabled");
+        assert_eq!(table, OdTable::default());
+        assert!(
+            table
+                .range
+                .check_safe_points(&BTreeMap::from([(2000, 5000)]))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_check_safe_points() {
+        let table = sample("cyan_skillfish_stock");
+        assert!(
+            table
+                .range
+                .check_safe_points(&BTreeMap::from([(350, 700), (2000, 1000)]))
+                .is_ok()
+        );
+        let err = table
+            .range
+            .check_safe_points(&BTreeMap::from([(350, 650), (2000, 1000)]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "safe point 350 MHz @ 650 mV is outside the kernel voltage range [700 - 1129] mV"
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let err = OdTable::parse("OD_SCLK:\n0: fastMhz *\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("pp_od_clk_voltage line 2"));
+    }
+}
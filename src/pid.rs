@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+/// Régulateur PID de la charge (section `[pid]`, avec `policy = "pid"`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PidConfig {
+    /// Pourcentage (0-100): charge visée
+    pub setpoint: f32,
+    /// MHz par % d'écart
+    pub kp: f32,
+    /// MHz par %·s d'écart cumulé
+    pub ki: f32,
+    /// MHz par %/s de variation de l'écart
+    pub kd: f32,
+}
+
+/// Régulateur PID: charge GPU en entrée, fréquence (MHz) en sortie
+///
+/// Le terme intégral porte la fréquence de repos. Il n'est pas intégré tant
+/// que la sortie est saturée dans le sens de l'écart (anti-windup), et reste
+/// dans les bornes: la fréquence quitte une borne dès que l'écart change de signe.
+#[derive(Debug, Clone)]
+pub struct PidController {
+    /// MHz
+    integral: f32,
+    last_error: Option<f32>,
+}
+
+impl PidController {
+    /// Régulateur démarrant à `freq`, sans à-coup
+    pub fn new(freq: f32) -> Self {
+        Self {
+            integral: freq,
+            last_error: None,
+        }
+    }
+
+    /// Repart de `freq` (fréquence figée, autre politique …)
+    pub fn reset(&mut self, freq: f32) {
+        *self = Self::new(freq);
+    }
+
+    /// Fréquence pour la charge `load`, mesurée `dt` secondes après la précédente
+    ///
+    /// `bounds` (min, max) en MHz: la sortie y est toujours ramenée.
+    pub fn update(&mut self, config: &PidConfig, load: f32, dt: f32, bounds: (f32, f32)) -> f32 {
+        let (min, max) = bounds;
+        // Positif quand le GPU est trop chargé: il faut monter
+        let error = load - config.setpoint;
+        let derivative = match self.last_error {
+            Some(last) if dt > 0.0 => (error - last) / dt,
+            _ => 0.0,
+        };
+        self.last_error = Some(error);
+
+        let integral = self.integral + config.ki * error * dt;
+        let output = integral + config.kp * error + config.kd * derivative;
+        let saturated = (output > max && error > 0.0) || (output < min && error < 0.0);
+        if !saturated {
+            self.integral = integral.clamp(min, max);
+        }
+        output.clamp(min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: (f32, f32) = (350.0, 2000.0);
+
+    fn config() -> PidConfig {
+        PidConfig {
+            setpoint: 85.0,
+            kp: 10.0,
+            ki: 20.0,
+            kd: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_on_setpoint_holds() {
+        let mut pid = PidController::new(1200.0);
+        for _ in 0..100 {
+            assert_eq!(pid.update(&config(), 85.0, 0.01, BOUNDS), 1200.0);
+        }
+    }
+
+    #[test]
+    fn test_proportional_and_integral() {
+        let mut pid = PidController::new(1000.0);
+        // 10 * 15 + 1000 + 20 * 15 * 0.5
+        assert_eq!(pid.update(&config(), 100.0, 0.5, BOUNDS), 1300.0);
+        assert_eq!(pid.update(&config(), 85.0, 0.5, BOUNDS), 1150.0);
+    }
+
+    #[test]
+    fn test_derivative() {
+        let config = PidConfig {
+            kp: 0.0,
+            ki: 0.0,
+            kd: 2.0,
+            ..config()
+        };
+        let mut pid = PidController::new(1000.0);
+        // Pas de dérivée sur le premier échantillon
+        assert_eq!(pid.update(&config, 85.0, 0.5, BOUNDS), 1000.0);
+        assert_eq!(pid.update(&config, 95.0, 0.5, BOUNDS), 1040.0);
+    }
+
+    #[test]
+    fn test_anti_windup() {
+        let mut pid = PidController::new(1000.0);
+        // Pleine charge pendant longtemps: la sortie reste à la borne haute …
+        for _ in 0..10_000 {
+            assert!(pid.update(&config(), 100.0, 0.01, BOUNDS) <= 2000.0);
+        }
+        // … et redescend dès que la charge passe sous la consigne
+        assert!(pid.update(&config(), 50.0, 0.01, BOUNDS) < 2000.0);
+
+        let mut pid = PidController::new(1000.0);
+        for _ in 0..10_000 {
+            pid.update(&config(), 0.0, 0.01, BOUNDS);
+        }
+        assert!(pid.update(&config(), 100.0, 0.01, BOUNDS) > 350.0);
+    }
+}
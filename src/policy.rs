@@ -0,0 +1,751 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GovernorConfig;
+use crate::constants::*;
+use crate::game_overrides::{self, GameOverride};
+use crate::governor::{GovernorMode, ProcessAwareGovernor};
+use crate::pid::PidController;
+use crate::profile_db::{ProcessDatabase, ProcessProfile, steam_app_id};
+
+/// Politiques disponibles, choisies par leur nom (`policy`, `set-policy`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    /// Rampes au-delà de `load-target`, plus le burst
+    #[default]
+    Threshold,
+    /// Régulateur PID vers `pid.setpoint`
+    Pid,
+    /// Fréquence apprise par processus (voir `governor`)
+    ProcessAware,
+}
+
+impl Policy {
+    pub const ALL: [Self; 3] = [Self::Threshold, Self::Pid, Self::ProcessAware];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Threshold => "threshold",
+            Self::Pid => "pid",
+            Self::ProcessAware => "process-aware",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    /// Nouvelle instance partant de `freq` (MHz)
+    ///
+    /// `process-aware` charge la base de profils de `config.profiles`.
+    pub fn build(&self, freq: u16, config: &GovernorConfig) -> Box<dyn FrequencyPolicy> {
+        match self {
+            Self::Threshold => Box::new(ThresholdPolicy::new(freq)),
+            Self::Pid => Box::new(PidPolicy::new(freq)),
+            Self::ProcessAware => Box::new(ProcessAwarePolicy::new(ProcessDatabase::with_path(
+                config.profiles.database_path(),
+                &config.paths,
+            ))),
+        }
+    }
+}
+
+/// Échantillon d'activité GPU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadSample {
+    /// GUI_ACTIVE lors de cet échantillon
+    pub busy: bool,
+    /// Pourcentage (0-100), moyenne sur la fenêtre glissante
+    pub load_percent: f32,
+    pub timestamp: Instant,
+}
+
+/// Ce que la boucle sait en dehors de la charge
+#[derive(Debug, Clone, Copy)]
+pub struct PolicyContext<'a> {
+    pub config: &'a GovernorConfig,
+    /// MHz: plage sûre, bornée par le GPU
+    pub min_freq: u16,
+    pub max_freq: u16,
+    /// Dernier changement de fréquence publié
+    pub last_change: Instant,
+    /// Clé du profil du processus GPU principal (`steam:<appid>` ou son nom),
+    /// si la politique les suit
+    pub active_process: Option<&'a str>,
+    /// Nom lisible du même processus
+    pub active_process_name: Option<&'a str>,
+    /// Le processus principal n'a pas changé depuis `PROCESS_STABILITY_SECS`
+    pub process_stable: bool,
+}
+
+/// Fréquence voulue par une politique
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    /// MHz, dans la plage sûre
+    pub freq: u16,
+    /// État de la politique (`ramp-up`, `burst`, `learning` …), publié comme mode
+    pub mode: &'static str,
+    /// Raison journalisée si la fréquence change
+    pub reason: Option<&'static str>,
+    /// À publier sans attendre `timing.intervals.adjust` ni `frequency-thresholds`
+    pub immediate: bool,
+}
+
+/// Choisit la prochaine fréquence à partir de la charge
+pub trait FrequencyPolicy: Send {
+    /// Nom dans la config et pour `set-policy`
+    fn name(&self) -> &'static str;
+
+    /// Appelé à chaque échantillon; `current_freq` est la dernière fréquence publiée
+    fn decide(&mut self, sample: &LoadSample, current_freq: u16, ctx: &PolicyContext) -> Decision;
+
+    /// La fréquence a été imposée de l'extérieur (`pin`): repartir de `freq`
+    fn reset(&mut self, _freq: u16) {}
+
+    /// La boucle doit renseigner `active_process` / `process_stable`
+    fn uses_processes(&self) -> bool {
+        false
+    }
+
+    /// Avancement d'un apprentissage en cours (0-1)
+    fn learning_progress(&self) -> Option<f32> {
+        None
+    }
+
+    /// Profils appris, pour les politiques qui en ont
+    fn learned_profiles(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Rampes au-delà de `load-target`, burst et optimisation en zone stable
+pub struct ThresholdPolicy {
+    /// MHz, fractionnaire pour accumuler les rampes lentes
+    target: f32,
+    /// Derniers échantillons, le plus récent dans le bit de poids faible
+    samples: u64,
+}
+
+impl ThresholdPolicy {
+    pub fn new(freq: u16) -> Self {
+        Self {
+            target: f32::from(freq),
+            samples: 0,
+        }
+    }
+}
+
+impl FrequencyPolicy for ThresholdPolicy {
+    fn name(&self) -> &'static str {
+        Policy::Threshold.name()
+    }
+
+    fn decide(&mut self, sample: &LoadSample, _current_freq: u16, ctx: &PolicyContext) -> Decision {
+        let config = ctx.config;
+        let timing = &config.timing;
+        // ms
+        let sampling_interval = f32::from(timing.intervals.sample) / 1000.0;
+        let ramp_rate = timing.ramp_rates.normal;
+        let (up_thresh, down_thresh) = (config.load_target.upper, config.load_target.lower);
+        let load = sample.load_percent;
+
+        // Buffer for burst detection
+        self.samples = self.samples << 1 | u64::from(sample.busy);
+        let burst = config
+            .burst_mask()
+            .is_some_and(|mask| self.samples & mask == mask);
+
+        // Stability zone: avoids oscillations between thresholds
+        // Except if optimization is enabled: slowly decrease to optimize
+        let in_stable_zone = load >= down_thresh && load <= up_thresh;
+        let stable_duration = sample.timestamp.saturating_duration_since(ctx.last_change);
+        let optimize_interval = timing.intervals.optimize;
+        let can_optimize = optimize_interval > 0
+            && in_stable_zone
+            && stable_duration >= Duration::from_micros(optimize_interval)
+            && load < (up_thresh - 2.0); // 2% margin: if already close to target, do nothing
+
+        let (mode, reason) = if burst {
+            // Burst: fast ramp up
+            self.target += timing.ramp_rates.burst * sampling_interval;
+            ("burst", Some("activity burst detected"))
+        } else if load > up_thresh {
+            self.target += ramp_rate * sampling_interval;
+            ("ramp-up", None)
+        } else if load < down_thresh {
+            self.target -= ramp_rate * sampling_interval;
+            ("ramp-down", None)
+        } else if can_optimize {
+            // Slow decrease (10% of normal speed) to increase load
+            self.target -= ramp_rate * 0.1 * sampling_interval;
+            ("optimize", Some("energy optimization"))
+        } else {
+            ("stable", None)
+        };
+        self.target = self
+            .target
+            .clamp(f32::from(ctx.min_freq), f32::from(ctx.max_freq));
+
+        Decision {
+            freq: self.target as u16,
+            mode,
+            reason,
+            immediate: burst,
+        }
+    }
+
+    fn reset(&mut self, freq: u16) {
+        self.target = f32::from(freq);
+    }
+}
+
+/// Régulateur PID vers `pid.setpoint` (voir `pid`)
+pub struct PidPolicy {
+    pid: PidController,
+}
+
+impl PidPolicy {
+    pub fn new(freq: u16) -> Self {
+        Self {
+            pid: PidController::new(f32::from(freq)),
+        }
+    }
+}
+
+impl FrequencyPolicy for PidPolicy {
+    fn name(&self) -> &'static str {
+        Policy::Pid.name()
+    }
+
+    fn decide(&mut self, sample: &LoadSample, _current_freq: u16, ctx: &PolicyContext) -> Decision {
+        // Intervalle nominal, comme les rampes de `threshold`
+        let dt = f32::from(ctx.config.timing.intervals.sample) / 1_000_000.0;
+        let bounds = (f32::from(ctx.min_freq), f32::from(ctx.max_freq));
+        let freq = self
+            .pid
+            .update(&ctx.config.pid, sample.load_percent, dt, bounds);
+        Decision {
+            freq: freq as u16,
+            mode: "pid",
+            reason: None,
+            immediate: false,
+        }
+    }
+
+    fn reset(&mut self, freq: u16) {
+        self.pid.reset(f32::from(freq));
+    }
+}
+
+/// Les tailles d'historique de `ProcessAwareGovernor` supposent un échantillon
+/// toutes les 10 ms
+const PROCESS_AWARE_SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Fréquence apprise par processus, enregistrée dans la base de profils
+///
+/// Un processus inconnu est appris pendant `LEARNING_DURATION_SECS` par paliers
+/// de `FREQ_STEP_MHZ`; un processus connu reçoit sa fréquence, réévaluée si la
+/// charge reste trop haute ou trop basse. Une section `[[game]]` passe avant
+/// la base: fréquence fixe, bornes, autre politique ou pas d'apprentissage.
+pub struct ProcessAwarePolicy {
+    governor: ProcessAwareGovernor,
+    db: ProcessDatabase,
+    /// Clé du profil suivi
+    tracked: Option<String>,
+    tracked_name: Option<String>,
+    /// `[[game]]` du processus suivi, lue au changement de processus
+    game: Option<GameOverride>,
+    /// Politique qui décide à la place de l'apprentissage pour ce jeu
+    delegate: Option<Box<dyn FrequencyPolicy>>,
+    last_sample: Option<Instant>,
+}
+
+impl ProcessAwarePolicy {
+    pub fn new(db: ProcessDatabase) -> Self {
+        Self {
+            governor: ProcessAwareGovernor::new(),
+            db,
+            tracked: None,
+            tracked_name: None,
+            game: None,
+            delegate: None,
+            last_sample: None,
+        }
+    }
+
+    pub fn governor(&self) -> &ProcessAwareGovernor {
+        &self.governor
+    }
+
+    pub fn database(&self) -> &ProcessDatabase {
+        &self.db
+    }
+
+    /// Profil appris pour le processus suivi, avec son nom, son AppID et la
+    /// courbe de tension de `config`
+    fn finalize_profile(&mut self, config: &GovernorConfig) -> Option<ProcessProfile> {
+        let key = self.tracked.as_ref()?;
+        let mut profile = self.governor.finalize_learning()?;
+        profile.name = self.tracked_name.clone().unwrap_or_else(|| key.clone());
+        profile.steam_app_id = steam_app_id(key);
+        profile.voltage_curve = Some(config.voltage_curve().fingerprint());
+        Some(profile)
+    }
+
+    fn switch_process(
+        &mut self,
+        process: Option<&str>,
+        name: Option<&str>,
+        current_freq: u16,
+        config: &GovernorConfig,
+    ) {
+        self.game = None;
+        self.delegate = None;
+        if let Some(new_process) = process {
+            match name {
+                Some(name) if name != new_process => {
+                    println!("\n🔄 Nouveau processus GPU: '{}' ({})", name, new_process)
+                }
+                _ => println!("\n🔄 Nouveau processus GPU: '{}'", new_process),
+            }
+
+            // Sauvegarder le profil du processus précédent si en apprentissage
+            if matches!(
+                self.governor.mode,
+                GovernorMode::Learning | GovernorMode::Reevaluating
+            ) && let Some(old_process) = &self.tracked
+            {
+                println!(
+                    "   Sauvegarde profil de '{}' (apprentissage interrompu)",
+                    self.tracked_name.as_ref().unwrap_or(old_process)
+                );
+                if let Some(profile) = self.finalize_profile(config) {
+                    self.db.set(profile);
+                }
+            }
+
+            // Un jeu appris sous son nom avant que son AppID soit lu garde son profil
+            if let Some(name) = name {
+                self.db.adopt(new_process, name);
+            }
+
+            // `[[game]]` passe avant les profils appris
+            let game = game_overrides::find(&config.games, new_process, name).cloned();
+            let delegate = game
+                .as_ref()
+                .and_then(|g| g.policy)
+                .filter(|p| *p != Policy::ProcessAware);
+            let learning = game.as_ref().is_none_or(|g| g.learning);
+
+            // Charger ou démarrer apprentissage pour le nouveau processus
+            if let Some(freq) = game.as_ref().and_then(|g| g.freq) {
+                println!("   📌 Fréquence imposée par [[game]]: {} MHz", freq);
+                self.governor.apply_known_frequency(freq);
+            } else if let Some(policy) = delegate {
+                println!("   📌 Politique imposée par [[game]]: {}", policy.name());
+                self.delegate = Some(policy.build(current_freq, config));
+                self.governor.enter_idle();
+            } else if let Some(profile) = self.db.get(new_process) {
+                println!(
+                    "   ✓ Profil connu trouvé: {} MHz (confort: {:.1}/100, {} échantillons)",
+                    profile.optimal_freq, profile.comfort_score, profile.samples_count
+                );
+                let freq = profile.optimal_freq;
+                self.governor.apply_known_frequency(freq);
+            } else if !learning {
+                println!("   📌 Apprentissage désactivé par [[game]], politique threshold");
+                self.delegate = Some(Policy::Threshold.build(current_freq, config));
+                self.governor.enter_idle();
+            } else {
+                println!(
+                    "   ⚠ Processus inconnu, lancement apprentissage ({} secondes)",
+                    LEARNING_DURATION_SECS
+                );
+                self.governor.start_learning(MIN_FREQ_MHZ);
+            }
+            if let Some(game) = &game {
+                self.governor.current_freq = game.clamp(self.governor.current_freq);
+            }
+            self.game = game;
+        } else if self.tracked.is_some() {
+            // Plus de processus GPU actif (ou seulement des processus inactifs)
+            println!("\n💤 Aucune activité GPU significative (processus desktop ignorés)");
+            self.governor.enter_idle();
+        }
+        self.tracked = process.map(str::to_string);
+        self.tracked_name = name.map(str::to_string);
+    }
+
+    fn add_load_sample(
+        &mut self,
+        load: f32,
+        timestamp: Instant,
+        process_stable: bool,
+        config: &GovernorConfig,
+    ) {
+        let governor = &mut self.governor;
+        governor.add_load_sample(load);
+        let freq = governor.current_freq;
+        if let Some(stats) = &mut governor.learning_stats {
+            stats.set_frequency(freq, load);
+        }
+
+        match governor.mode {
+            GovernorMode::Idle => {}
+            GovernorMode::Applied => {
+                // Fréquence fixée ou apprentissage désactivé par `[[game]]`
+                if self
+                    .game
+                    .as_ref()
+                    .is_some_and(|g| g.freq.is_some() || !g.learning)
+                {
+                    return;
+                }
+                let saturated = governor.check_saturation();
+                if !(saturated || governor.check_underload()) || !process_stable {
+                    return;
+                }
+                let Some(profile) = self.tracked.as_ref().and_then(|p| self.db.get(p)) else {
+                    return;
+                };
+                if saturated {
+                    println!(
+                        "\n⚠ SURCHARGE DÉTECTÉE: Charge > {:.0}% pendant 60s (moyenne: {:.1}%)",
+                        HIGH_LOAD_THRESHOLD,
+                        governor.average_load()
+                    );
+                    println!(
+                        "   La config graphique a peut-être changé, augmentation par palier de {} MHz",
+                        FREQ_STEP_MHZ
+                    );
+                } else {
+                    println!(
+                        "\n🔻 SOUS-CHARGE DÉTECTÉE: Charge < {:.0}% pendant 60s (moyenne: {:.1}%)",
+                        LOW_LOAD_THRESHOLD,
+                        governor.average_load()
+                    );
+                    println!(
+                        "   La config graphique a peut-être changé, réduction par palier de {} MHz",
+                        FREQ_STEP_MHZ
+                    );
+                }
+                governor.start_reevaluation(profile.optimal_freq);
+            }
+            GovernorMode::Learning | GovernorMode::Reevaluating => {
+                // Ajustement dynamique pendant l'apprentissage
+                let old_freq = governor.current_freq;
+                if let Some(new_freq) = governor.try_adjust_learning() {
+                    // Les paliers restent dans les bornes de `[[game]]`
+                    governor.current_freq =
+                        self.game.as_ref().map_or(new_freq, |g| g.clamp(new_freq));
+                }
+                let new_freq = governor.current_freq;
+                if new_freq != old_freq {
+                    let direction = if new_freq > old_freq { "↑" } else { "↓" };
+                    println!(
+                        "   [{}] {} MHz {} {} MHz (charge: {:.1}%, palier: ±{} MHz)",
+                        if governor.mode == GovernorMode::Learning {
+                            "LEARNING"
+                        } else {
+                            "REEVALUAT"
+                        },
+                        old_freq,
+                        direction,
+                        new_freq,
+                        load,
+                        FREQ_STEP_MHZ
+                    );
+                }
+
+                let learning_done = timestamp.saturating_duration_since(governor.mode_start)
+                    >= Duration::from_secs(LEARNING_DURATION_SECS);
+                if learning_done
+                    && process_stable
+                    && let Some(profile) = self.finalize_profile(config)
+                {
+                    println!(
+                        "\n✓ Apprentissage terminé pour '{}': {} MHz optimal",
+                        profile.name, profile.optimal_freq
+                    );
+                    let freq = profile.optimal_freq;
+                    self.db.set(profile);
+                    self.governor.apply_known_frequency(freq);
+                }
+            }
+        }
+    }
+}
+
+impl FrequencyPolicy for ProcessAwarePolicy {
+    fn name(&self) -> &'static str {
+        Policy::ProcessAware.name()
+    }
+
+    fn decide(&mut self, sample: &LoadSample, current_freq: u16, ctx: &PolicyContext) -> Decision {
+        if ctx.active_process != self.tracked.as_deref() {
+            self.switch_process(
+                ctx.active_process,
+                ctx.active_process_name,
+                current_freq,
+                ctx.config,
+            );
+        }
+        if let Some(delegate) = &mut self.delegate {
+            let mut decision = delegate.decide(sample, current_freq, ctx);
+            if let Some(game) = &self.game {
+                decision.freq = game.clamp(decision.freq).clamp(ctx.min_freq, ctx.max_freq);
+            }
+            return decision;
+        }
+        let due = self.last_sample.is_none_or(|last| {
+            sample.timestamp.saturating_duration_since(last) >= PROCESS_AWARE_SAMPLE_INTERVAL
+        });
+        if due {
+            self.last_sample = Some(sample.timestamp);
+            self.add_load_sample(
+                sample.load_percent,
+                sample.timestamp,
+                ctx.process_stable,
+                ctx.config,
+            );
+        }
+
+        let mode = self.governor.mode;
+        let fixed = self.game.as_ref().is_some_and(|g| g.freq.is_some());
+        let freq = self.governor.current_freq;
+        Decision {
+            freq: self
+                .game
+                .as_ref()
+                .map_or(freq, |g| g.clamp(freq))
+                .clamp(ctx.min_freq, ctx.max_freq),
+            mode: mode.as_str(),
+            reason: Some(match mode {
+                GovernorMode::Idle => "no GPU process",
+                GovernorMode::Applied if fixed => "game override",
+                GovernorMode::Applied => "known profile",
+                GovernorMode::Learning => "learning",
+                GovernorMode::Reevaluating => "re-evaluation",
+            }),
+            // Les paliers sont déjà espacés de MIN_CHANGE_INTERVAL_SECS
+            immediate: true,
+        }
+    }
+
+    fn reset(&mut self, freq: u16) {
+        if let Some(delegate) = &mut self.delegate {
+            delegate.reset(freq);
+        }
+    }
+
+    fn uses_processes(&self) -> bool {
+        true
+    }
+
+    fn learning_progress(&self) -> Option<f32> {
+        self.governor.learning_progress()
+    }
+
+    fn learned_profiles(&self) -> Option<usize> {
+        Some(self.db.profiles.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::TempDir;
+    use crate::profile_db::DATABASE_FILE;
+
+    fn context(config: &GovernorConfig) -> PolicyContext<'_> {
+        PolicyContext {
+            config,
+            min_freq: 350,
+            max_freq: 2000,
+            last_change: Instant::now(),
+            active_process: None,
+            active_process_name: None,
+            process_stable: false,
+        }
+    }
+
+    fn sample(busy: bool, load_percent: f32) -> LoadSample {
+        LoadSample {
+            busy,
+            load_percent,
+            timestamp: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for policy in Policy::ALL {
+            assert_eq!(Policy::from_name(policy.name()), Some(policy));
+        }
+        assert_eq!(Policy::from_name("ondemand"), None);
+        assert_eq!(
+            Policy::Pid
+                .build(350, &GovernorConfig::parse("").unwrap())
+                .name(),
+            "pid"
+        );
+    }
+
+    #[test]
+    fn test_threshold_ramps() {
+        // 2 ms * 1 MHz/ms par échantillon
+        let config = GovernorConfig::parse("[timing]\nburst-samples = 0").unwrap();
+        let ctx = context(&config);
+        let mut policy = ThresholdPolicy::new(1000);
+
+        let decision = policy.decide(&sample(true, 100.0), 1000, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1002, "ramp-up"));
+        assert!(!decision.immediate);
+        let decision = policy.decide(&sample(false, 0.0), 1000, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1000, "ramp-down"));
+        let decision = policy.decide(&sample(true, 85.0), 1000, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1000, "stable"));
+
+        policy.reset(350);
+        let decision = policy.decide(&sample(false, 0.0), 350, &ctx);
+        assert_eq!(decision.freq, 350);
+    }
+
+    #[test]
+    fn test_threshold_burst_is_immediate() {
+        let config = GovernorConfig::parse("[timing]\nburst-samples = 3").unwrap();
+        let ctx = context(&config);
+        let mut policy = ThresholdPolicy::new(1000);
+        for _ in 0..2 {
+            assert_ne!(policy.decide(&sample(true, 50.0), 1000, &ctx).mode, "burst");
+        }
+        let decision = policy.decide(&sample(true, 50.0), 1000, &ctx);
+        assert_eq!(decision.mode, "burst");
+        assert_eq!(decision.reason, Some("activity burst detected"));
+        assert!(decision.immediate);
+        // 1000 - 2 * 2 MHz de rampe normale, puis 2 ms * 50 MHz/ms
+        assert_eq!(decision.freq, 1096);
+    }
+
+    #[test]
+    fn test_threshold_optimizes_when_stable() {
+        let config = GovernorConfig::parse("[timing.intervals]\noptimize = 1000").unwrap();
+        let mut ctx = context(&config);
+        ctx.last_change -= Duration::from_secs(1);
+        let mut policy = ThresholdPolicy::new(1000);
+        let decision = policy.decide(&sample(false, 85.0), 1000, &ctx);
+        assert_eq!(decision.mode, "optimize");
+        assert_eq!(decision.reason, Some("energy optimization"));
+        assert!(decision.freq < 1000);
+    }
+
+    #[test]
+    fn test_process_aware() {
+        let dir = TempDir::new("policy-process-aware");
+        let mut db = ProcessDatabase::open(dir.join(DATABASE_FILE));
+        db.set(ProcessProfile::new("game".to_string(), 1400, 90.0, 100));
+        let config = GovernorConfig::parse("").unwrap();
+        let mut ctx = context(&config);
+        let mut policy = ProcessAwarePolicy::new(db);
+        assert!(policy.uses_processes());
+        assert_eq!(policy.learned_profiles(), Some(1));
+
+        ctx.active_process = Some("game");
+        let decision = policy.decide(&sample(true, 70.0), 350, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1400, "applied"));
+        assert!(decision.immediate);
+
+        ctx.active_process = Some("new-game");
+        let decision = policy.decide(&sample(true, 70.0), 1400, &ctx);
+        assert_eq!((decision.freq, decision.mode), (MIN_FREQ_MHZ, "learning"));
+        assert!(policy.learning_progress().unwrap() < 0.1);
+
+        // Apprentissage interrompu: enregistré avec la courbe de tension
+        let start = Instant::now();
+        for n in 1..=5 {
+            let sample = LoadSample {
+                timestamp: start + PROCESS_AWARE_SAMPLE_INTERVAL * n,
+                ..sample(true, 70.0)
+            };
+            policy.decide(&sample, MIN_FREQ_MHZ, &ctx);
+        }
+        ctx.active_process = Some("game");
+        policy.decide(&sample(true, 70.0), MIN_FREQ_MHZ, &ctx);
+        assert_eq!(
+            policy.database().get("new-game").unwrap().voltage_curve,
+            Some(config.voltage_curve().fingerprint())
+        );
+        assert_eq!(policy.database().get("game").unwrap().voltage_curve, None);
+
+        ctx.active_process = None;
+        let decision = policy.decide(&sample(false, 0.0), 350, &ctx);
+        assert_eq!(decision.mode, "idle");
+        assert_eq!(policy.learning_progress(), None);
+
+        // Profil appris sous le nom du jeu avant que l'AppID soit lu
+        ctx.active_process = Some("steam:1145360");
+        ctx.active_process_name = Some("game");
+        let decision = policy.decide(&sample(true, 70.0), 350, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1400, "applied"));
+        let profile = policy.database().get("steam:1145360").unwrap();
+        assert_eq!(
+            (profile.name.as_str(), profile.steam_app_id),
+            ("game", Some(1145360))
+        );
+    }
+
+    #[test]
+    fn test_game_overrides() {
+        let dir = TempDir::new("policy-games");
+        let mut db = ProcessDatabase::open(dir.join(DATABASE_FILE));
+        db.set(ProcessProfile::new("vkcube".to_string(), 1400, 90.0, 100));
+        let config = GovernorConfig::parse(
+            "[[game]]\nsteam-app-id = 1145360\nfreq = 1200\n\
+            [[game]]\nglob = \"*dolphin*\"\nmax-freq = 900\npolicy = \"pid\"\n\
+            [[game]]\nname = \"vkcube\"\nmax-freq = 1000\nlearning = false\n\
+            [[game]]\nname = \"glxgears\"\nlearning = false\n\
+            [[game]]\nname = \"Celeste\"\nmin-freq = 800\n",
+        )
+        .unwrap();
+        let mut ctx = context(&config);
+        let mut policy = ProcessAwarePolicy::new(db);
+
+        // Fréquence fixe, sans apprentissage ni réévaluation
+        ctx.active_process = Some("steam:1145360");
+        ctx.active_process_name = Some("Hades/Hades");
+        let decision = policy.decide(&sample(true, 100.0), 350, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1200, "applied"));
+        assert_eq!(decision.reason, Some("game override"));
+        assert_eq!(policy.learning_progress(), None);
+
+        // Autre politique, bornée
+        ctx.active_process = Some("/usr/bin/dolphin-emu");
+        ctx.active_process_name = None;
+        ctx.last_change -= Duration::from_secs(1);
+        for _ in 0..100 {
+            let decision = policy.decide(&sample(true, 100.0), 900, &ctx);
+            assert!(decision.freq <= 900);
+            assert_ne!(decision.mode, "learning");
+        }
+
+        // Profil connu, borné, jamais réévalué
+        ctx.active_process = Some("vkcube");
+        let decision = policy.decide(&sample(true, 100.0), 900, &ctx);
+        assert_eq!((decision.freq, decision.mode), (1000, "applied"));
+
+        // Sans profil ni apprentissage: `threshold`
+        ctx.active_process = Some("glxgears");
+        let decision = policy.decide(&sample(true, 100.0), 1000, &ctx);
+        assert_ne!(decision.mode, "learning");
+        assert_eq!(policy.learning_progress(), None);
+
+        // Bornes seules: l'apprentissage part du minimum imposé
+        ctx.active_process = Some("Celeste");
+        let decision = policy.decide(&sample(true, 70.0), 1000, &ctx);
+        assert_eq!((decision.freq, decision.mode), (800, "learning"));
+
+        assert_eq!(policy.database().profiles.len(), 1);
+    }
+}
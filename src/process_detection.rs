@@ -1,4 +1,8 @@
-use crate::gpu_info::{is_drm_device, parse_fdinfo_cycles};
+use crate::gpu_info::{FdInfo, is_drm_device};
+use crate::process_rules::ProcessRules;
+use crate::profile_db::profile_key;
+use crate::system_paths::SystemPaths;
+use std::collections::{BTreeMap, HashSet};
 use std::io::Error as IoError;
 
 /// Liste de processus à exclure (desktop, utilitaires, etc.)
@@ -27,26 +31,28 @@ pub const EXCLUDED_PROCESSES: &[&str] = &[
 ];
 
 /// Informations sur un processus utilisant le GPU
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GpuProcess {
-    pub _pid: u32,
+    pub pid: u32,
     pub name: String,
-    pub total_cycles: u64,
+    /// `/proc/<pid>/comm`
+    pub comm: String,
+    /// Cible de `/proc/<pid>/exe`, sans le suffixe ` (deleted)`
+    pub exe: Option<String>,
+    pub cmdline: Vec<String>,
+    /// Nom du jeu Steam, depuis un `.exe` de la ligne de commande ou le cwd
+    pub game: Option<String>,
+    /// AppID Steam du processus ou d'un ancêtre (voir `read_steam_app_id`)
+    pub steam_app_id: Option<u64>,
+    /// ns d'activité cumulés par moteur, sur chaque client DRM du processus
+    pub engines: BTreeMap<String, u64>,
 }
 
-/// Vérifie si un chemin/nom de processus correspond à un processus exclu
-pub fn is_excluded_process(name: &str) -> bool {
-    // Extraire le nom du fichier si c'est un chemin
-    let basename = std::path::Path::new(name)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or(name);
-
-    // Vérifier uniquement l'égalité exacte sur le basename
-    // (ne pas utiliser contains sur le chemin complet pour éviter les faux positifs)
-    EXCLUDED_PROCESSES
-        .iter()
-        .any(|&excluded| basename == excluded)
+impl GpuProcess {
+    /// Clé du profil: `steam:<appid>` pour un jeu Steam, le nom sinon
+    pub fn profile_key(&self) -> String {
+        profile_key(&self.name, self.steam_app_id)
+    }
 }
 
 /// Extrait le nom du jeu depuis un chemin Steam
@@ -68,12 +74,85 @@ pub fn extract_steam_game_name(path: &str) -> Option<String> {
     None
 }
 
+/// Arguments de `/proc/<pid>/cmdline`, vide si illisible (processus noyau, fini)
+pub fn read_cmdline(paths: &SystemPaths, pid: u32) -> Vec<String> {
+    std::fs::read(paths.process(pid).join("cmdline"))
+        .map(|bytes| {
+            String::from_utf8_lossy(&bytes)
+                .split('\0')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Nom du jeu Steam d'un processus: d'abord un `.exe` de `cmdline`, puis le cwd
+pub fn read_steam_game_name(paths: &SystemPaths, pid: u32, cmdline: &[String]) -> Option<String> {
+    cmdline
+        .iter()
+        .filter(|arg| arg.ends_with(".exe"))
+        .find_map(|arg| extract_steam_game_name(arg))
+        .or_else(|| {
+            let cwd = std::fs::read_link(paths.process(pid).join("cwd")).ok()?;
+            extract_steam_game_name(&cwd.to_string_lossy())
+        })
+}
+
+/// Ancêtres parcourus au plus pour trouver l'AppID (jeu → wine → proton → reaper)
+const MAX_PARENT_DEPTH: usize = 16;
+
+/// AppID Steam d'un processus, cherché aussi chez ses parents
+///
+/// Steam exporte `SteamAppId` (ou `SteamGameId` pour les raccourcis hors
+/// Steam) à tout ce qu'il lance, et `reaper` le reçoit en argument `AppId=`.
+/// Un processus qui a vidé son environnement le tient donc d'un ancêtre.
+pub fn read_steam_app_id(paths: &SystemPaths, pid: u32) -> Option<u64> {
+    let mut pid = pid;
+    for _ in 0..MAX_PARENT_DEPTH {
+        if let Some(app_id) = read_environ_app_id(paths, pid).or_else(|| {
+            read_cmdline(paths, pid)
+                .iter()
+                .find_map(|arg| arg.strip_prefix("AppId=")?.parse().ok())
+                .filter(|&id| id != 0)
+        }) {
+            return Some(app_id);
+        }
+        pid = read_parent_pid(paths, pid).filter(|&ppid| ppid > 1)?;
+    }
+    None
+}
+
+/// `SteamAppId`, ou `SteamGameId`, de `/proc/<pid>/environ` (0 = absent)
+fn read_environ_app_id(paths: &SystemPaths, pid: u32) -> Option<u64> {
+    let environ = std::fs::read(paths.process(pid).join("environ")).ok()?;
+    let environ = String::from_utf8_lossy(&environ);
+    let var = |name: &str| {
+        environ
+            .split('\0')
+            .find_map(|entry| entry.strip_prefix(name)?.strip_prefix('='))
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|&id| id != 0)
+    };
+    var("SteamAppId").or_else(|| var("SteamGameId"))
+}
+
+/// `PPid` de `/proc/<pid>/status`
+fn read_parent_pid(paths: &SystemPaths, pid: u32) -> Option<u32> {
+    let status = std::fs::read_to_string(paths.process(pid).join("status")).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("PPid:"))
+        .and_then(|ppid| ppid.trim().parse().ok())
+}
+
 /// Parse le nom d'un processus de manière intelligente
 /// Pour les jeux Wine/Proton, essaie d'extraire le nom du jeu depuis cmdline ou cwd
 /// Sinon utilise le chemin complet de l'exécutable
-pub fn read_process_name(pid: u32) -> Result<String, IoError> {
+pub fn read_process_name(paths: &SystemPaths, pid: u32) -> Result<String, IoError> {
+    let proc_dir = paths.process(pid);
     // D'abord essayer de lire cmdline pour les jeux Wine/Proton
-    let cmdline_path = format!("/proc/{}/cmdline", pid);
+    let cmdline_path = proc_dir.join("cmdline");
     if let Ok(cmdline_bytes) = std::fs::read(&cmdline_path) {
         let cmdline = String::from_utf8_lossy(&cmdline_bytes);
         // Les arguments sont séparés par des null bytes
@@ -113,14 +192,14 @@ pub fn read_process_name(pid: u32) -> Result<String, IoError> {
     }
 
     // Si pas de .exe trouvé, essayer le répertoire de travail (cwd)
-    let cwd_path = format!("/proc/{}/cwd", pid);
+    let cwd_path = proc_dir.join("cwd");
     if let Ok(cwd_link) = std::fs::read_link(&cwd_path) {
         let cwd_str = cwd_link.to_string_lossy();
 
         // Essayer d'extraire le nom du jeu Steam depuis le cwd
         if let Some(game_name) = extract_steam_game_name(&cwd_str) {
-            let exe_path_str = format!("/proc/{}/exe", pid);
-            if let Ok(exe_link) = std::fs::read_link(&exe_path_str) {
+            let exe_path = proc_dir.join("exe");
+            if let Ok(exe_link) = std::fs::read_link(&exe_path) {
                 if let Some(exe_name) = exe_link.file_name() {
                     let exe_name_str = exe_name.to_string_lossy();
                     if exe_name_str.contains("wine") || exe_name_str.contains("proton") {
@@ -132,8 +211,8 @@ pub fn read_process_name(pid: u32) -> Result<String, IoError> {
         }
 
         // Sinon utiliser juste le dernier dossier du cwd
-        let exe_path_str = format!("/proc/{}/exe", pid);
-        if let Ok(exe_link) = std::fs::read_link(&exe_path_str) {
+        let exe_path = proc_dir.join("exe");
+        if let Ok(exe_link) = std::fs::read_link(&exe_path) {
             let exe_name = exe_link
                 .file_name()
                 .and_then(|n| n.to_str())
@@ -148,7 +227,7 @@ pub fn read_process_name(pid: u32) -> Result<String, IoError> {
     }
 
     // Fallback: chemin complet de l'exécutable
-    let exe_path = format!("/proc/{}/exe", pid);
+    let exe_path = proc_dir.join("exe");
     if let Ok(exe_link) = std::fs::read_link(&exe_path) {
         let path_str = exe_link.to_string_lossy().to_string();
         let clean_path = path_str.split(" (").next().unwrap_or(&path_str).to_string();
@@ -158,63 +237,283 @@ pub fn read_process_name(pid: u32) -> Result<String, IoError> {
     }
 
     // Dernier fallback: /proc/{pid}/comm
-    let comm_path = format!("/proc/{}/comm", pid);
+    let comm_path = proc_dir.join("comm");
     let name = std::fs::read_to_string(&comm_path)?.trim().to_string();
     Ok(name)
 }
 
 /// Collecte les statistiques GPU pour tous les processus
-pub fn collect_gpu_processes() -> Vec<GpuProcess> {
+///
+/// Un client DRM (`drm-pdev` + `drm-client-id`) n'est compté qu'une fois,
+/// même s'il est ouvert sur plusieurs fd ou partagé par un `fork`: il revient
+/// au processus de plus petit PID qui le tient, en général le parent.
+///
+/// Les processus sans activité GPU sont écartés, sauf ceux que `rules`
+/// autorise: ils restent suivis même sans compteurs d'activité.
+pub fn collect_gpu_processes(paths: &SystemPaths, rules: &ProcessRules) -> Vec<GpuProcess> {
     let mut processes = Vec::new();
 
-    let Ok(proc_entries) = std::fs::read_dir("/proc") else {
+    let Ok(proc_entries) = std::fs::read_dir(&paths.proc) else {
         return processes;
     };
+    let mut pids: Vec<u32> = proc_entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            if !name.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            name.parse().ok()
+        })
+        .collect();
+    pids.sort_unstable();
 
-    for entry in proc_entries.flatten() {
-        let path = entry.path();
-        let file_name = entry.file_name();
-        let file_name_str = file_name.to_string_lossy();
-
-        if !path.is_dir() || !file_name_str.chars().all(|c| c.is_ascii_digit()) {
-            continue;
-        }
-
-        let Ok(pid) = file_name_str.parse::<u32>() else {
-            continue;
-        };
-
+    let mut clients = HashSet::new();
+    for pid in pids {
+        let path = paths.process(pid);
         let fd_dir = path.join("fd");
         let Ok(fd_entries) = std::fs::read_dir(&fd_dir) else {
             continue;
         };
 
-        let mut total_cycles = 0u64;
-        let mut has_drm = false;
+        let mut engines: BTreeMap<String, u64> = BTreeMap::new();
+        let mut uses_drm = false;
 
         for fd_entry in fd_entries.flatten() {
             let fd_path = fd_entry.path();
 
-            if !is_drm_device(&fd_path) {
+            if !is_drm_device(paths, &fd_path) {
                 continue;
             }
+            // Les noyaux sans `drm-driver` (5.15) ne donnent pas d'activité,
+            // mais le processus reste un client du GPU
+            uses_drm = true;
 
-            has_drm = true;
             let fd_num = fd_entry.file_name().to_string_lossy().to_string();
-            let fdinfo_path = format!("/proc/{}/fdinfo/{}", pid, fd_num);
-            total_cycles += parse_fdinfo_cycles(&fdinfo_path);
+            let fdinfo_path = path.join("fdinfo").join(fd_num);
+            let Some(info) = FdInfo::read(&fdinfo_path) else {
+                continue;
+            };
+            // Sans drm-client-id (noyaux anciens), chaque fd est compté
+            if let Some((pdev, client_id)) = info.client()
+                && !clients.insert((pdev.map(str::to_string), client_id))
+            {
+                continue;
+            }
+            for (engine, ns) in info.engines {
+                *engines.entry(engine).or_default() += ns;
+            }
         }
 
-        if has_drm && total_cycles > 0 {
-            if let Ok(name) = read_process_name(pid) {
-                processes.push(GpuProcess {
-                    _pid: pid,
-                    name,
-                    total_cycles,
-                });
-            }
+        if !uses_drm {
+            continue;
+        }
+        let Ok(name) = read_process_name(paths, pid) else {
+            continue;
+        };
+        let cmdline = read_cmdline(paths, pid);
+        let process = GpuProcess {
+            pid,
+            name,
+            comm: std::fs::read_to_string(path.join("comm"))
+                .map(|comm| comm.trim().to_string())
+                .unwrap_or_default(),
+            exe: std::fs::read_link(path.join("exe")).ok().map(|exe| {
+                let exe = exe.to_string_lossy();
+                exe.strip_suffix(" (deleted)").unwrap_or(&exe).to_string()
+            }),
+            game: read_steam_game_name(paths, pid, &cmdline),
+            steam_app_id: read_steam_app_id(paths, pid),
+            cmdline,
+            engines,
+        };
+        if process.engines.values().any(|&ns| ns > 0) || rules.allows(&process) {
+            processes.push(process);
         }
     }
 
     processes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::{FakeSystem, fdinfo_sample};
+    use crate::process_rules::{Field, Pattern, ProcessRule};
+
+    fn collect(system: &FakeSystem) -> Vec<GpuProcess> {
+        collect_with(system, &ProcessRules::default())
+    }
+
+    fn collect_with(system: &FakeSystem, rules: &ProcessRules) -> Vec<GpuProcess> {
+        let mut processes = collect_gpu_processes(system.paths(), rules);
+        processes.sort_by_key(|p| p.pid);
+        processes
+    }
+
+    #[test]
+    fn test_duplicated_fds_are_counted_once() {
+        let system = FakeSystem::new("dup");
+        let game = fdinfo_sample("amdgpu_game");
+        system
+            .process(100, "game")
+            .file_fd()
+            .drm_fd(&game)
+            .drm_fd(&game)
+            .fd(system.paths().dri().join("card0"), &game);
+        let processes = collect(&system);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].name, "game");
+        assert_eq!(processes[0].engines["gfx"], 23_519_446_431);
+    }
+
+    #[test]
+    fn test_distinct_clients_are_summed() {
+        let system = FakeSystem::new("clients");
+        let video = fdinfo_sample("amdgpu_video_decode");
+        system
+            .process(200, "player")
+            .drm_fd(&fdinfo_sample("amdgpu_game"))
+            .drm_fd(&video);
+        // Même client-id hérité par un autre processus (fork): compté une
+        // seule fois, pour le parent
+        system.process(201, "child").drm_fd(&video);
+        let processes = collect(&system);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].name, "player");
+        assert_eq!(processes[0].engines["gfx"], 23_519_446_431 + 1_250_000);
+        assert_eq!(processes[0].engines["dec"], 98_000_000_000);
+    }
+
+    #[test]
+    fn test_processes_without_usage_are_skipped() {
+        let system = FakeSystem::new("idle");
+        system
+            .process(300, "old-kernel")
+            .drm_fd(&fdinfo_sample("amdgpu_5_15"));
+        system.process(301, "no-gpu").file_fd();
+        // Un fd DRM sans drm-client-id est compté à chaque fois
+        let anonymous = "drm-driver:\tamdgpu\ndrm-engine-gfx:\t500 ns\n";
+        system
+            .process(302, "anonymous")
+            .drm_fd(anonymous)
+            .drm_fd(anonymous);
+        system
+            .process(303, "idle")
+            .drm_fd("drm-driver:\tamdgpu\ndrm-client-id:\t9\ndrm-engine-gfx:\t0 ns\n");
+        let processes = collect(&system);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].name, "anonymous");
+        assert_eq!(processes[0].engines["gfx"], 1000);
+
+        // Un processus autorisé est gardé sans activité (5.15 compris), pas
+        // sans fd DRM
+        let rules = ProcessRules {
+            allow: ["old-kernel", "idle", "no-gpu"]
+                .map(|name| ProcessRule::new(Field::Name, Pattern::Glob(name.into())).unwrap())
+                .into(),
+            ..Default::default()
+        };
+        let processes = collect_with(&system, &rules);
+        let names: Vec<_> = processes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["old-kernel", "anonymous", "idle"]);
+    }
+
+    #[test]
+    fn test_host_dri_links_from_container() {
+        // Processus de l'hôte vu depuis un conteneur (`/host/proc`): ses liens
+        // pointent vers le `/dev/dri` de l'hôte, pas vers `<dev>/dri`
+        let system = FakeSystem::new("container");
+        system
+            .process(400, "host")
+            .fd("/dev/dri/renderD128", &fdinfo_sample("amdgpu_game"));
+        system
+            .process(401, "notes")
+            .fd("/home/deck/dri/renderD128", &fdinfo_sample("amdgpu_game"));
+        // Nœud absent du `/dev` monté pour le gouverneur
+        system
+            .process(402, "other-gpu")
+            .fd("/dev/dri/renderD129", &fdinfo_sample("amdgpu_game"));
+        let processes = collect(&system);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].name, "host");
+    }
+
+    #[test]
+    fn test_process_names() {
+        let system = FakeSystem::new("names");
+        system.process(500, "wine64-preload").cmdline(&[
+            "/usr/bin/wine64-preloader",
+            "/home/deck/.steam/steamapps/common/Hades/x64/Hades.exe",
+            "-vulkan",
+        ]);
+        system
+            .process(501, "GameThread")
+            .cwd("/home/deck/.steam/steamapps/common/Celeste")
+            .exe("/usr/bin/wine64-preloader");
+        system
+            .process(502, "vkcube")
+            .exe("/usr/bin/vkcube (deleted)");
+        system.process(503, "kworker");
+        let name = |pid| read_process_name(system.paths(), pid).unwrap();
+        assert_eq!(name(500), "Hades/Hades");
+        assert_eq!(name(501), "Celeste/wine64-preloader");
+        assert_eq!(name(502), "/usr/bin/vkcube");
+        assert_eq!(name(503), "kworker");
+        assert!(read_process_name(system.paths(), 504).is_err());
+    }
+
+    #[test]
+    fn test_identity() {
+        let system = FakeSystem::new("identity");
+        system
+            .process(600, "GameThread")
+            .cmdline(&["/usr/bin/wine64-preloader", "launcher.exe", "-dx11"])
+            .cwd("/home/deck/.steam/steamapps/common/Celeste")
+            .exe("/usr/bin/wine64-preloader (deleted)")
+            .drm_fd(&fdinfo_sample("amdgpu_game"));
+        let process = &collect(&system)[0];
+        assert_eq!(process.comm, "GameThread");
+        assert_eq!(process.exe.as_deref(), Some("/usr/bin/wine64-preloader"));
+        assert_eq!(process.cmdline.len(), 3);
+        assert_eq!(process.game.as_deref(), Some("Celeste"));
+    }
+
+    #[test]
+    fn test_steam_app_id() {
+        let system = FakeSystem::new("appid");
+        let paths = system.paths();
+        system.process(700, "reaper").parent(1).cmdline(&[
+            "reaper",
+            "SteamLaunch",
+            "AppId=1145360",
+            "--",
+            "proton",
+        ]);
+        system
+            .process(701, "wine64")
+            .parent(700)
+            .environ(&[("HOME", "/home/deck")]);
+        system
+            .process(702, "Hades.exe")
+            .parent(701)
+            .drm_fd(&fdinfo_sample("amdgpu_game"));
+        system
+            .process(710, "shortcut")
+            .parent(1)
+            .environ(&[("SteamAppId", "0"), ("SteamGameId", "12394813248719323136")]);
+        system
+            .process(711, "retroarch")
+            .environ(&[("SteamAppId", "1118310")]);
+        system.process(712, "vkcube").parent(1);
+
+        assert_eq!(read_steam_app_id(paths, 700), Some(1145360));
+        assert_eq!(read_steam_app_id(paths, 702), Some(1145360));
+        assert_eq!(read_steam_app_id(paths, 710), Some(12394813248719323136));
+        assert_eq!(read_steam_app_id(paths, 711), Some(1118310));
+        assert_eq!(read_steam_app_id(paths, 712), None);
+        assert_eq!(collect(&system)[0].profile_key(), "steam:1145360");
+    }
+}
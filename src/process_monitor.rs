@@ -1,30 +1,87 @@
-use crate::constants::{MIN_GPU_USAGE_PERCENT, PROCESS_STABILITY_SECS, PROCESS_SWITCH_RATIO, PROCESS_UPDATE_INTERVAL_SECS};
-use crate::process_detection::{collect_gpu_processes, is_excluded_process};
-use std::collections::HashMap;
+use crate::constants::{
+    MIN_GPU_USAGE_PERCENT, PROCESS_STABILITY_SECS, PROCESS_SWITCH_RATIO,
+    PROCESS_UPDATE_INTERVAL_SECS,
+};
+use crate::gpu_info::RENDER_ENGINES;
+use crate::process_detection::{GpuProcess, collect_gpu_processes};
+use crate::process_rules::ProcessRules;
+use crate::system_paths::SystemPaths;
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, Instant};
 
+/// Utilisation GPU d'un processus sur le dernier intervalle
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessUsage {
+    pub name: String,
+    /// Pourcentage d'occupation par moteur (gfx, compute, dec, enc …)
+    pub engines: BTreeMap<String, f64>,
+}
+
+impl ProcessUsage {
+    /// Utilisation des moteurs de rendu, la seule qui compte pour choisir le jeu
+    ///
+    /// Un lecteur vidéo qui n'occupe que `dec` reste à 0%.
+    pub fn render_percent(&self) -> f64 {
+        RENDER_ENGINES
+            .iter()
+            .filter_map(|engine| self.engines.get(*engine))
+            .fold(0.0, |max, &usage| f64::max(max, usage))
+    }
+}
+
 /// Moniteur de processus GPU
 pub struct ProcessMonitor {
+    paths: SystemPaths,
+    rules: ProcessRules,
+    /// Clé du profil (`GpuProcess::profile_key`) du processus suivi
     current_process: Option<String>,
+    current_name: Option<String>,
     process_start: Option<Instant>,
-    last_cycles: HashMap<String, u64>,
+    /// Compteurs du relevé précédent, par PID: deux processus de même nom
+    /// ont chacun les leurs
+    last_engines: HashMap<u32, BTreeMap<String, u64>>,
     last_update: Instant,
+    usages: Vec<ProcessUsage>,
     pub debug_mode: bool,
-    pub current_process_usage_percent: f64, // Pourcentage GPU actuel du processus en cours
+    pub current_process_usage_percent: f64, // Pourcentage GPU (rendu) actuel du processus en cours
 }
 
 impl ProcessMonitor {
     pub fn new() -> Self {
+        Self::with_paths(SystemPaths::default())
+    }
+
+    /// Moniteur lisant les processus sous d'autres racines (conteneur, tests)
+    pub fn with_paths(paths: SystemPaths) -> Self {
         Self {
+            paths,
+            rules: ProcessRules::default(),
             current_process: None,
+            current_name: None,
             process_start: None,
-            last_cycles: HashMap::new(),
+            last_engines: HashMap::new(),
             last_update: Instant::now(),
+            usages: Vec::new(),
             debug_mode: false,
             current_process_usage_percent: 0.0,
         }
     }
 
+    /// Remplace les règles d'exclusion et d'autorisation (`EXCLUDED_PROCESSES` par défaut)
+    pub fn set_rules(&mut self, rules: ProcessRules) {
+        self.rules = rules;
+    }
+
+    /// Nom lisible du processus suivi, dont `update` renvoie la clé
+    pub fn current_name(&self) -> Option<&str> {
+        self.current_name.as_deref()
+    }
+
+    /// Utilisation par moteur de chaque processus, lors de la dernière mise à jour
+    pub fn usages(&self) -> &[ProcessUsage] {
+        &self.usages
+    }
+
     pub fn update(&mut self) -> Option<String> {
         let elapsed_since_last = self.last_update.elapsed();
 
@@ -33,41 +90,77 @@ impl ProcessMonitor {
             return self.current_process.clone();
         }
 
-        let processes = collect_gpu_processes();
+        let processes = collect_gpu_processes(&self.paths, &self.rules);
         self.last_update = Instant::now();
+        self.select(&processes, elapsed_since_last)
+    }
+
+    /// Calcule l'utilisation par moteur depuis le relevé précédent
+    fn measure(&mut self, processes: &[GpuProcess], elapsed: Duration) -> Vec<ProcessUsage> {
+        let elapsed_ns = elapsed.as_nanos() as f64;
+        let mut usages = Vec::with_capacity(processes.len());
+        let mut last_engines = HashMap::with_capacity(processes.len());
+
+        for proc in processes {
+            let last = self.last_engines.get(&proc.pid);
+            let engines = proc
+                .engines
+                .iter()
+                .map(|(engine, &ns)| {
+                    let previous = last.and_then(|l| l.get(engine)).copied().unwrap_or(0);
+                    let delta = ns.saturating_sub(previous);
+                    let usage_percent = if elapsed_ns > 0.0 {
+                        (delta as f64 / elapsed_ns) * 100.0
+                    } else {
+                        0.0
+                    };
+                    (engine.clone(), usage_percent)
+                })
+                .collect();
 
+            usages.push(ProcessUsage {
+                name: proc.name.clone(),
+                engines,
+            });
+            last_engines.insert(proc.pid, proc.engines.clone());
+        }
+        // Les processus terminés sont oubliés: leur PID peut être réutilisé
+        self.last_engines = last_engines;
+
+        usages
+    }
+
+    /// Choisit le processus dominant parmi `processes`, relevés `elapsed` après le précédent
+    ///
+    /// Renvoie la clé de son profil: les processus d'un même jeu Steam la partagent.
+    fn select(&mut self, processes: &[GpuProcess], elapsed: Duration) -> Option<String> {
         if processes.is_empty() {
+            self.usages.clear();
             self.current_process = None;
+            self.current_name = None;
             self.process_start = None;
             return None;
         }
 
-        // Calculer le delta de cycles pour chaque process
-        let mut deltas: Vec<(String, u64, f64)> = Vec::new();
-
-        for proc in &processes {
-            let last = self.last_cycles.get(&proc.name).copied().unwrap_or(0);
-            let delta = proc.total_cycles.saturating_sub(last);
+        self.usages = self.measure(processes, elapsed);
 
-            // Calculer le pourcentage d'utilisation GPU
-            let elapsed_ns = elapsed_since_last.as_nanos() as f64;
-            let usage_percent = if elapsed_ns > 0.0 {
-                (delta as f64 / elapsed_ns) * 100.0
-            } else {
-                0.0
-            };
-
-            deltas.push((proc.name.clone(), delta, usage_percent));
-            self.last_cycles
-                .insert(proc.name.clone(), proc.total_cycles);
-        }
+        // Seuls les moteurs de rendu comptent: le décodage vidéo ne fait pas un jeu
+        let deltas: Vec<(String, f64)> = self
+            .usages
+            .iter()
+            .zip(processes)
+            .map(|(usage, proc)| (proc.profile_key(), usage.render_percent()))
+            .collect();
 
-        // Filtrer les processus avec utilisation GPU significative ET non exclus
+        // Filtrer les processus autorisés, ou avec utilisation GPU significative ET non exclus
         let active_processes: Vec<_> = deltas
             .iter()
-            .filter(|(name, _, usage_percent)| {
-                *usage_percent >= MIN_GPU_USAGE_PERCENT && !is_excluded_process(name)
+            .zip(processes)
+            .filter(|((_, usage_percent), proc)| {
+                self.rules.allows(proc)
+                    || (*usage_percent >= MIN_GPU_USAGE_PERCENT && !self.rules.excludes(proc))
             })
+            .map(|(delta, _)| delta)
             .collect();
 
         // Si aucun processus actif, rester sur MIN_FREQ
@@ -78,6 +171,7 @@ impl ProcessMonitor {
                     MIN_GPU_USAGE_PERCENT
                 );
                 self.current_process = None;
+                self.current_name = None;
                 self.process_start = None;
                 self.current_process_usage_percent = 0.0;
             }
@@ -85,9 +179,9 @@ impl ProcessMonitor {
         }
 
         // Trouver le process avec l'utilisation GPU la plus élevée parmi les actifs
-        if let Some((dominant_process, _, dominant_usage)) = active_processes
+        if let Some((dominant_process, dominant_usage)) = active_processes
             .iter()
-            .max_by(|(_, _, usage_a), (_, _, usage_b)| usage_a.partial_cmp(usage_b).unwrap())
+            .max_by(|(_, usage_a), (_, usage_b)| usage_a.partial_cmp(usage_b).unwrap())
         {
             // Vérifier si on doit changer de processus
             let should_change = if let Some(current) = &self.current_process {
@@ -96,8 +190,8 @@ impl ProcessMonitor {
                     // Si le nouveau process est significativement plus gourmand, changer
                     let current_usage = deltas
                         .iter()
-                        .find(|(name, _, _)| name == current)
-                        .map(|(_, _, usage)| *usage)
+                        .find(|(name, _)| name == current)
+                        .map(|(_, usage)| *usage)
                         .unwrap_or(0.0);
 
                     if self.debug_mode {
@@ -128,6 +222,10 @@ impl ProcessMonitor {
 
             if should_change {
                 self.current_process = Some((*dominant_process).clone());
+                self.current_name = processes
+                    .iter()
+                    .find(|proc| proc.profile_key() == **dominant_process)
+                    .map(|proc| proc.name.clone());
                 self.process_start = Some(Instant::now());
                 self.current_process_usage_percent = *dominant_usage;
                 return Some((*dominant_process).clone());
@@ -152,3 +250,93 @@ impl Default for ProcessMonitor {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, engines: &[(&str, u64)]) -> GpuProcess {
+        GpuProcess {
+            pid,
+            name: name.to_string(),
+            engines: engines.iter().map(|(e, ns)| (e.to_string(), *ns)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_video_decode_is_not_a_game() {
+        let mut monitor = ProcessMonitor::new();
+        let second = Duration::from_secs(1);
+        monitor.select(&[process(1, "mpv", &[("gfx", 0), ("dec", 0)])], second);
+
+        // 1 s plus tard: mpv a occupé `dec` 900 ms et `gfx` 1 ms
+        let selected = monitor.select(
+            &[process(
+                1,
+                "mpv",
+                &[("gfx", 1_000_000), ("dec", 900_000_000)],
+            )],
+            second,
+        );
+        assert_eq!(selected, None);
+        let usage = &monitor.usages()[0];
+        assert_eq!(usage.engines["dec"], 90.0);
+        assert_eq!(usage.render_percent(), 0.1);
+
+        // Un jeu qui occupe `gfx` à 60% est retenu malgré la vidéo à 90%
+        let selected = monitor.select(
+            &[
+                process(1, "mpv", &[("gfx", 2_000_000), ("dec", 1_800_000_000)]),
+                process(2, "game", &[("gfx", 600_000_000), ("compute", 10_000_000)]),
+            ],
+            second,
+        );
+        assert_eq!(selected.as_deref(), Some("game"));
+        assert_eq!(monitor.current_process_usage_percent, 60.0);
+    }
+
+    #[test]
+    fn test_rules() {
+        use crate::process_rules::{Field, Pattern, ProcessRule};
+
+        let glob = |field, glob: &str| ProcessRule::new(field, Pattern::Glob(glob.into())).unwrap();
+        let mut monitor = ProcessMonitor::new();
+        monitor.set_rules(ProcessRules {
+            exclude: vec![glob(Field::Name, "bench*")],
+            allow: vec![glob(Field::Name, "steam")],
+        });
+        let second = Duration::from_secs(1);
+        let snapshot = |steam_ns, bench_ns| {
+            [
+                process(1, "steam", &[("gfx", steam_ns)]),
+                process(2, "benchmark", &[("gfx", bench_ns)]),
+            ]
+        };
+        monitor.select(&snapshot(0, 0), second);
+
+        // `steam` est autorisé malgré la liste intégrée et 1% seulement,
+        // `benchmark` exclu malgré 80%
+        let selected = monitor.select(&snapshot(10_000_000, 800_000_000), second);
+        assert_eq!(selected.as_deref(), Some("steam"));
+        assert_eq!(monitor.current_process_usage_percent, 1.0);
+    }
+
+    #[test]
+    fn test_same_name_processes_measured_apart() {
+        let mut monitor = ProcessMonitor::new();
+        let second = Duration::from_secs(1);
+        let snapshot = |a_ns, b_ns| {
+            [
+                process(10, "wine64-preloader", &[("gfx", a_ns)]),
+                process(11, "wine64-preloader", &[("gfx", b_ns)]),
+            ]
+        };
+        monitor.select(&snapshot(50_000_000_000, 1_000_000), second);
+
+        // Chacun son intervalle: 40% et 1%, pas 0% et >100% croisés
+        monitor.select(&snapshot(50_400_000_000, 11_000_000), second);
+        let usages: Vec<_> = monitor.usages().iter().map(|u| u.engines["gfx"]).collect();
+        assert_eq!(usages, [40.0, 1.0]);
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::process_detection::{EXCLUDED_PROCESSES, GpuProcess};
+
+/// Attribut d'un processus comparé par une règle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Field {
+    /// Nom retenu par `read_process_name` (celui des profils)
+    #[default]
+    Name,
+    /// `/proc/<pid>/comm`, tronqué à 15 caractères par le noyau
+    Comm,
+    /// Cible de `/proc/<pid>/exe`
+    Exe,
+    /// Arguments séparés par des espaces
+    Cmdline,
+    /// Nom du jeu Steam (dossier sous `steamapps/common`)
+    Game,
+}
+
+impl Field {
+    pub const ALL: [Self; 5] = [Self::Name, Self::Comm, Self::Exe, Self::Cmdline, Self::Game];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Comm => "comm",
+            Self::Exe => "exe",
+            Self::Cmdline => "cmdline",
+            Self::Game => "game",
+        }
+    }
+}
+
+/// Motif d'une règle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pattern {
+    /// `*`, `?` et `[...]`, sur toute la valeur; sans `/`, sur le dernier
+    /// composant de `name` et `exe`
+    Glob(String),
+    /// Recherché n'importe où dans la valeur (ancrer avec `^...$`)
+    Regex(String),
+}
+
+/// Règle de la section `[processes]`: un motif appliqué à un attribut
+#[derive(Debug, Clone, Serialize)]
+#[serde(into = "RuleSpec")]
+pub struct ProcessRule {
+    field: Field,
+    pattern: Pattern,
+    regex: Regex,
+}
+
+#[derive(Serialize)]
+struct RuleSpec {
+    #[serde(rename = "match")]
+    field: Field,
+    #[serde(flatten)]
+    pattern: Pattern,
+}
+
+impl From<ProcessRule> for RuleSpec {
+    fn from(rule: ProcessRule) -> Self {
+        Self {
+            field: rule.field,
+            pattern: rule.pattern,
+        }
+    }
+}
+
+impl PartialEq for ProcessRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field && self.pattern == other.pattern
+    }
+}
+
+impl fmt::Display for ProcessRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.pattern {
+            Pattern::Glob(glob) => write!(f, "{} glob \"{glob}\"", self.field.name()),
+            Pattern::Regex(regex) => write!(f, "{} regex \"{regex}\"", self.field.name()),
+        }
+    }
+}
+
+impl ProcessRule {
+    pub fn new(field: Field, pattern: Pattern) -> Result<Self, regex::Error> {
+        let regex = match &pattern {
+            Pattern::Glob(glob) => Regex::new(&glob_to_regex(glob))?,
+            Pattern::Regex(regex) => Regex::new(regex)?,
+        };
+        Ok(Self {
+            field,
+            pattern,
+            regex,
+        })
+    }
+
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn matches(&self, process: &GpuProcess) -> bool {
+        let value = match self.field {
+            Field::Name => process.name.clone(),
+            Field::Comm => process.comm.clone(),
+            Field::Exe => match &process.exe {
+                Some(exe) => exe.clone(),
+                None => return false,
+            },
+            Field::Cmdline => process.cmdline.join(" "),
+            Field::Game => match &process.game {
+                Some(game) => game.clone(),
+                None => return false,
+            },
+        };
+        let basename_only = matches!(self.field, Field::Name | Field::Exe)
+            && matches!(&self.pattern, Pattern::Glob(glob) if !glob.contains('/'));
+        if basename_only {
+            let basename = Path::new(&value)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(&value);
+            return self.regex.is_match(basename);
+        }
+        self.regex.is_match(&value)
+    }
+}
+
+/// Traduit un glob en expression régulière ancrée
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                // Classe recopiée telle quelle jusqu'au `]`, `!` en tête pour la négation
+                let mut class = String::new();
+                if chars.next_if_eq(&'!').is_some() {
+                    class.push('^');
+                }
+                if let Some(']') = chars.peek() {
+                    class.push_str("\\]");
+                    chars.next();
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if closed {
+                    regex.push('[');
+                    regex.push_str(&class);
+                    regex.push(']');
+                } else {
+                    // `[` sans `]`: caractère littéral
+                    regex.push_str("\\[");
+                    regex.push_str(&regex::escape(&class));
+                }
+            }
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// Processus ignorés et processus toujours suivis (section `[processes]`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessRules {
+    /// Par défaut `EXCLUDED_PROCESSES`, remplacé par celui de la config
+    pub exclude: Vec<ProcessRule>,
+    /// Suivis même exclus et même sous `MIN_GPU_USAGE_PERCENT`
+    pub allow: Vec<ProcessRule>,
+}
+
+impl Default for ProcessRules {
+    fn default() -> Self {
+        Self {
+            exclude: builtin_exclusions(),
+            allow: Vec::new(),
+        }
+    }
+}
+
+impl ProcessRules {
+    pub fn excludes(&self, process: &GpuProcess) -> bool {
+        self.exclude.iter().any(|rule| rule.matches(process))
+    }
+
+    pub fn allows(&self, process: &GpuProcess) -> bool {
+        self.allow.iter().any(|rule| rule.matches(process))
+    }
+}
+
+/// `EXCLUDED_PROCESSES`, sur le dernier composant du nom
+pub fn builtin_exclusions() -> Vec<ProcessRule> {
+    EXCLUDED_PROCESSES
+        .iter()
+        .map(|name| ProcessRule::new(Field::Name, Pattern::Glob(name.to_string())).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process() -> GpuProcess {
+        GpuProcess {
+            name: "Hades/Hades".to_string(),
+            comm: "Hades.exe".to_string(),
+            exe: Some("/usr/bin/wine64-preloader".to_string()),
+            cmdline: vec![
+                "/usr/bin/wine64-preloader".to_string(),
+                "Z:\\steamapps\\common\\Hades\\x64\\Hades.exe".to_string(),
+            ],
+            game: Some("Hades".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn rule(field: Field, pattern: Pattern) -> ProcessRule {
+        ProcessRule::new(field, pattern).unwrap()
+    }
+
+    #[test]
+    fn test_glob() {
+        let glob = |field, glob: &str| rule(field, Pattern::Glob(glob.to_string()));
+        let process = process();
+        assert!(glob(Field::Name, "Hades").matches(&process));
+        assert!(glob(Field::Name, "Hades/*").matches(&process));
+        assert!(!glob(Field::Name, "Hade").matches(&process));
+        assert!(glob(Field::Exe, "wine*").matches(&process));
+        assert!(!glob(Field::Exe, "/usr/*/wine").matches(&process));
+        assert!(glob(Field::Comm, "[Hh]ades.???").matches(&process));
+        assert!(!glob(Field::Comm, "[!H]ades.exe").matches(&process));
+        assert!(glob(Field::Cmdline, "*Hades.exe").matches(&process));
+        assert!(glob(Field::Game, "H*").matches(&process));
+        assert!(glob(Field::Name, "a[b").matches(&GpuProcess {
+            name: "a[b".to_string(),
+            ..Default::default()
+        }));
+        assert!(!glob(Field::Game, "*").matches(&GpuProcess::default()));
+    }
+
+    #[test]
+    fn test_regex() {
+        let regex = |field, regex: &str| rule(field, Pattern::Regex(regex.to_string()));
+        let process = process();
+        assert!(regex(Field::Cmdline, r"common\\Hades\\").matches(&process));
+        assert!(regex(Field::Name, "^Hades/").matches(&process));
+        assert!(!regex(Field::Game, "^Had$").matches(&process));
+        assert!(ProcessRule::new(Field::Name, Pattern::Regex("(".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_builtin_exclusions() {
+        let rules = ProcessRules::default();
+        let named = |name: &str| GpuProcess {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        assert!(rules.excludes(&named("/usr/bin/kwin_wayland")));
+        assert!(rules.excludes(&named("steam")));
+        assert!(!rules.excludes(&named("/usr/bin/steam-runtime")));
+        assert!(!rules.excludes(&named("Hades/Hades")));
+        assert!(!rules.allows(&named("steam")));
+    }
+}
@@ -0,0 +1,476 @@
+//! Paquets de profils appris, partagés entre machines identiques
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::profile_db::{DeviceIdentity, ProcessDatabase, ProcessProfile, unix_now};
+
+/// Version du format des paquets
+pub const BUNDLE_VERSION: u64 = 1;
+
+/// Format d'un paquet sur disque
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleFormat {
+    Json,
+    Toml,
+}
+
+impl BundleFormat {
+    pub const ALL: [Self; 2] = [Self::Json, Self::Toml];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// TOML pour l'extension `.toml`, JSON sinon
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Choix entre un profil local et celui d'un paquet rangés sous la même clé
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Le profil local est gardé
+    #[default]
+    KeepLocal,
+    /// Le profil appris sur le plus d'échantillons l'emporte, le local à égalité
+    MoreSamples,
+    /// Le profil du paquet remplace le local
+    PreferRemote,
+}
+
+impl MergeStrategy {
+    pub const ALL: [Self; 3] = [Self::KeepLocal, Self::MoreSamples, Self::PreferRemote];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::KeepLocal => "keep-local",
+            Self::MoreSamples => "more-samples",
+            Self::PreferRemote => "prefer-remote",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    fn replaces(self, local: &ProcessProfile, remote: &ProcessProfile) -> bool {
+        match self {
+            Self::KeepLocal => false,
+            Self::MoreSamples => remote.samples_count > local.samples_count,
+            Self::PreferRemote => true,
+        }
+    }
+}
+
+/// Échec d'un export ou d'un import
+#[derive(Debug)]
+pub enum BundleError {
+    Io(io::Error),
+    /// Paquet mal formé
+    Parse(String),
+    /// Paquet écrit par une version plus récente
+    UnsupportedVersion(u64),
+    /// Aucun profil ne porte cette clé ou ce nom
+    UnknownProfile(String),
+    /// Profils appris avec d'autres points sûrs ou une autre interpolation
+    CurveMismatch {
+        bundle: String,
+        local: String,
+    },
+    /// Profil appris avant que la courbe de tension soit enregistrée
+    UnknownCurve(String),
+    /// Profils sélectionnés appris avec des courbes différentes
+    MixedCurves(String, String),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Parse(e) => write!(f, "invalid bundle: {e}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "bundle version {version} is newer than the supported one ({BUNDLE_VERSION})"
+            ),
+            Self::UnknownProfile(selector) => write!(f, "no profile named \"{selector}\""),
+            Self::CurveMismatch { bundle, local } => write!(
+                f,
+                "profiles learned with another voltage curve (bundle {bundle}, local {local}): \
+                the safe-points and voltage.interpolation must match"
+            ),
+            Self::UnknownCurve(key) => write!(
+                f,
+                "profile \"{key}\" has no recorded voltage curve: learn it again before exporting"
+            ),
+            Self::MixedCurves(first, other) => write!(
+                f,
+                "the selected profiles were learned with different voltage curves \
+                ({first}, {other}): export them separately"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<io::Error> for BundleError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Clés des profils importés, selon ce qu'il en est advenu
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    /// Profils locaux gardés à la place de ceux du paquet
+    pub kept: Vec<String>,
+}
+
+/// Profils exportés, avec le GPU et la courbe de tension qui les ont appris
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileBundle {
+    pub version: u64,
+    /// Secondes depuis l'époque Unix
+    pub created: u64,
+    /// `VoltageCurve::fingerprint` sous laquelle les profils ont été appris
+    pub voltage_curve: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceIdentity>,
+    pub profiles: Vec<ProcessProfile>,
+}
+
+impl ProfileBundle {
+    /// Profils de `db` désignés par leur clé ou leur nom, tous si `selectors` est vide
+    ///
+    /// Ils doivent tous avoir été appris sous la même courbe de tension,
+    /// enregistrée avec eux: c'est l'empreinte du paquet.
+    pub fn export(db: &ProcessDatabase, selectors: &[String]) -> Result<Self, BundleError> {
+        let mut profiles: Vec<ProcessProfile> = if selectors.is_empty() {
+            db.profiles.values().cloned().collect()
+        } else {
+            let mut profiles = Vec::new();
+            for selector in selectors {
+                let before = profiles.len();
+                profiles.extend(
+                    db.profiles
+                        .iter()
+                        .filter(|(key, p)| *key == selector || p.name == *selector)
+                        .map(|(_, p)| p.clone()),
+                );
+                if profiles.len() == before {
+                    return Err(BundleError::UnknownProfile(selector.clone()));
+                }
+            }
+            profiles
+        };
+        profiles.sort_by_key(|p| p.key());
+        profiles.dedup_by_key(|p| p.key());
+
+        let mut voltage_curve: Option<&String> = None;
+        for profile in &profiles {
+            let curve = profile
+                .voltage_curve
+                .as_ref()
+                .ok_or_else(|| BundleError::UnknownCurve(profile.key()))?;
+            match voltage_curve {
+                Some(first) if first != curve => {
+                    return Err(BundleError::MixedCurves(first.clone(), curve.clone()));
+                }
+                _ => voltage_curve = Some(curve),
+            }
+        }
+        let voltage_curve = voltage_curve.cloned().unwrap_or_default();
+
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            created: unix_now(),
+            voltage_curve,
+            device: db.device().cloned(),
+            profiles,
+        })
+    }
+
+    pub fn parse(content: &str, format: BundleFormat) -> Result<Self, BundleError> {
+        let bundle: Self = match format {
+            BundleFormat::Json => {
+                serde_json::from_str(content).map_err(|e| BundleError::Parse(e.to_string()))?
+            }
+            BundleFormat::Toml => {
+                toml::from_str(content).map_err(|e| BundleError::Parse(e.to_string()))?
+            }
+        };
+        if bundle.version > BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(bundle.version));
+        }
+        Ok(bundle)
+    }
+
+    pub fn to_string(&self, format: BundleFormat) -> Result<String, BundleError> {
+        match format {
+            BundleFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| BundleError::Parse(e.to_string()))
+            }
+            BundleFormat::Toml => {
+                toml::to_string(self).map_err(|e| BundleError::Parse(e.to_string()))
+            }
+        }
+    }
+
+    /// Paquet de `path`, au format déduit de son extension
+    pub fn load(path: &Path) -> Result<Self, BundleError> {
+        Self::parse(
+            &std::fs::read_to_string(path)?,
+            BundleFormat::from_path(path),
+        )
+    }
+
+    /// Fusionne les profils dans `db` puis la sauvegarde
+    ///
+    /// Refusé si `voltage_curve` (empreinte de la config locale) diffère de
+    /// celle d'un profil du paquet: la fréquence apprise n'a de sens qu'avec
+    /// ses tensions.
+    pub fn import_into(
+        &self,
+        db: &mut ProcessDatabase,
+        voltage_curve: &str,
+        strategy: MergeStrategy,
+    ) -> Result<ImportSummary, BundleError> {
+        for profile in &self.profiles {
+            let curve = profile
+                .voltage_curve
+                .as_ref()
+                .unwrap_or(&self.voltage_curve);
+            if curve != voltage_curve {
+                return Err(BundleError::CurveMismatch {
+                    bundle: curve.clone(),
+                    local: voltage_curve.to_string(),
+                });
+            }
+        }
+
+        let mut summary = ImportSummary::default();
+        for profile in &self.profiles {
+            let key = profile.key();
+            let mut profile = profile.clone();
+            profile.voltage_curve = Some(voltage_curve.to_string());
+            match db.get(&key) {
+                None => summary.added.push(key),
+                Some(local) if strategy.replaces(local, &profile) => summary.replaced.push(key),
+                Some(_) => {
+                    summary.kept.push(key);
+                    continue;
+                }
+            }
+            db.insert(profile);
+        }
+        if !summary.added.is_empty() || !summary.replaced.is_empty() {
+            db.save()?;
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::TempDir;
+    use crate::profile_db::DATABASE_FILE;
+
+    const CURVE: &str = "0123456789abcdef";
+
+    fn profile(name: &str, app_id: Option<u64>, freq: u16, samples: usize) -> ProcessProfile {
+        let mut profile = ProcessProfile::new(name.to_string(), freq, 90.0, samples);
+        profile.steam_app_id = app_id;
+        profile.voltage_curve = Some(CURVE.to_string());
+        profile
+    }
+
+    fn database(dir: &TempDir, profiles: &[ProcessProfile]) -> ProcessDatabase {
+        let mut db = ProcessDatabase::open(dir.join(DATABASE_FILE));
+        for profile in profiles {
+            db.insert(profile.clone());
+        }
+        db.save().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_export_selection() {
+        let dir = TempDir::new("bundle-export");
+        let db = database(
+            &dir,
+            &[
+                profile("Hades/Hades", Some(1145360), 1400, 100),
+                profile("vkcube", None, 1000, 50),
+                profile("glxgears", None, 800, 10),
+            ],
+        );
+        let all = ProfileBundle::export(&db, &[]).unwrap();
+        assert_eq!(all.voltage_curve, CURVE);
+        let keys: Vec<_> = all.profiles.iter().map(|p| p.key()).collect();
+        assert_eq!(keys, ["glxgears", "steam:1145360", "vkcube"]);
+
+        // Par nom ou par clé, sans doublon
+        let selectors = ["Hades/Hades", "steam:1145360", "vkcube"].map(String::from);
+        let some = ProfileBundle::export(&db, &selectors).unwrap();
+        assert_eq!(some.profiles.len(), 2);
+        assert!(matches!(
+            ProfileBundle::export(&db, &["Celeste".to_string()]),
+            Err(BundleError::UnknownProfile(name)) if name == "Celeste"
+        ));
+    }
+
+    #[test]
+    fn test_export_uses_learned_curve() {
+        let dir = TempDir::new("bundle-export-curve");
+        let mut older = profile("glxgears", None, 800, 10);
+        older.voltage_curve = None;
+        let mut other = profile("Celeste", None, 900, 20);
+        other.voltage_curve = Some("fedcba9876543210".to_string());
+        let db = database(&dir, &[profile("vkcube", None, 1000, 50), older, other]);
+
+        let vkcube = ProfileBundle::export(&db, &["vkcube".to_string()]).unwrap();
+        assert_eq!(vkcube.voltage_curve, CURVE);
+        assert!(matches!(
+            ProfileBundle::export(&db, &["glxgears".to_string()]),
+            Err(BundleError::UnknownCurve(key)) if key == "glxgears"
+        ));
+        assert!(matches!(
+            ProfileBundle::export(&db, &["vkcube".to_string(), "Celeste".to_string()]),
+            Err(BundleError::MixedCurves(..))
+        ));
+    }
+
+    #[test]
+    fn test_formats_roundtrip() {
+        let bundle = ProfileBundle {
+            version: BUNDLE_VERSION,
+            created: 1_700_000_000,
+            voltage_curve: CURVE.to_string(),
+            device: Some(DeviceIdentity {
+                pci_address: "0000:01:00.0".to_string(),
+                pci_id: "1002:13fe".to_string(),
+            }),
+            profiles: vec![
+                profile("Hades/Hades", Some(1145360), 1400, 100),
+                profile("vkcube", None, 1000, 50),
+            ],
+        };
+        for format in BundleFormat::ALL {
+            let text = bundle.to_string(format).unwrap();
+            assert_eq!(ProfileBundle::parse(&text, format).unwrap(), bundle);
+        }
+        assert_eq!(
+            BundleFormat::from_path(Path::new("deck.toml")),
+            BundleFormat::Toml
+        );
+        assert_eq!(
+            BundleFormat::from_path(Path::new("deck.bundle")),
+            BundleFormat::Json
+        );
+
+        let newer = r#"{"version": 2, "created": 0, "voltage_curve": "", "profiles": []}"#;
+        assert!(matches!(
+            ProfileBundle::parse(newer, BundleFormat::Json),
+            Err(BundleError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_import_strategies() {
+        let bundle = ProfileBundle {
+            version: BUNDLE_VERSION,
+            created: 0,
+            voltage_curve: CURVE.to_string(),
+            device: None,
+            profiles: vec![
+                profile("Hades/Hades", Some(1145360), 1500, 200),
+                profile("vkcube", None, 1100, 10),
+                profile("glxgears", None, 800, 10),
+            ],
+        };
+        let local = [
+            profile("Hades/Hades", Some(1145360), 1400, 100),
+            profile("vkcube", None, 1000, 50),
+        ];
+        let freq = |db: &ProcessDatabase, key| db.get(key).unwrap().optimal_freq;
+
+        let cases = [
+            (MergeStrategy::KeepLocal, 1400, 1000),
+            (MergeStrategy::MoreSamples, 1500, 1000),
+            (MergeStrategy::PreferRemote, 1500, 1100),
+        ];
+        for (strategy, hades, vkcube) in cases {
+            let dir = TempDir::new(&format!("bundle-{}", strategy.name()));
+            let mut db = database(&dir, &local);
+            let summary = bundle.import_into(&mut db, CURVE, strategy).unwrap();
+            assert_eq!(summary.added, ["glxgears"]);
+            assert_eq!(
+                summary.replaced.len() + summary.kept.len(),
+                2,
+                "{}",
+                strategy.name()
+            );
+            assert_eq!(freq(&db, "steam:1145360"), hades, "{}", strategy.name());
+            assert_eq!(freq(&db, "vkcube"), vkcube, "{}", strategy.name());
+
+            // Enregistré sur disque
+            let reopened = ProcessDatabase::open(db.path());
+            assert_eq!(freq(&reopened, "steam:1145360"), hades);
+            assert_eq!(reopened.profiles.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_import_refuses_other_curve() {
+        let bundle = ProfileBundle {
+            version: BUNDLE_VERSION,
+            created: 0,
+            voltage_curve: CURVE.to_string(),
+            device: None,
+            profiles: vec![profile("vkcube", None, 1100, 10)],
+        };
+        let dir = TempDir::new("bundle-curve");
+        let mut db = database(&dir, &[]);
+        assert!(matches!(
+            bundle.import_into(&mut db, "fedcba9876543210", MergeStrategy::PreferRemote),
+            Err(BundleError::CurveMismatch { .. })
+        ));
+        assert!(db.profiles.is_empty());
+
+        // Un profil qui porte une autre courbe que celle du paquet
+        let mut mixed = bundle.clone();
+        mixed.profiles[0].voltage_curve = Some("fedcba9876543210".to_string());
+        assert!(matches!(
+            mixed.import_into(&mut db, CURVE, MergeStrategy::PreferRemote),
+            Err(BundleError::CurveMismatch { bundle, .. }) if bundle == "fedcba9876543210"
+        ));
+        assert!(db.profiles.is_empty());
+
+        // Importé avec la courbe, pour pouvoir être réexporté
+        bundle
+            .import_into(&mut db, CURVE, MergeStrategy::PreferRemote)
+            .unwrap();
+        assert_eq!(
+            db.get("vkcube").unwrap().voltage_curve.as_deref(),
+            Some(CURVE)
+        );
+    }
+}
@@ -1,67 +1,572 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::discovery::{PciDevice, scan_pci_devices};
+use crate::system_paths::SystemPaths;
+
+/// Nom du fichier de la base dans son répertoire
+pub const DATABASE_FILE: &str = "process_profiles.json";
+
+/// Répertoire de la base d'un service système lancé en root
+pub const SYSTEM_STATE_DIR: &str = "/var/lib/cyan-skillfish-governor";
+
+/// Sous-répertoire de `~/.cache` et `~/.local/share`
+const APP_DIR: &str = "cyan-skillfish-governor";
+
+/// Section `[profiles]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfilesConfig {
+    /// Fichier de la base; `default_database_path()` si absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl ProfilesConfig {
+    pub fn database_path(&self) -> PathBuf {
+        self.path.clone().unwrap_or_else(default_database_path)
+    }
+}
+
+/// Emplacement par défaut de la base
+///
+/// `$STATE_DIRECTORY` sous systemd (`StateDirectory=`), `SYSTEM_STATE_DIR`
+/// pour root, `~/.local/share/cyan-skillfish-governor` sinon: pas de
+/// répertoire de cache, qui peut être vidé alors que l'apprentissage prend
+/// plusieurs minutes de jeu par profil.
+pub fn default_database_path() -> PathBuf {
+    // SAFETY: geteuid n'a pas de précondition et n'échoue jamais
+    let root = unsafe { libc::geteuid() } == 0;
+    state_dir(std::env::var_os("STATE_DIRECTORY"), root, dirs::data_dir()).join(DATABASE_FILE)
+}
+
+fn state_dir(state_directory: Option<OsString>, root: bool, data_dir: Option<PathBuf>) -> PathBuf {
+    // systemd sépare par `:` les répertoires de plusieurs `StateDirectory=`
+    let from_systemd =
+        state_directory.and_then(|dirs| std::env::split_paths(&dirs).find(|dir| dir.is_absolute()));
+    if let Some(dir) = from_systemd {
+        return dir;
+    }
+    match data_dir {
+        Some(data_dir) if !root => data_dir.join(APP_DIR),
+        _ => PathBuf::from(SYSTEM_STATE_DIR),
+    }
+}
+
+/// Emplacement des versions précédentes, dans le répertoire de cache
+fn legacy_database_path() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join(APP_DIR).join(DATABASE_FILE))
+}
+
+/// Importe une seule fois la base de `legacy` dans `path`, encore absent
+///
+/// `legacy` est ensuite renommé en `<fichier>.imported`: son contenu reste
+/// disponible, mais une base supprimée volontairement n'est pas réimportée.
+fn import_legacy(path: &Path, legacy: &Path) -> io::Result<bool> {
+    if path == legacy || !legacy.exists() {
+        return Ok(false);
+    }
+    let _lock = lock_database(path)?;
+    if path.exists() {
+        return Ok(false);
+    }
+    write_atomic(path, &std::fs::read_to_string(legacy)?)?;
+    std::fs::rename(legacy, with_suffix(legacy, ".imported"))?;
+    println!(
+        "📦 Base de profils importée de {} vers {}",
+        legacy.display(),
+        path.display()
+    );
+    Ok(true)
+}
+
+/// Version du format de `process_profiles.json`
+///
+/// - 0: table `clé → profil` nue, écrite avant l'enveloppe
+/// - 1: enveloppe `DatabaseFile`
+pub const DATABASE_VERSION: u64 = 1;
+
+/// `MIGRATIONS[n]` passe un fichier de la version `n` à la version `n + 1`
+const MIGRATIONS: [fn(Value) -> Value; DATABASE_VERSION as usize] = [migrate_v0];
+
+/// Préfixe des clés de profils de jeux Steam
+pub const STEAM_KEY_PREFIX: &str = "steam:";
+
+/// Clé d'un profil: `steam:<appid>` pour un jeu Steam, stable quel que soit
+/// le lanceur ou la version de Proton; le nom du processus sinon
+pub fn profile_key(name: &str, steam_app_id: Option<u64>) -> String {
+    match steam_app_id {
+        Some(app_id) => format!("{STEAM_KEY_PREFIX}{app_id}"),
+        None => name.to_string(),
+    }
+}
+
+/// AppID d'une clé `steam:<appid>`
+pub fn steam_app_id(key: &str) -> Option<u64> {
+    key.strip_prefix(STEAM_KEY_PREFIX)?.parse().ok()
+}
 
 /// Profil d'un processus
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProcessProfile {
+    /// Nom lisible du processus (celui de `read_process_name`)
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steam_app_id: Option<u64>,
     pub optimal_freq: u16,
     pub comfort_score: f32,
     pub samples_count: usize,
+    /// `VoltageCurve::fingerprint` de la config lors de l'apprentissage;
+    /// absent pour les profils appris avant qu'elle soit enregistrée
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voltage_curve: Option<String>,
 }
 
 impl ProcessProfile {
     pub fn new(name: String, freq: u16, comfort: f32, samples: usize) -> Self {
         Self {
             name,
+            steam_app_id: None,
             optimal_freq: freq,
             comfort_score: comfort,
             samples_count: samples,
+            voltage_curve: None,
+        }
+    }
+
+    /// Clé sous laquelle le profil est rangé
+    pub fn key(&self) -> String {
+        profile_key(&self.name, self.steam_app_id)
+    }
+}
+
+/// GPU sur lequel les profils ont été appris
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    /// Adresse PCI (ex: `0000:01:00.0`)
+    pub pci_address: String,
+    /// `vendor:device` (ex: `1002:13fe`)
+    pub pci_id: String,
+}
+
+impl From<&PciDevice> for DeviceIdentity {
+    fn from(device: &PciDevice) -> Self {
+        Self {
+            pci_address: device.location.to_string(),
+            pci_id: format!("{:04x}:{:04x}", device.vendor, device.device),
         }
     }
 }
 
+impl DeviceIdentity {
+    /// Premier Cyan Skillfish de `<sysfs_root>/bus/pci/devices`
+    pub fn detect(sysfs_root: &Path) -> Option<Self> {
+        let devices = scan_pci_devices(sysfs_root).ok()?;
+        devices
+            .iter()
+            .find(|d| d.is_cyan_skillfish())
+            .map(Self::from)
+    }
+}
+
+/// Contenu de `process_profiles.json` depuis la version 1
+#[derive(Debug, Serialize, Deserialize)]
+struct DatabaseFile {
+    version: u64,
+    /// Secondes depuis l'époque Unix; 0 si inconnu (fichier migré)
+    #[serde(default)]
+    created: u64,
+    #[serde(default)]
+    updated: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device: Option<DeviceIdentity>,
+    profiles: HashMap<String, ProcessProfile>,
+}
+
+/// Version 0 → 1: la table nue devient `profiles` de l'enveloppe
+fn migrate_v0(profiles: Value) -> Value {
+    serde_json::json!({ "version": 1, "profiles": profiles })
+}
+
+/// Version d'un fichier: `version` de l'enveloppe, 0 pour une table nue
+fn file_version(value: &Value) -> Result<u64, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| "objet JSON attendu".to_string())?;
+    match object.get("version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("version invalide: {}", version)),
+        None => Ok(0),
+    }
+}
+
+/// Échec de `parse_database`
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    /// Écrit par une version plus récente du gouverneur: à laisser intact
+    Newer(u64),
+    Invalid(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Newer(version) => write!(
+                f,
+                "version {} plus récente que celle supportée ({})",
+                version, DATABASE_VERSION
+            ),
+            Self::Invalid(e) => f.write_str(e),
+        }
+    }
+}
+
+/// Lit un fichier de n'importe quelle version connue, migré vers `DATABASE_VERSION`
+///
+/// Renvoie aussi la version du fichier avant migration.
+fn parse_database(content: &str) -> Result<(DatabaseFile, u64), ParseError> {
+    let mut value: Value =
+        serde_json::from_str(content).map_err(|e| ParseError::Invalid(e.to_string()))?;
+    let version = file_version(&value).map_err(ParseError::Invalid)?;
+    if version > DATABASE_VERSION {
+        return Err(ParseError::Newer(version));
+    }
+    for migrate in &MIGRATIONS[version as usize..] {
+        value = migrate(value);
+    }
+    let file = serde_json::from_value(value).map_err(|e| ParseError::Invalid(e.to_string()))?;
+    Ok((file, version))
+}
+
+/// Fichier `path` migré, `None` s'il est absent ou illisible
+///
+/// Avec `backup`, un fichier qui ne se parse pas est renommé (voir
+/// `backup_path`) plutôt qu'écrasé à la prochaine sauvegarde. Un fichier d'une
+/// version plus récente est une erreur et reste en place: le réécrire en
+/// perdrait le contenu.
+fn read_database(path: &Path, backup: bool) -> io::Result<Option<DatabaseFile>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            println!("⚠ Lecture de {} impossible: {}", path.display(), e);
+            return Ok(None);
+        }
+    };
+
+    match parse_database(&content) {
+        Ok((file, version)) => {
+            if version < DATABASE_VERSION {
+                println!(
+                    "📦 Base de profils migrée de la version {} à {}",
+                    version, DATABASE_VERSION
+                );
+            }
+            Ok(Some(file))
+        }
+        Err(e @ ParseError::Newer(_)) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("base de profils {}: {}", path.display(), e),
+        )),
+        Err(e) if !backup => {
+            println!("⚠ Base de profils {} illisible ({})", path.display(), e);
+            Ok(None)
+        }
+        Err(e) => {
+            let backup = backup_path(path);
+            match std::fs::rename(path, &backup) {
+                Ok(()) => println!(
+                    "⚠ Base de profils {} illisible ({}), conservée sous {}",
+                    path.display(),
+                    e,
+                    backup.display()
+                ),
+                Err(rename_err) => println!(
+                    "⚠ Base de profils {} illisible ({}), copie impossible: {}",
+                    path.display(),
+                    e,
+                    rename_err
+                ),
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Écrit `content` dans `path` via un fichier temporaire synchronisé puis renommé:
+/// une coupure laisse l'ancienne version ou la nouvelle, jamais un mélange
+fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let temp_path = with_suffix(path, ".tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temp_path, path)?;
+
+    // Le renommage n'est durable qu'une fois le répertoire synchronisé
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Verrou exclusif sur `<fichier>.lock`, relâché à la fermeture du fichier
+///
+/// Le verrou ne peut pas porter sur la base elle-même: chaque sauvegarde
+/// la remplace par un nouveau fichier.
+fn lock_database(path: &Path) -> io::Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(with_suffix(path, ".lock"))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// `<fichier>.bak-<horodatage>`, suivi de `-1`, `-2` … si une copie de la
+/// même seconde existe déjà
+fn backup_path(path: &Path) -> PathBuf {
+    let stamp = format!(".bak-{}", unix_now());
+    std::iter::once(with_suffix(path, &stamp))
+        .chain((1..).map(|n| with_suffix(path, &format!("{stamp}-{n}"))))
+        .find(|backup| !backup.exists())
+        .unwrap()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
 /// Base de données de profils par processus
+///
+/// Plusieurs instances (service et lancement manuel) peuvent partager le même
+/// fichier: chaque sauvegarde se fait sous le verrou `<fichier>.lock` et
+/// fusionne les profils modifiés ici avec ceux enregistrés entre-temps.
 pub struct ProcessDatabase {
+    /// Lecture seule: les modifications passent par `set` et `adopt` pour être
+    /// fusionnées
     pub profiles: HashMap<String, ProcessProfile>,
     db_path: PathBuf,
+    created: u64,
+    updated: u64,
+    device: Option<DeviceIdentity>,
+    /// Clés écrites ou supprimées depuis la dernière sauvegarde
+    modified: HashSet<String>,
+    removed: HashSet<String>,
+    /// Ouverte par `open_read_only`: ni verrou, ni copie, ni sauvegarde
+    read_only: bool,
 }
 
 impl ProcessDatabase {
+    /// Base à l'emplacement par défaut (voir `default_database_path`)
     pub fn new() -> Self {
-        let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("/tmp"));
-        path.push("cyan-skillfish-governor");
-        std::fs::create_dir_all(&path).ok();
-        path.push("process_profiles.json");
+        Self::with_path(default_database_path(), &SystemPaths::default())
+    }
+
+    /// Base du gouverneur stockée dans `path`
+    ///
+    /// Crée le répertoire, importe la base de l'ancien emplacement et
+    /// enregistre le GPU courant, cherché sous `paths.sys`.
+    pub fn with_path(path: impl Into<PathBuf>, paths: &SystemPaths) -> Self {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        if let Some(legacy) = legacy_database_path()
+            && let Err(e) = import_legacy(&path, &legacy)
+        {
+            println!(
+                "⚠ Import de {} vers {} impossible: {}",
+                legacy.display(),
+                path.display(),
+                e
+            );
+        }
+        let mut db = Self::open(path);
+        db.set_device(DeviceIdentity::detect(&paths.sys));
+        db
+    }
 
+    /// Fichier de la base
+    pub fn path(&self) -> &Path {
+        &self.db_path
+    }
+
+    /// Base stockée dans `path` (chargée si le fichier existe), sans import
+    /// ni détection du GPU
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self::open_with(path.into(), false)
+    }
+
+    /// Base stockée dans `path`, consultée sans rien modifier sur le disque
+    ///
+    /// Ni répertoire, ni import, ni `.lock`, ni copie d'un fichier illisible;
+    /// `save` échoue.
+    pub fn open_read_only(path: impl Into<PathBuf>) -> Self {
+        Self::open_with(path.into(), true)
+    }
+
+    fn open_with(db_path: PathBuf, read_only: bool) -> Self {
+        let now = unix_now();
         let mut db = Self {
             profiles: HashMap::new(),
-            db_path: path,
+            db_path,
+            created: now,
+            updated: now,
+            device: None,
+            modified: HashSet::new(),
+            removed: HashSet::new(),
+            read_only,
         };
 
         db.load();
         db
     }
 
+    /// Recharge le fichier, migré depuis les versions précédentes
+    ///
+    /// Un fichier illisible est renommé en `<fichier>.bak-<horodatage>[-n]`
+    /// plutôt qu'écrasé à la prochaine sauvegarde. Celui d'une version plus
+    /// récente n'est pas chargé et `save` le refuse.
     pub fn load(&mut self) {
-        if self.db_path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&self.db_path) {
-                if let Ok(profiles) = serde_json::from_str(&content) {
-                    self.profiles = profiles;
-                    println!("📚 {} profils de processus chargés", self.profiles.len());
+        // Sans verrou (répertoire en lecture seule), la lecture reste possible
+        let _lock = if self.read_only {
+            None
+        } else {
+            self.lock().ok()
+        };
+        let file = read_database(&self.db_path, !self.read_only).unwrap_or_else(|e| {
+            println!("⚠ {}, profils non chargés ni enregistrés", e);
+            None
+        });
+        if let Some(file) = file {
+            self.profiles = file.profiles;
+            if file.created != 0 {
+                self.created = file.created;
+            }
+            self.updated = file.updated;
+            self.device = file.device;
+            println!("📚 {} profils de processus chargés", self.profiles.len());
+        }
+        self.modified.clear();
+        self.removed.clear();
+    }
+
+    fn lock(&self) -> io::Result<File> {
+        lock_database(&self.db_path)
+    }
+
+    /// Enregistre la base sous verrou, fusionnée avec le fichier
+    ///
+    /// Les profils modifiés ici l'emportent; les autres sont repris du fichier,
+    /// tels qu'une autre instance a pu les écrire. Échoue sans rien écrire si
+    /// le fichier vient d'une version plus récente.
+    pub fn save(&mut self) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "base ouverte en lecture seule",
+            ));
+        }
+        let _lock = self.lock()?;
+        let mut profiles = self.profiles.clone();
+        if let Some(file) = read_database(&self.db_path, true)? {
+            profiles = file.profiles;
+            for key in &self.removed {
+                profiles.remove(key);
+            }
+            for key in &self.modified {
+                if let Some(profile) = self.profiles.get(key) {
+                    profiles.insert(key.clone(), profile.clone());
                 }
             }
+            if file.created != 0 {
+                self.created = file.created;
+            }
+            if self.device.is_none() {
+                self.device = file.device;
+            }
+        }
+
+        let file = DatabaseFile {
+            version: DATABASE_VERSION,
+            created: self.created,
+            updated: unix_now(),
+            device: self.device.clone(),
+            profiles,
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(io::Error::other)?;
+        write_atomic(&self.db_path, &json)?;
+
+        self.profiles = file.profiles;
+        self.updated = file.updated;
+        self.modified.clear();
+        self.removed.clear();
+        Ok(())
+    }
+
+    /// `save`, l'échec seulement signalé: l'apprentissage continue en mémoire
+    fn save_or_warn(&mut self) {
+        if let Err(e) = self.save() {
+            println!(
+                "⚠ Sauvegarde de {} impossible: {}",
+                self.db_path.display(),
+                e
+            );
         }
     }
 
-    pub fn save(&self) {
-        if let Ok(json) = serde_json::to_string_pretty(&self.profiles) {
-            let _ = std::fs::write(&self.db_path, json);
+    /// Création de la base, en secondes depuis l'époque Unix
+    pub fn created(&self) -> u64 {
+        self.created
+    }
+
+    /// Dernière sauvegarde, en secondes depuis l'époque Unix
+    pub fn updated(&self) -> u64 {
+        self.updated
+    }
+
+    /// GPU sur lequel les profils ont été appris
+    pub fn device(&self) -> Option<&DeviceIdentity> {
+        self.device.as_ref()
+    }
+
+    /// Enregistre le GPU courant, signalé s'il diffère de celui de la base
+    ///
+    /// Les profils sont conservés: ils restent un point de départ raisonnable.
+    pub fn set_device(&mut self, device: Option<DeviceIdentity>) {
+        let Some(device) = device else {
+            return;
+        };
+        if let Some(previous) = &self.device
+            && *previous != device
+        {
+            println!(
+                "⚠ Profils appris sur le GPU {} [{}], GPU actuel {} [{}]",
+                previous.pci_address, previous.pci_id, device.pci_address, device.pci_id
+            );
         }
+        self.device = Some(device);
     }
 
-    pub fn get(&self, process_name: &str) -> Option<&ProcessProfile> {
-        self.profiles.get(process_name)
+    /// Profil rangé sous `key` (voir `profile_key`)
+    pub fn get(&self, key: &str) -> Option<&ProcessProfile> {
+        self.profiles.get(key)
     }
 
     pub fn set(&mut self, profile: ProcessProfile) {
@@ -69,13 +574,54 @@ impl ProcessDatabase {
             "💾 Sauvegarde profil: {} → {} MHz (confort: {:.1}/100)",
             profile.name, profile.optimal_freq, profile.comfort_score
         );
-        self.profiles.insert(profile.name.clone(), profile);
-        self.save();
+        self.insert(profile);
+        self.save_or_warn();
+    }
+
+    /// Ajoute ou remplace un profil sans sauvegarder, pour en enregistrer
+    /// plusieurs d'un coup avec `save`
+    pub fn insert(&mut self, profile: ProcessProfile) {
+        let key = profile.key();
+        self.removed.remove(&key);
+        self.modified.insert(key.clone());
+        self.profiles.insert(key, profile);
+    }
+
+    /// Range sous `key` le profil appris sous le nom `name` avant que son AppID soit connu
+    ///
+    /// Sans effet si `key` a déjà un profil ou n'est pas une clé Steam.
+    pub fn adopt(&mut self, key: &str, name: &str) -> bool {
+        let Some(app_id) = steam_app_id(key) else {
+            return false;
+        };
+        if self.profiles.contains_key(key)
+            || self
+                .profiles
+                .get(name)
+                .is_none_or(|p| p.steam_app_id.is_some())
+        {
+            return false;
+        }
+        let mut profile = self.profiles.remove(name).unwrap();
+        profile.steam_app_id = Some(app_id);
+        println!("🔑 Profil '{}' rangé sous {}", name, key);
+        self.profiles.insert(key.to_string(), profile);
+        self.modified.remove(name);
+        self.removed.insert(name.to_string());
+        self.removed.remove(key);
+        self.modified.insert(key.to_string());
+        self.save_or_warn();
+        true
     }
 
     pub fn print_summary(&self) {
         println!("=== BASE DE DONNÉES JEUX/PROCESSUS ===");
-        for (name, profile) in &self.profiles {
+        for (key, profile) in &self.profiles {
+            let name = if *key == profile.name {
+                profile.name.clone()
+            } else {
+                format!("{} ({})", profile.name, key)
+            };
             println!(
                 "  🎮 {} → {} MHz (confort: {:.1}/100, {} échantillons)",
                 name, profile.optimal_freq, profile.comfort_score, profile.samples_count
@@ -90,3 +636,253 @@ impl Default for ProcessDatabase {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::TempDir;
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(profile_key("Hades/Hades", Some(1145360)), "steam:1145360");
+        assert_eq!(profile_key("/usr/bin/vkcube", None), "/usr/bin/vkcube");
+        assert_eq!(steam_app_id("steam:1145360"), Some(1145360));
+        assert_eq!(steam_app_id("steam:Hades"), None);
+        assert_eq!(steam_app_id("Hades/Hades"), None);
+    }
+
+    #[test]
+    fn test_adopt_profile_learned_by_name() {
+        let dir = TempDir::new("db-adopt");
+        let path = dir.join(DATABASE_FILE);
+        // Base écrite avant les AppID: rangée par nom, sans `steam_app_id`
+        std::fs::write(
+            &path,
+            r#"{"Hades/Hades": {"name": "Hades/Hades", "optimal_freq": 1400,
+                "comfort_score": 90.0, "samples_count": 100}}"#,
+        )
+        .unwrap();
+        let mut db = ProcessDatabase::open(&path);
+        assert!(!db.adopt("Hades/Hades", "Hades/Hades"));
+        assert!(db.adopt("steam:1145360", "Hades/Hades"));
+        assert!(!db.adopt("steam:1145360", "Hades/Hades"));
+        assert!(db.get("Hades/Hades").is_none());
+        let profile = db.get("steam:1145360").unwrap();
+        assert_eq!(
+            (profile.name.as_str(), profile.steam_app_id),
+            ("Hades/Hades", Some(1145360))
+        );
+
+        let db = ProcessDatabase::open(&path);
+        assert_eq!(db.get("steam:1145360").unwrap().key(), "steam:1145360");
+    }
+
+    const V0_FILE: &str = r#"{"vkcube": {"name": "vkcube", "optimal_freq": 1000,
+        "comfort_score": 80.0, "samples_count": 50}}"#;
+
+    #[test]
+    fn test_migrate_v0() {
+        let value: Value = serde_json::from_str(V0_FILE).unwrap();
+        assert_eq!(file_version(&value), Ok(0));
+        let migrated = migrate_v0(value);
+        assert_eq!(file_version(&migrated), Ok(1));
+        assert_eq!(migrated["profiles"]["vkcube"]["optimal_freq"], 1000);
+
+        let (file, version) = parse_database(V0_FILE).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!((file.version, file.created), (1, 0));
+        assert_eq!(file.profiles["vkcube"].samples_count, 50);
+    }
+
+    #[test]
+    fn test_save_writes_current_version() {
+        let dir = TempDir::new("db-save");
+        let path = dir.join(DATABASE_FILE);
+        std::fs::write(&path, V0_FILE).unwrap();
+        let mut db = ProcessDatabase::open(&path);
+        let created = db.created();
+        db.set_device(Some(DeviceIdentity {
+            pci_address: "0000:01:00.0".to_string(),
+            pci_id: "1002:13fe".to_string(),
+        }));
+        db.set(ProcessProfile::new("glxgears".to_string(), 800, 95.0, 10));
+
+        let (file, version) = parse_database(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            (file.version, version),
+            (DATABASE_VERSION, DATABASE_VERSION)
+        );
+        assert_eq!(file.profiles.len(), 2);
+        assert_eq!(file.created, created);
+        assert!(file.updated >= created);
+        assert_eq!(file.device.unwrap().pci_id, "1002:13fe");
+
+        let db = ProcessDatabase::open(&path);
+        assert_eq!(db.created(), created);
+        assert_eq!(db.device().unwrap().pci_address, "0000:01:00.0");
+    }
+
+    /// Contenu des copies de fichiers illisibles conservées à côté de `path`
+    fn backups(path: &Path) -> Vec<String> {
+        std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.to_string_lossy().contains(".bak-"))
+            .map(|p| std::fs::read_to_string(p).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_unparseable_file_is_backed_up() {
+        let dir = TempDir::new("db-corrupt");
+        let path = dir.join(DATABASE_FILE);
+        std::fs::write(&path, "{\"vkcube\": {\"name\": ").unwrap();
+        let db = ProcessDatabase::open(&path);
+        assert!(db.profiles.is_empty());
+        assert!(!path.exists());
+        assert_eq!(backups(&path), ["{\"vkcube\": {\"name\": "]);
+
+        // Une deuxième copie dans la même seconde ne remplace pas la première
+        std::fs::write(&path, "[").unwrap();
+        ProcessDatabase::open(&path);
+        let mut contents = backups(&path);
+        contents.sort();
+        assert_eq!(contents, ["[", "{\"vkcube\": {\"name\": "]);
+    }
+
+    #[test]
+    fn test_read_only_changes_nothing() {
+        let dir = TempDir::new("db-read-only");
+        let path = dir.join("missing").join(DATABASE_FILE);
+        let mut db = ProcessDatabase::open_read_only(&path);
+        assert!(db.profiles.is_empty());
+        assert!(!path.parent().unwrap().exists());
+
+        let path = dir.join(DATABASE_FILE);
+        std::fs::write(&path, "[").unwrap();
+        db = ProcessDatabase::open_read_only(&path);
+        db.set(ProcessProfile::new("vkcube".to_string(), 1000, 80.0, 50));
+        assert_eq!(
+            db.save().unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        let mut entries: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, [DATABASE_FILE]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[");
+    }
+
+    #[test]
+    fn test_concurrent_instances_merge() {
+        let dir = TempDir::new("db-merge");
+        let path = dir.join(DATABASE_FILE);
+        std::fs::write(
+            &path,
+            r#"{"Hades/Hades": {"name": "Hades/Hades", "optimal_freq": 1400,
+                "comfort_score": 90.0, "samples_count": 100}}"#,
+        )
+        .unwrap();
+        let mut service = ProcessDatabase::open(&path);
+        let mut manual = ProcessDatabase::open(&path);
+
+        service.set(ProcessProfile::new("vkcube".to_string(), 1000, 80.0, 50));
+        assert!(service.adopt("steam:1145360", "Hades/Hades"));
+        // Sauvegarde de l'autre instance, qui n'a vu aucune de ces modifications
+        manual.set(ProcessProfile::new("glxgears".to_string(), 800, 95.0, 10));
+
+        let mut keys: Vec<_> = manual.profiles.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["glxgears", "steam:1145360", "vkcube"]);
+        let db = ProcessDatabase::open(&path);
+        assert_eq!(db.profiles.len(), 3);
+        assert!(db.get("Hades/Hades").is_none());
+    }
+
+    #[test]
+    fn test_save_is_atomic_and_locked() {
+        let dir = TempDir::new("db-atomic");
+        let path = dir.join(DATABASE_FILE);
+        let mut db = ProcessDatabase::open(&path);
+        db.set(ProcessProfile::new("vkcube".to_string(), 1000, 80.0, 50));
+        assert!(path.exists());
+        assert!(!with_suffix(&path, ".tmp").exists());
+
+        let lock = db.lock().unwrap();
+        let other = File::open(with_suffix(&path, ".lock")).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_state_dir() {
+        let data_dir = Some(PathBuf::from("/home/deck/.local/share"));
+        let systemd = Some(OsString::from(
+            "/var/lib/cyan-skillfish-governor:/var/lib/other",
+        ));
+        assert_eq!(
+            state_dir(systemd, false, data_dir.clone()),
+            Path::new(SYSTEM_STATE_DIR)
+        );
+        assert_eq!(
+            state_dir(Some(OsString::from("/srv/csg")), true, None),
+            Path::new("/srv/csg")
+        );
+        assert_eq!(
+            state_dir(None, true, data_dir.clone()),
+            Path::new(SYSTEM_STATE_DIR)
+        );
+        assert_eq!(
+            state_dir(None, false, data_dir),
+            Path::new("/home/deck/.local/share/cyan-skillfish-governor")
+        );
+        assert_eq!(state_dir(None, false, None), Path::new(SYSTEM_STATE_DIR));
+    }
+
+    #[test]
+    fn test_import_legacy_once() {
+        let dir = TempDir::new("db-import");
+        let path = dir.join(DATABASE_FILE);
+        let legacy = dir.join("cache").join(DATABASE_FILE);
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(&legacy, V0_FILE).unwrap();
+
+        assert!(import_legacy(&path, &legacy).unwrap());
+        assert!(!legacy.exists());
+        assert!(with_suffix(&legacy, ".imported").exists());
+        assert_eq!(ProcessDatabase::open(&path).profiles.len(), 1);
+
+        // Une nouvelle base ancienne n'écrase pas celle déjà importée
+        std::fs::write(&legacy, "{}").unwrap();
+        assert!(!import_legacy(&path, &legacy).unwrap());
+        assert!(legacy.exists());
+        assert!(!import_legacy(&legacy, &legacy).unwrap());
+        assert_eq!(ProcessDatabase::open(&path).profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_newer_version_is_left_untouched() {
+        let newer = format!(
+            r#"{{"version": {}, "profiles": {{"glxgears": {{"name": "glxgears"}}}}}}"#,
+            DATABASE_VERSION + 1
+        );
+        assert_eq!(
+            parse_database(&newer).unwrap_err(),
+            ParseError::Newer(DATABASE_VERSION + 1)
+        );
+
+        let dir = TempDir::new("db-newer");
+        let path = dir.join(DATABASE_FILE);
+        std::fs::write(&path, &newer).unwrap();
+        let mut db = ProcessDatabase::open(&path);
+        assert!(db.profiles.is_empty());
+        db.set(ProcessProfile::new("vkcube".to_string(), 1000, 80.0, 50));
+        let err = db.save().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer);
+        assert!(backups(&path).is_empty());
+    }
+}
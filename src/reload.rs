@@ -0,0 +1,93 @@
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+use crate::config::GovernorConfig;
+
+static SIGHUP_RECEIVED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sighup(_: libc::c_int) {
+    SIGHUP_RECEIVED.store(true, Ordering::SeqCst);
+}
+
+/// Installe le handler SIGHUP qui demande un rechargement de la configuration
+///
+/// À appeler après `ClockGuard::install_handlers`, dont le handler ctrlc
+/// remplace aussi celui de SIGHUP.
+pub fn install_sighup_handler() -> Result<(), IoError> {
+    let handler = on_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: le handler ne fait qu'écrire dans un AtomicBool
+    if unsafe { libc::signal(libc::SIGHUP, handler) } == libc::SIG_ERR {
+        return Err(IoError::last_os_error());
+    }
+    Ok(())
+}
+
+/// Détecte les demandes de rechargement (SIGHUP ou fichier modifié)
+pub struct ConfigWatcher {
+    path: PathBuf,
+    watch_file: bool,
+    last_modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: impl Into<PathBuf>, watch_file: bool) -> Self {
+        let path = path.into();
+        let last_modified = modified(&path);
+        Self {
+            path,
+            watch_file,
+            last_modified,
+        }
+    }
+
+    /// Active ou désactive la surveillance du fichier (clé `reload.watch`)
+    pub fn set_watch_file(&mut self, watch_file: bool) {
+        self.watch_file = watch_file;
+    }
+
+    /// Relit la configuration si un rechargement a été demandé depuis le dernier appel
+    ///
+    /// Renvoie `None` s'il n'y a rien à faire; une erreur signifie que la
+    /// nouvelle configuration est invalide et que l'ancienne doit être gardée.
+    pub fn poll(&mut self) -> Option<Result<GovernorConfig, IoError>> {
+        let signaled = SIGHUP_RECEIVED.swap(false, Ordering::SeqCst);
+        let changed = self.watch_file && {
+            let current = modified(&self.path);
+            current != self.last_modified
+        };
+        if !signaled && !changed {
+            return None;
+        }
+        self.last_modified = modified(&self.path);
+        Some(GovernorConfig::load(&self.path))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::TempDir;
+
+    #[test]
+    fn test_file_change_triggers_reload() {
+        let dir = TempDir::new("reload");
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "[load-target]\nupper = 95\n").unwrap();
+
+        let mut watcher = ConfigWatcher::new(&path, true);
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&path, "safe-points = []\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        assert!(watcher.poll().unwrap().is_err());
+        assert!(watcher.poll().is_none());
+    }
+}
@@ -0,0 +1,263 @@
+use std::time::{Duration, Instant};
+
+use crate::config::GovernorConfig;
+use crate::load_monitor::GpuLoadMonitor;
+use crate::policy::{Decision, FrequencyPolicy, LoadSample, PolicyContext};
+
+/// Un échantillon GRBM et ce que la boucle sait à cet instant
+#[derive(Debug, Clone, Copy)]
+pub struct SampleInput<'a> {
+    pub busy: bool,
+    pub timestamp: Instant,
+    pub config: &'a GovernorConfig,
+    /// MHz: plage sûre, bornée par le GPU
+    pub min_freq: u16,
+    pub max_freq: u16,
+    /// Fréquence figée via le socket de contrôle
+    pub pinned: Option<u16>,
+    /// Clé du profil du processus principal (voir `PolicyContext`)
+    pub active_process: Option<&'a str>,
+    pub active_process_name: Option<&'a str>,
+    pub process_stable: bool,
+}
+
+/// Fréquence à publier, avec les raisons du changement (comme dans le log `[FREQ]`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreqChange {
+    /// MHz
+    pub from: u16,
+    pub to: u16,
+    pub reasons: Vec<&'static str>,
+}
+
+/// Résultat d'un échantillon
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// Pourcentage (0-100), moyenne sur `timing.window-samples`
+    pub load_percent: f32,
+    pub decision: Decision,
+    /// MHz, bornée à la plage sûre
+    pub target_freq: u16,
+    /// Présent si la fréquence doit être appliquée
+    pub change: Option<FreqChange>,
+}
+
+/// Boucle d'échantillonnage du gouverneur, sans GPU ni horloge
+///
+/// La politique choisit une fréquence à chaque échantillon; sauf décision
+/// immédiate (burst, `pin` …), elle n'est publiée que toutes les
+/// `timing.intervals.adjust` et si `frequency-thresholds` le justifie.
+/// L'heure vient des échantillons: le démon passe l'heure réelle, le
+/// simulateur une horloge virtuelle.
+pub struct Sampler {
+    policy: Box<dyn FrequencyPolicy>,
+    load: GpuLoadMonitor,
+    window_samples: usize,
+    /// MHz: dernière fréquence publiée
+    curr_freq: u16,
+    last_adjustment: Instant,
+    last_finetune: Instant,
+    last_freq_change: Instant,
+}
+
+impl Sampler {
+    /// Boucle démarrant à `freq` (MHz), déjà appliquée
+    pub fn new(
+        policy: Box<dyn FrequencyPolicy>,
+        window_samples: usize,
+        freq: u16,
+        now: Instant,
+    ) -> Self {
+        Self {
+            policy,
+            load: GpuLoadMonitor::new(window_samples),
+            window_samples,
+            curr_freq: freq,
+            last_adjustment: now,
+            last_finetune: now,
+            last_freq_change: now,
+        }
+    }
+
+    pub fn policy(&self) -> &dyn FrequencyPolicy {
+        &*self.policy
+    }
+
+    /// Remplace la politique (config rechargée, `set-policy`)
+    pub fn set_policy(&mut self, policy: Box<dyn FrequencyPolicy>) {
+        self.policy = policy;
+    }
+
+    /// Change la taille de la fenêtre de charge; l'historique est perdu
+    pub fn set_window(&mut self, window_samples: usize) {
+        if window_samples != self.window_samples {
+            self.load = GpuLoadMonitor::new(window_samples);
+            self.window_samples = window_samples;
+        }
+    }
+
+    /// MHz: dernière fréquence publiée
+    pub fn current_freq(&self) -> u16 {
+        self.curr_freq
+    }
+
+    pub fn step(&mut self, input: &SampleInput) -> Step {
+        let config = input.config;
+        let (min_freq, max_freq) = (input.min_freq, input.max_freq);
+        let now = input.timestamp;
+
+        // Radeontop method: counting for percentage
+        self.load.add_sample(input.busy);
+        let load_percent = self.load.load_percent();
+
+        let decision = match input.pinned {
+            // Pinned from the control socket: the load is ignored
+            Some(pinned) => {
+                self.policy.reset(pinned);
+                Decision {
+                    freq: pinned,
+                    mode: "pinned",
+                    reason: Some("pinned from control socket"),
+                    immediate: true,
+                }
+            }
+            None => {
+                let sample = LoadSample {
+                    busy: input.busy,
+                    load_percent,
+                    timestamp: now,
+                };
+                let ctx = PolicyContext {
+                    config,
+                    min_freq,
+                    max_freq,
+                    last_change: self.last_freq_change,
+                    active_process: input.active_process,
+                    active_process_name: input.active_process_name,
+                    process_stable: input.process_stable,
+                };
+                self.policy.decide(&sample, self.curr_freq, &ctx)
+            }
+        };
+        let target_freq = decision.freq.clamp(min_freq, max_freq);
+
+        let intervals = &config.timing.intervals;
+        // MHz
+        let small_change = config.frequency_thresholds.finetune;
+        let significant_change = config.frequency_thresholds.adjust;
+        let curr_freq = self.curr_freq;
+        let mut change = None;
+
+        let adj_now = elapsed(self.last_adjustment, now) >= Duration::from_micros(intervals.adjust);
+        if adj_now || decision.immediate {
+            let hit_bounds =
+                target_freq != curr_freq && (target_freq == min_freq || target_freq == max_freq);
+            let big_change = curr_freq.abs_diff(target_freq) >= significant_change;
+            let finetune = elapsed(self.last_finetune, now)
+                >= Duration::from_micros(intervals.finetune)
+                && curr_freq.abs_diff(target_freq) >= small_change;
+            let forced = decision.immediate && curr_freq != target_freq;
+            if hit_bounds || big_change || finetune || forced {
+                let mut reasons = Vec::new();
+                if forced {
+                    reasons.extend(decision.reason);
+                }
+                if hit_bounds {
+                    if target_freq == min_freq {
+                        reasons.push("min limit reached");
+                    } else {
+                        reasons.push("max limit reached");
+                    }
+                }
+                if big_change {
+                    reasons.push("significant change");
+                }
+                if finetune {
+                    reasons.push("fine adjustment");
+                }
+                if reasons.is_empty() {
+                    reasons.extend(decision.reason);
+                }
+
+                change = Some(FreqChange {
+                    from: curr_freq,
+                    to: target_freq,
+                    reasons,
+                });
+                self.curr_freq = target_freq;
+                self.last_finetune = now;
+                self.last_freq_change = now;
+            }
+            self.last_adjustment = now;
+        }
+
+        Step {
+            load_percent,
+            decision,
+            target_freq,
+            change,
+        }
+    }
+}
+
+fn elapsed(since: Instant, now: Instant) -> Duration {
+    now.saturating_duration_since(since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+
+    fn input(config: &GovernorConfig, busy: bool, timestamp: Instant) -> SampleInput<'_> {
+        SampleInput {
+            busy,
+            timestamp,
+            config,
+            min_freq: 350,
+            max_freq: 2000,
+            pinned: None,
+            active_process: None,
+            active_process_name: None,
+            process_stable: false,
+        }
+    }
+
+    #[test]
+    fn test_changes_wait_for_thresholds() {
+        // 1 MHz/ms, échantillon de 2 ms, ajustement toutes les 20 ms, palier de 100 MHz
+        let config = GovernorConfig::parse("[timing]\nburst-samples = 0").unwrap();
+        let start = Instant::now();
+        let mut sampler = Sampler::new(Policy::Threshold.build(1000, &config), 10, 1000, start);
+
+        let mut changes = Vec::new();
+        for i in 1..=100 {
+            let now = start + Duration::from_millis(2 * i);
+            if let Some(change) = sampler.step(&input(&config, true, now)).change {
+                changes.push((i, change));
+            }
+        }
+        // +2 MHz par échantillon, vérifié tous les 10: publié tous les 100 MHz
+        let published: Vec<_> = changes.iter().map(|(i, c)| (*i, c.from, c.to)).collect();
+        assert_eq!(published, [(50, 1000, 1100), (100, 1100, 1200)]);
+        assert_eq!(changes[0].1.reasons, ["significant change"]);
+        assert_eq!(sampler.current_freq(), 1200);
+    }
+
+    #[test]
+    fn test_pin_is_immediate() {
+        let config = GovernorConfig::parse("").unwrap();
+        let start = Instant::now();
+        let mut sampler = Sampler::new(Policy::Threshold.build(350, &config), 10, 350, start);
+        let step = sampler.step(&SampleInput {
+            pinned: Some(1200),
+            ..input(&config, false, start + Duration::from_millis(1))
+        });
+        assert_eq!(step.decision.mode, "pinned");
+        // 1 ms après le démarrage: publié sans attendre l'intervalle d'ajustement
+        assert_eq!(
+            step.change.unwrap().reasons,
+            ["pinned from control socket", "significant change"]
+        );
+    }
+}
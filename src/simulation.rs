@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::config::GovernorConfig;
+use crate::policy::FrequencyPolicy;
+use crate::sampler::{SampleInput, Sampler};
+use crate::trace;
+
+/// Largeur des tranches de `Report::time_at_freq`, en MHz
+pub const FREQ_BUCKET_MHZ: u16 = 100;
+
+/// Source d'échantillons GRBM pour le simulateur
+pub trait LoadSource {
+    /// Prochain échantillon (GUI_ACTIVE), `freq` étant la fréquence appliquée
+    /// (MHz) pendant `interval`; None à la fin de la source
+    fn next_sample(&mut self, freq: u16, interval: Duration) -> Option<bool>;
+
+    /// Images rendues en retard, pour les sources qui en simulent
+    fn missed_frames(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Trace enregistrée, rejouée telle quelle quelle que soit la fréquence
+///
+/// Soit un fichier écrit par le gouverneur (`[trace]`, voir `crate::trace`),
+/// dont seul `busy` est repris, soit un caractère par échantillon: `1` =
+/// actif, `0` = inactif; les blancs sont ignorés, ainsi que les lignes
+/// commençant par `#`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trace {
+    samples: Vec<bool>,
+    pos: usize,
+}
+
+impl Trace {
+    pub fn new(samples: Vec<bool>) -> Self {
+        Self { samples, pos: 0 }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, IoError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, IoError> {
+        if trace::is_trace(content) {
+            let records = trace::parse_trace(content)?;
+            return Ok(Self::new(records.iter().map(|r| r.busy).collect()));
+        }
+        let mut samples = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            for c in line.chars().filter(|c| !c.is_whitespace()) {
+                samples.push(match c {
+                    '1' => true,
+                    '0' => false,
+                    _ => {
+                        return Err(IoError::new(
+                            ErrorKind::InvalidData,
+                            format!("trace line {}: unexpected '{c}'", i + 1),
+                        ));
+                    }
+                });
+            }
+        }
+        Ok(Self::new(samples))
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+impl LoadSource for Trace {
+    fn next_sample(&mut self, _freq: u16, _interval: Duration) -> Option<bool> {
+        let sample = self.samples.get(self.pos).copied()?;
+        self.pos += 1;
+        Some(sample)
+    }
+}
+
+/// Créneau: actif pendant `duty_percent` de chaque période, quelle que soit la fréquence
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquareWave {
+    period: Duration,
+    busy: Duration,
+    elapsed: Duration,
+}
+
+impl SquareWave {
+    pub fn new(period: Duration, duty_percent: f64) -> Self {
+        Self {
+            period,
+            busy: period.mul_f64(duty_percent.clamp(0.0, 100.0) / 100.0),
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl LoadSource for SquareWave {
+    fn next_sample(&mut self, _freq: u16, interval: Duration) -> Option<bool> {
+        if self.period.is_zero() {
+            return Some(!self.busy.is_zero());
+        }
+        let busy = self.elapsed < self.busy;
+        let elapsed = (self.elapsed + interval).as_nanos() % self.period.as_nanos();
+        self.elapsed = Duration::from_nanos(elapsed as u64);
+        Some(busy)
+    }
+}
+
+/// Rendu cadencé par la vsync, comme un jeu
+///
+/// Chaque image demande `work` millions de cycles: le GPU reste actif le temps
+/// de les exécuter à la fréquence appliquée, puis attend la vsync suivante.
+/// Une image pas terminée à la vsync est comptée en retard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramePacing {
+    frame: Duration,
+    /// Mcycles par image
+    work: f64,
+    /// Mcycles restant pour l'image en cours
+    left: f64,
+    elapsed: Duration,
+    missed: u64,
+}
+
+impl FramePacing {
+    pub fn new(fps: f64, work: f64) -> Self {
+        Self {
+            frame: Duration::from_secs_f64(1.0 / fps.max(1.0)),
+            work,
+            left: work,
+            elapsed: Duration::ZERO,
+            missed: 0,
+        }
+    }
+
+    /// Bureau inactif: le compositeur à 60 Hz, 0,5 Mcycles par image
+    pub fn idle_desktop() -> Self {
+        Self::new(60.0, 0.5)
+    }
+}
+
+impl LoadSource for FramePacing {
+    fn next_sample(&mut self, freq: u16, interval: Duration) -> Option<bool> {
+        if self.elapsed >= self.frame {
+            self.elapsed -= self.frame;
+            if self.left > 0.0 {
+                self.missed += 1;
+            } else {
+                self.left = self.work;
+            }
+        }
+        let busy = self.left > 0.0;
+        if busy {
+            // MHz * s = Mcycles
+            self.left -= f64::from(freq) * interval.as_secs_f64();
+        }
+        self.elapsed += interval;
+        Some(busy)
+    }
+
+    fn missed_frames(&self) -> Option<u64> {
+        Some(self.missed)
+    }
+}
+
+/// Résultat d'une simulation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub duration: Duration,
+    pub samples: u64,
+    /// Fréquences publiées, donc écrites dans `pp_od_clk_voltage`
+    pub writes: u64,
+    /// Pourcentage (0-100) d'échantillons actifs
+    pub average_load: f64,
+    /// Temps passé par tranche de `FREQ_BUCKET_MHZ` (clé: borne basse en MHz)
+    pub time_at_freq: BTreeMap<u16, Duration>,
+    /// Énergie dynamique estimée pendant l'activité: somme de f (GHz) · V² · t (s)
+    ///
+    /// Unités arbitraires, seulement pour comparer des configurations.
+    pub energy: f64,
+    pub missed_frames: Option<u64>,
+}
+
+/// Rejoue `source` à travers la boucle du gouverneur, à `timing.intervals.sample`
+///
+/// Le temps est virtuel: le résultat ne dépend que de la config, de la
+/// politique et de la source. La fréquence démarre au plus bas point sûr et
+/// chaque fréquence publiée est appliquée dès l'échantillon suivant.
+pub fn simulate(
+    config: &GovernorConfig,
+    policy: Box<dyn FrequencyPolicy>,
+    source: &mut dyn LoadSource,
+    duration: Duration,
+) -> Report {
+    let interval = Duration::from_micros(u64::from(config.timing.intervals.sample));
+    let (min_freq, max_freq) = (config.min_safe_freq(), config.max_safe_freq());
+    let curve = config.voltage_curve();
+    let start = Instant::now();
+    let mut sampler = Sampler::new(policy, config.timing.window_samples, min_freq, start);
+    let mut freq = min_freq;
+    let mut report = Report::default();
+    let mut busy_samples = 0u64;
+
+    while report.duration + interval <= duration {
+        let Some(busy) = source.next_sample(freq, interval) else {
+            break;
+        };
+        report.samples += 1;
+        report.duration += interval;
+        *report
+            .time_at_freq
+            .entry(freq / FREQ_BUCKET_MHZ * FREQ_BUCKET_MHZ)
+            .or_default() += interval;
+        if busy {
+            busy_samples += 1;
+            if let Some(mv) = curve.voltage(freq) {
+                let voltage = f64::from(mv) / 1000.0;
+                report.energy +=
+                    f64::from(freq) / 1000.0 * voltage * voltage * interval.as_secs_f64();
+            }
+        }
+
+        let step = sampler.step(&SampleInput {
+            busy,
+            timestamp: start + report.duration,
+            config,
+            min_freq,
+            max_freq,
+            pinned: None,
+            active_process: None,
+            active_process_name: None,
+            process_stable: false,
+        });
+        if let Some(change) = step.change {
+            report.writes += 1;
+            freq = change.to;
+        }
+    }
+    if report.samples > 0 {
+        report.average_load = busy_samples as f64 * 100.0 / report.samples as f64;
+    }
+    report.missed_frames = source.missed_frames();
+    report
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Simulated {:.1} s ({} samples)",
+            self.duration.as_secs_f64(),
+            self.samples
+        )?;
+        writeln!(f, "  Frequency writes: {}", self.writes)?;
+        writeln!(f, "  Average load: {:.1}%", self.average_load)?;
+        writeln!(
+            f,
+            "  Estimated energy: {:.2} (relative, f·V²·t)",
+            self.energy
+        )?;
+        if let Some(missed) = self.missed_frames {
+            writeln!(f, "  Missed frames: {missed}")?;
+        }
+        write!(f, "  Time at frequency:")?;
+        for (bucket, time) in &self.time_at_freq {
+            let share = time.as_secs_f64() * 100.0 / self.duration.as_secs_f64();
+            write!(
+                f,
+                "\n    {:4}-{:4} MHz: {:8.2} s ({:5.1}%)",
+                bucket,
+                bucket + FREQ_BUCKET_MHZ - 1,
+                time.as_secs_f64(),
+                share
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+
+    fn config() -> GovernorConfig {
+        GovernorConfig::parse("[timing]\nburst-samples = 0").unwrap()
+    }
+
+    #[test]
+    fn test_parse_trace() {
+        let trace = Trace::parse("# 2 ms\n1101\n 0 0\n").unwrap();
+        assert_eq!(trace.len(), 6);
+        assert_eq!(trace.samples, [true, true, false, true, false, false]);
+        let err = Trace::parse("10\n1x\n").unwrap_err();
+        assert_eq!(err.to_string(), "trace line 2: unexpected 'x'");
+
+        let recorded = format!(
+            "{} v1\n{}\n1,1,0.0,350,350,\n2,0,0.0,350,350,\n",
+            trace::TRACE_MAGIC,
+            trace::TRACE_COLUMNS
+        );
+        assert_eq!(Trace::parse(&recorded).unwrap().samples, [true, false]);
+    }
+
+    #[test]
+    fn test_square_wave() {
+        let mut wave = SquareWave::new(Duration::from_millis(10), 40.0);
+        let samples: Vec<_> = (0..10)
+            .map(|_| wave.next_sample(350, Duration::from_millis(2)).unwrap())
+            .collect();
+        assert_eq!(
+            samples,
+            [
+                true, true, false, false, false, true, true, false, false, false
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frame_pacing_follows_frequency() {
+        // 2 Mcycles par image à 100 fps: 2 ms à 1000 MHz, 10 ms (trop lent) à 200 MHz
+        let interval = Duration::from_millis(1);
+        let mut fast = FramePacing::new(100.0, 2.0);
+        let busy = (0..100)
+            .filter(|_| fast.next_sample(1000, interval).unwrap())
+            .count();
+        assert_eq!(busy, 20);
+        assert_eq!(fast.missed_frames(), Some(0));
+
+        let mut slow = FramePacing::new(100.0, 2.0);
+        for _ in 0..100 {
+            slow.next_sample(150, interval);
+        }
+        assert!(slow.missed_frames().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_full_trace_reaches_max() {
+        let config = config();
+        let mut trace = Trace::new(vec![true; 5000]);
+        let report = simulate(
+            &config,
+            Policy::Threshold.build(350, &config),
+            &mut trace,
+            Duration::MAX,
+        );
+        assert_eq!(report.samples, 5000);
+        assert_eq!(report.duration, Duration::from_secs(10));
+        assert_eq!(report.average_load, 100.0);
+        assert!(report.writes > 0);
+        assert!(report.time_at_freq.contains_key(&2000));
+        assert!(report.energy > 0.0);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let config = config();
+        let run = || {
+            simulate(
+                &config,
+                Policy::Threshold.build(350, &config),
+                &mut FramePacing::new(60.0, 12.0),
+                Duration::from_secs(5),
+            )
+        };
+        let report = run();
+        assert_eq!(report, run());
+        assert_eq!(report.duration, Duration::from_secs(5));
+        assert!(
+            report
+                .to_string()
+                .starts_with("Simulated 5.0 s (2500 samples)")
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::DEFAULT_SYSFS_ROOT;
+use crate::kernel::DEFAULT_PROC_ROOT;
+
+/// Racine devtmpfs par défaut
+pub const DEFAULT_DEV_ROOT: &str = "/dev";
+
+/// Racines de procfs, sysfs et devtmpfs (section `[paths]`)
+///
+/// Dans un conteneur, celles de l'hôte sont souvent montées ailleurs
+/// (`/host/proc`, `/host/sys`); les tests y placent une arborescence factice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemPaths {
+    pub proc: PathBuf,
+    pub sys: PathBuf,
+    pub dev: PathBuf,
+}
+
+impl Default for SystemPaths {
+    fn default() -> Self {
+        Self {
+            proc: PathBuf::from(DEFAULT_PROC_ROOT),
+            sys: PathBuf::from(DEFAULT_SYSFS_ROOT),
+            dev: PathBuf::from(DEFAULT_DEV_ROOT),
+        }
+    }
+}
+
+impl SystemPaths {
+    /// `proc`, `sys` et `dev` sous un même préfixe (ex: `/host`)
+    pub fn under(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            proc: root.join("proc"),
+            sys: root.join("sys"),
+            dev: root.join("dev"),
+        }
+    }
+
+    /// `/proc/<pid>`
+    pub fn process(&self, pid: u32) -> PathBuf {
+        self.proc.join(pid.to_string())
+    }
+
+    /// `/dev/dri`, où sont les nœuds `card*` et `renderD*`
+    pub fn dri(&self) -> PathBuf {
+        self.dev.join("dri")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_under() {
+        let paths = SystemPaths::under("/host");
+        assert_eq!(paths.proc, Path::new("/host/proc"));
+        assert_eq!(paths.process(42), Path::new("/host/proc/42"));
+        assert_eq!(paths.dri(), Path::new("/host/dev/dri"));
+        assert_eq!(SystemPaths::under("/"), SystemPaths::default());
+    }
+}
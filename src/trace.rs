@@ -0,0 +1,465 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Error as IoError, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Version du format, écrite dans l'en-tête de chaque fichier
+///
+/// À incrémenter à tout changement de colonnes; les lecteurs refusent les
+/// versions plus récentes que la leur.
+pub const TRACE_FORMAT_VERSION: u32 = 1;
+
+/// Début de la première ligne d'un fichier de trace, suivi de ` v<version>`
+pub const TRACE_MAGIC: &str = "# cyan-skillfish-governor trace";
+
+/// Deuxième ligne: les colonnes du format `TRACE_FORMAT_VERSION`
+pub const TRACE_COLUMNS: &str = "timestamp_us,busy,load,target_freq,freq,reasons";
+
+/// Chemin par défaut de `trace.path`
+pub const DEFAULT_TRACE_PATH: &str = "/var/log/cyan-skillfish-governor/trace.csv";
+
+/// Délai maximal avant qu'un échantillon enregistré n'atteigne le fichier
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Échantillons en attente d'écriture (~8 s à 2 ms) avant d'être abandonnés
+const QUEUE_SIZE: usize = 4096;
+
+/// Enregistrement des échantillons (section `[trace]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TraceConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// Mio: taille d'un fichier avant rotation
+    pub max_size: u64,
+    /// Anciens fichiers conservés (`trace.csv.1` …); 0 = le fichier est vidé
+    pub max_files: u8,
+}
+
+/// Un échantillon du gouverneur, une ligne du fichier
+///
+/// ```text
+/// # cyan-skillfish-governor trace v1
+/// timestamp_us,busy,load,target_freq,freq,reasons
+/// 1718000000000000,1,92.0,1100,1100,significant change
+/// 1718000000002000,0,91.0,1098,1100,
+/// ```
+///
+/// `timestamp_us` en µs depuis l'époque Unix, `busy` le bit GUI_ACTIVE (0/1),
+/// `load` la charge moyenne de la fenêtre (%), `target_freq` la fréquence
+/// voulue par la politique et `freq` la fréquence publiée (MHz). `reasons`
+/// (séparées par `;`) n'est rempli que sur un changement de fréquence, avec
+/// les raisons du log `[FREQ]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+    pub timestamp_us: u64,
+    pub busy: bool,
+    /// Pourcentage (0-100)
+    pub load_percent: f32,
+    /// MHz
+    pub target_freq: u16,
+    pub freq: u16,
+    pub reasons: Vec<String>,
+}
+
+impl TraceRecord {
+    fn write_to(&self, out: &mut impl Write) -> Result<(), IoError> {
+        writeln!(
+            out,
+            "{},{},{:.1},{},{},{}",
+            self.timestamp_us,
+            u8::from(self.busy),
+            self.load_percent,
+            self.target_freq,
+            self.freq,
+            self.reasons.join(";")
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(6, ',');
+        let mut next = || fields.next();
+        Some(Self {
+            timestamp_us: next()?.parse().ok()?,
+            busy: match next()? {
+                "1" => true,
+                "0" => false,
+                _ => return None,
+            },
+            load_percent: next()?.parse().ok()?,
+            target_freq: next()?.parse().ok()?,
+            freq: next()?.parse().ok()?,
+            reasons: next()?
+                .split(';')
+                .filter(|r| !r.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+}
+
+/// Lit un fichier de trace complet
+pub fn read_trace(path: impl AsRef<Path>) -> Result<Vec<TraceRecord>, IoError> {
+    parse_trace(&fs::read_to_string(path)?)
+}
+
+/// Lit le contenu d'un fichier de trace, en-tête compris
+pub fn parse_trace(content: &str) -> Result<Vec<TraceRecord>, IoError> {
+    let invalid = |msg: String| IoError::new(ErrorKind::InvalidData, msg);
+    let mut lines = content.lines();
+
+    let version = lines
+        .next()
+        .and_then(|l| l.strip_prefix(TRACE_MAGIC))
+        .and_then(|v| v.trim().strip_prefix('v'))
+        .ok_or_else(|| invalid("not a governor trace".to_string()))?;
+    let version: u32 = version
+        .parse()
+        .map_err(|_| invalid(format!("invalid trace version \"{version}\"")))?;
+    if version > TRACE_FORMAT_VERSION {
+        return Err(invalid(format!(
+            "trace version {version} is newer than the supported version {TRACE_FORMAT_VERSION}"
+        )));
+    }
+    if lines.next() != Some(TRACE_COLUMNS) {
+        return Err(invalid("unexpected trace columns".to_string()));
+    }
+
+    lines
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            TraceRecord::parse(line)
+                .ok_or_else(|| invalid(format!("trace line {}: invalid", i + 3)))
+        })
+        .collect()
+}
+
+/// `true` si `content` commence par l'en-tête d'un fichier de trace
+pub fn is_trace(content: &str) -> bool {
+    content.starts_with(TRACE_MAGIC)
+}
+
+/// Écrit les échantillons dans `trace.path`, avec rotation
+///
+/// Quand le fichier dépasserait `max-size`, il devient `<path>.1` (les plus
+/// anciens `<path>.2` …, au-delà de `max-files` ils sont supprimés) et un
+/// nouveau fichier commence par l'en-tête. Les écritures passent par un
+/// tampon vidé au moins toutes les secondes.
+///
+/// Les écritures sont synchrones; la boucle d'échantillonnage passe par
+/// `TraceRecorder`.
+pub struct TraceWriter {
+    path: PathBuf,
+    /// Octets
+    max_size: u64,
+    max_files: u8,
+    file: BufWriter<File>,
+    /// Octets dans le fichier courant
+    size: u64,
+    last_flush: Instant,
+}
+
+impl TraceWriter {
+    /// Ouvre le fichier de `config.path` et le continue s'il s'agit d'une
+    /// trace du même format; tout autre fichier est d'abord mis de côté
+    pub fn create(config: &TraceConfig) -> Result<Self, IoError> {
+        let path = PathBuf::from(&config.path);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let max_size = config.max_size.saturating_mul(1024 * 1024);
+        let (file, size) = match fs::read(&path) {
+            Ok(content) if content.starts_with(header().as_bytes()) => (
+                BufWriter::new(OpenOptions::new().append(true).open(&path)?),
+                content.len() as u64,
+            ),
+            Ok(content) if !content.is_empty() => {
+                rotate(&path, config.max_files)?;
+                (start_file(&path)?, header().len() as u64)
+            }
+            Ok(_) => (start_file(&path)?, header().len() as u64),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                (start_file(&path)?, header().len() as u64)
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            file,
+            size,
+            path,
+            max_size,
+            max_files: config.max_files,
+            last_flush: Instant::now(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, record: &TraceRecord) -> Result<(), IoError> {
+        let mut line = Vec::with_capacity(64);
+        record.write_to(&mut line)?;
+        if self.size + line.len() as u64 > self.max_size {
+            self.file.flush()?;
+            rotate(&self.path, self.max_files)?;
+            self.file = start_file(&self.path)?;
+            self.size = header().len() as u64;
+        }
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), IoError> {
+        self.last_flush = Instant::now();
+        self.file.flush()
+    }
+}
+
+/// Enregistre les échantillons depuis un thread dédié
+///
+/// `record` ne fait que placer l'échantillon dans une file bornée: l'écriture
+/// et la rotation se font sur le thread, hors de la boucle d'échantillonnage.
+/// Quand la file est pleine, l'échantillon est abandonné et compté dans
+/// `dropped`. `Drop` attend que la file soit écrite.
+pub struct TraceRecorder {
+    path: PathBuf,
+    queue: Option<SyncSender<TraceRecord>>,
+    writer: Option<JoinHandle<Result<(), IoError>>>,
+    dropped: u64,
+}
+
+impl TraceRecorder {
+    /// Ouvre le fichier (voir `TraceWriter::create`) et démarre le thread
+    pub fn start(config: &TraceConfig) -> Result<Self, IoError> {
+        let mut writer = TraceWriter::create(config)?;
+        let path = writer.path().to_path_buf();
+        let (queue, records) = mpsc::sync_channel::<TraceRecord>(QUEUE_SIZE);
+        let writer = std::thread::Builder::new()
+            .name("trace".into())
+            .spawn(move || {
+                loop {
+                    match records.recv_timeout(FLUSH_INTERVAL) {
+                        Ok(record) => writer.record(&record)?,
+                        Err(RecvTimeoutError::Timeout) => writer.flush()?,
+                        Err(RecvTimeoutError::Disconnected) => return writer.flush(),
+                    }
+                }
+            })?;
+        Ok(Self {
+            path,
+            queue: Some(queue),
+            writer: Some(writer),
+            dropped: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Échantillons abandonnés faute de place dans la file
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Met `record` en file; renvoie l'erreur du thread s'il s'est arrêté
+    pub fn record(&mut self, record: TraceRecord) -> Result<(), IoError> {
+        let Some(queue) = &self.queue else {
+            return Err(IoError::other("trace writer stopped"));
+        };
+        match queue.try_send(record) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.queue = None;
+                self.finish()
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), IoError> {
+        match self.writer.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(IoError::other("trace writer panicked")),
+            None => Err(IoError::other("trace writer stopped")),
+        }
+    }
+}
+
+impl Drop for TraceRecorder {
+    fn drop(&mut self) {
+        self.queue = None;
+        if self.writer.is_some() {
+            let _ = self.finish();
+        }
+    }
+}
+
+fn header() -> String {
+    format!("{TRACE_MAGIC} v{TRACE_FORMAT_VERSION}\n{TRACE_COLUMNS}\n")
+}
+
+fn start_file(path: &Path) -> Result<BufWriter<File>, IoError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(header().as_bytes())?;
+    Ok(file)
+}
+
+fn rotated(path: &Path, n: u8) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+/// `<path>` → `<path>.1` → … → `<path>.<max_files>`, le plus ancien est supprimé
+fn rotate(path: &Path, max_files: u8) -> Result<(), IoError> {
+    if max_files == 0 {
+        return fs::remove_file(path);
+    }
+    match fs::remove_file(rotated(path, max_files)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    for n in (1..max_files).rev() {
+        match fs::rename(rotated(path, n), rotated(path, n + 1)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    fs::rename(path, rotated(path, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_system::TempDir;
+
+    fn record(n: u64, reasons: &[&str]) -> TraceRecord {
+        TraceRecord {
+            timestamp_us: 1_000_000 + n * 2000,
+            busy: n % 2 == 0,
+            load_percent: 42.5,
+            target_freq: 1100,
+            freq: 1000,
+            reasons: reasons.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    fn config(dir: &Path, max_files: u8) -> TraceConfig {
+        TraceConfig {
+            enabled: true,
+            path: dir.join("trace.csv").to_string_lossy().into_owned(),
+            max_size: 1,
+            max_files,
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = TempDir::new("trace-roundtrip");
+        let records = [
+            record(0, &["significant change", "max limit reached"]),
+            record(1, &[]),
+        ];
+        {
+            let mut writer = TraceWriter::create(&config(dir.path(), 2)).unwrap();
+            for r in &records {
+                writer.record(r).unwrap();
+            }
+        }
+        let content = fs::read_to_string(dir.join("trace.csv")).unwrap();
+        assert!(is_trace(&content));
+        assert_eq!(
+            content.lines().nth(2),
+            Some("1000000,1,42.5,1100,1000,significant change;max limit reached")
+        );
+        assert_eq!(parse_trace(&content).unwrap(), records);
+    }
+
+    #[test]
+    fn test_versions() {
+        let newer = format!(
+            "{TRACE_MAGIC} v{}\n{TRACE_COLUMNS}\n",
+            TRACE_FORMAT_VERSION + 1
+        );
+        assert!(
+            parse_trace(&newer)
+                .unwrap_err()
+                .to_string()
+                .contains("newer")
+        );
+        assert!(parse_trace("timestamp_us,busy\n").is_err());
+        let bad = format!("{}1,2,3\n", header());
+        assert_eq!(
+            parse_trace(&bad).unwrap_err().to_string(),
+            "trace line 3: invalid"
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = TempDir::new("trace-rotation");
+        let config = config(dir.path(), 2);
+        // Un fichier qui n'est pas une trace est conservé en .1 au démarrage
+        fs::write(dir.join("trace.csv"), "old").unwrap();
+        let mut writer = TraceWriter::create(&config).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("trace.csv.1")).unwrap(), "old");
+
+        // ~27 octets par ligne, 1 Mio par fichier: 4 fichiers écrits
+        for n in 0..150_000 {
+            writer.record(&record(n, &[])).unwrap();
+        }
+        drop(writer);
+        assert!(!dir.join("trace.csv.3").exists());
+        for name in ["trace.csv", "trace.csv.1", "trace.csv.2"] {
+            let path = dir.join(name);
+            assert!(fs::metadata(&path).unwrap().len() <= 1024 * 1024);
+            assert!(!read_trace(&path).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_restart_appends() {
+        let dir = TempDir::new("trace-restart");
+        let config = config(dir.path(), 2);
+        for n in 0..2 {
+            let mut recorder = TraceRecorder::start(&config).unwrap();
+            recorder.record(record(n, &[])).unwrap();
+        }
+        // Pas de rotation tant que la taille le permet
+        assert!(!dir.join("trace.csv.1").exists());
+        let records = read_trace(dir.join("trace.csv")).unwrap();
+        assert_eq!(records, [record(0, &[]), record(1, &[])]);
+    }
+
+    #[test]
+    fn test_recorder_reports_write_errors() {
+        let dir = TempDir::new("trace-recorder-error");
+        let mut recorder = TraceRecorder::start(&config(dir.path(), 0)).unwrap();
+        // La rotation avec max-files = 0 supprime le fichier: sans lui, elle échoue
+        fs::remove_file(dir.join("trace.csv")).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut n = 0;
+        let error = loop {
+            if let Err(e) = recorder.record(record(n, &[])) {
+                break e;
+            }
+            assert!(Instant::now() < deadline, "no write error reported");
+            n += 1;
+        };
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+}
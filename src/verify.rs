@@ -0,0 +1,181 @@
+use std::io::Error as IoError;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gpu_backend::GpuBackend;
+
+/// ms: attente maximale avant une relecture, quel que soit le doublement
+pub const MAX_BACKOFF_MS: u64 = 1_000;
+
+/// ms: attente totale maximale pour une fréquence, toutes tentatives comprises
+///
+/// Les relectures ont lieu dans le thread qui écrit les fréquences: au-delà,
+/// le gouverneur ne pourrait plus suivre la charge.
+pub const MAX_RETRY_MS: u64 = 5_000;
+
+/// Vérification des fréquences appliquées (section `[verify]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifyConfig {
+    pub enabled: bool,
+    /// MHz: écart toléré entre la fréquence demandée et celle de `pp_dpm_sclk`
+    pub tolerance: u16,
+    /// Nouvelles tentatives après un écart
+    pub retries: u8,
+    /// ms: attente avant la première relecture, doublée à chaque tentative
+    /// (sans dépasser `MAX_BACKOFF_MS`)
+    pub backoff: u64,
+}
+
+impl VerifyConfig {
+    /// Attente avant la relecture de la tentative `attempt` (à partir de 0)
+    pub fn delay(&self, attempt: u8) -> Duration {
+        let ms = self
+            .backoff
+            .saturating_mul(1u64.checked_shl(u32::from(attempt)).unwrap_or(u64::MAX));
+        Duration::from_millis(ms.min(MAX_BACKOFF_MS))
+    }
+
+    /// Attente cumulée quand aucune tentative n'aboutit
+    pub fn total_delay(&self) -> Duration {
+        (0..=self.retries).map(|attempt| self.delay(attempt)).sum()
+    }
+}
+
+/// Écarts constatés depuis le démarrage
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerifyStats {
+    /// Fréquences jamais constatées malgré les nouvelles tentatives
+    pub mismatches: u64,
+    pub last_error: Option<String>,
+}
+
+/// Applique un point puis relit la fréquence moteur jusqu'à ce qu'elle corresponde
+///
+/// Sur un noyau non patché, `vc` peut être accepté sans effet: sans relecture,
+/// rien ne le signale.
+#[derive(Debug, Default)]
+pub struct ClockVerifier {
+    stats: VerifyStats,
+}
+
+impl ClockVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stats(&self) -> &VerifyStats {
+        &self.stats
+    }
+
+    /// Applique `freq` / `voltage`; `Ok(false)` si la fréquence n'a jamais été constatée
+    ///
+    /// Les erreurs d'écriture sont renvoyées telles quelles; les écarts sont
+    /// affichés et retentés `retries` fois avec une attente croissante, dans la
+    /// limite de `MAX_RETRY_MS`. Un échec final compte pour un écart.
+    pub fn apply<B: GpuBackend + ?Sized>(
+        &mut self,
+        backend: &B,
+        config: &VerifyConfig,
+        freq: u16,
+        voltage: u16,
+    ) -> Result<bool, IoError> {
+        backend.set_frequency(freq, voltage)?;
+        if !config.enabled {
+            return Ok(true);
+        }
+
+        let mut waited = Duration::ZERO;
+        for attempt in 0..=config.retries {
+            let delay = config.delay(attempt);
+            waited += delay;
+            if waited > Duration::from_millis(MAX_RETRY_MS) {
+                break;
+            }
+            std::thread::sleep(delay);
+            let error = match backend.read_sclk() {
+                Ok(sclk) if sclk.abs_diff(freq) <= config.tolerance => return Ok(true),
+                Ok(sclk) => format!("requested {freq} MHz, kernel reports {sclk} MHz"),
+                Err(e) => format!("cannot read back the engine clock: {e}"),
+            };
+            eprintln!(
+                "⚠️  Vérification {}/{}: {error}",
+                attempt + 1,
+                u16::from(config.retries) + 1
+            );
+            self.stats.last_error = Some(error);
+            if attempt < config.retries {
+                backend.set_frequency(freq, voltage)?;
+            }
+        }
+        self.stats.mismatches += 1;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu_backend::MockBackend;
+
+    fn config() -> VerifyConfig {
+        VerifyConfig {
+            enabled: true,
+            tolerance: 25,
+            retries: 2,
+            backoff: 0,
+        }
+    }
+
+    #[test]
+    fn test_applied_clock_matches() {
+        let mock = MockBackend::new(350, 2000);
+        let mut verifier = ClockVerifier::new();
+        assert!(verifier.apply(&mock, &config(), 1200, 900).unwrap());
+        assert_eq!(verifier.stats(), &VerifyStats::default());
+        assert_eq!(mock.committed(), vec![(1200, 900)]);
+    }
+
+    #[test]
+    fn test_ignored_write_is_retried_and_counted() {
+        let mock = MockBackend::new(350, 2000).ignoring_writes();
+        let mut verifier = ClockVerifier::new();
+        assert!(!verifier.apply(&mock, &config(), 1200, 900).unwrap());
+        assert_eq!(verifier.stats().mismatches, 1);
+        assert_eq!(
+            verifier.stats().last_error.as_deref(),
+            Some("requested 1200 MHz, kernel reports 350 MHz")
+        );
+        assert_eq!(mock.committed().len(), 3);
+    }
+
+    #[test]
+    fn test_disabled_skips_read_back() {
+        let mock = MockBackend::new(350, 2000).ignoring_writes();
+        let mut verifier = ClockVerifier::new();
+        let config = VerifyConfig {
+            enabled: false,
+            ..config()
+        };
+        assert!(verifier.apply(&mock, &config, 1200, 900).unwrap());
+        assert_eq!(verifier.stats().mismatches, 0);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let config = VerifyConfig {
+            retries: 10,
+            backoff: 10_000,
+            ..config()
+        };
+        assert_eq!(config.delay(0), Duration::from_millis(MAX_BACKOFF_MS));
+        assert_eq!(config.delay(10), Duration::from_millis(MAX_BACKOFF_MS));
+        let config = VerifyConfig {
+            retries: 3,
+            backoff: 20,
+            ..config
+        };
+        assert_eq!(config.delay(3), Duration::from_millis(160));
+        assert_eq!(config.total_delay(), Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Calcul de la tension entre deux points sûrs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    /// Tension du point sûr suivant (conservateur)
+    #[default]
+    Step,
+    /// Droite entre les deux points sûrs encadrants, arrondie au mV supérieur
+    Linear,
+}
+
+impl Interpolation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Step => "step",
+            Self::Linear => "linear",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "step" => Some(Self::Step),
+            "linear" => Some(Self::Linear),
+            _ => None,
+        }
+    }
+}
+
+/// Courbe fréquence (MHz) → tension (mV) construite à partir des `safe-points`
+///
+/// Sous le premier point, sa tension est utilisée; au-delà du dernier, aucune
+/// tension n'est sûre.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoltageCurve {
+    points: BTreeMap<u16, u16>,
+    interpolation: Interpolation,
+}
+
+impl VoltageCurve {
+    /// `points` ne doit pas être vide (garanti par la validation de la config)
+    pub fn new(points: BTreeMap<u16, u16>, interpolation: Interpolation) -> Self {
+        assert!(
+            !points.is_empty(),
+            "a voltage curve needs at least one point"
+        );
+        Self {
+            points,
+            interpolation,
+        }
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Tension pour `freq`, None au-delà du dernier point sûr
+    pub fn voltage(&self, freq: u16) -> Option<u16> {
+        let (&upper_freq, &upper_voltage) = self.points.range(freq..).next()?;
+        let Some((&lower_freq, &lower_voltage)) = self.points.range(..freq).next_back() else {
+            return Some(upper_voltage);
+        };
+        match self.interpolation {
+            Interpolation::Step => Some(upper_voltage),
+            Interpolation::Linear => {
+                let span = u32::from(upper_freq - lower_freq);
+                let offset = u32::from(freq - lower_freq);
+                // Les points sont triés par tension croissante (voir la config)
+                let rise = u32::from(upper_voltage.saturating_sub(lower_voltage));
+                let extra = (offset * rise).div_ceil(span);
+                Some(lower_voltage + extra as u16)
+            }
+        }
+    }
+
+    /// Empreinte des points sûrs et de l'interpolation (FNV-1a 64 bits, en hexadécimal)
+    ///
+    /// Identique d'une machine à l'autre pour une même courbe: des profils
+    /// appris sous une autre empreinte l'ont été avec d'autres tensions.
+    pub fn fingerprint(&self) -> String {
+        let mut canonical = self.interpolation.name().to_string();
+        for (freq, voltage) in &self.points {
+            canonical.push_str(&format!(" {freq}@{voltage}"));
+        }
+        let hash = canonical.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+        format!("{hash:016x}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn curve(interpolation: Interpolation) -> VoltageCurve {
+        VoltageCurve::new(
+            BTreeMap::from([(350, 700), (1000, 850), (2000, 1000)]),
+            interpolation,
+        )
+    }
+
+    #[test]
+    fn test_step() {
+        let curve = curve(Interpolation::Step);
+        assert_eq!(curve.voltage(200), Some(700));
+        assert_eq!(curve.voltage(350), Some(700));
+        assert_eq!(curve.voltage(351), Some(850));
+        assert_eq!(curve.voltage(1001), Some(1000));
+        assert_eq!(curve.voltage(2001), None);
+    }
+
+    #[test]
+    fn test_linear() {
+        let curve = curve(Interpolation::Linear);
+        assert_eq!(curve.voltage(200), Some(700));
+        assert_eq!(curve.voltage(675), Some(775));
+        assert_eq!(curve.voltage(1000), Some(850));
+        // 850 + 150 * 1 / 1000 arrondi au-dessus
+        assert_eq!(curve.voltage(1001), Some(851));
+        assert_eq!(curve.voltage(2000), Some(1000));
+        assert_eq!(curve.voltage(2001), None);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let step = curve(Interpolation::Step);
+        assert_eq!(step.fingerprint(), curve(Interpolation::Step).fingerprint());
+        assert_ne!(
+            step.fingerprint(),
+            curve(Interpolation::Linear).fingerprint()
+        );
+        let other = VoltageCurve::new(
+            BTreeMap::from([(350, 700), (1000, 855), (2000, 1000)]),
+            Interpolation::Step,
+        );
+        assert_ne!(step.fingerprint(), other.fingerprint());
+        assert_eq!(step.fingerprint().len(), 16);
+    }
+
+    /// Points sûrs valides: fréquences et tensions croissantes
+    fn safe_points() -> impl Strategy<Value = BTreeMap<u16, u16>> {
+        prop::collection::btree_map(1u16..4000, 0u16..200, 1..8).prop_map(|steps| {
+            let mut voltage = 600;
+            steps
+                .into_iter()
+                .map(|(freq, rise)| {
+                    voltage += rise;
+                    (freq, voltage)
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_never_below_lower_point(points in safe_points(), freq in 0u16..4500) {
+            for interpolation in [Interpolation::Step, Interpolation::Linear] {
+                let curve = VoltageCurve::new(points.clone(), interpolation);
+                let max_freq = *points.last_key_value().unwrap().0;
+                match curve.voltage(freq) {
+                    None => prop_assert!(freq > max_freq),
+                    Some(voltage) => {
+                        if let Some((_, &lower)) = points.range(..=freq).next_back() {
+                            prop_assert!(voltage >= lower);
+                        }
+                        let (_, &upper) = points.range(freq..).next().unwrap();
+                        prop_assert!(voltage <= upper);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn prop_linear_never_above_step(points in safe_points(), freq in 0u16..4500) {
+            let step = VoltageCurve::new(points.clone(), Interpolation::Step);
+            let linear = VoltageCurve::new(points, Interpolation::Linear);
+            prop_assert!(linear.voltage(freq) <= step.voltage(freq));
+        }
+    }
+}
@@ -26,4 +26,4 @@ echo "  sudo systemctl enable cyan-skillfish-governor.service"
 echo "  sudo systemctl start cyan-skillfish-governor.service"
 echo ""
 echo "Note: La base de données des profils est conservée dans:"
-echo "      ~/.cache/cyan-skillfish-governor/process_profiles.json"
+echo "      /var/lib/cyan-skillfish-governor/process_profiles.json"